@@ -0,0 +1,98 @@
+use crate::fatx::time::FatxTimestamp;
+use crate::fatx::MAX_NAME_LENGTH;
+use alloc::string::String;
+use bitflags::bitflags;
+
+/// Size of an on-disk directory entry.
+pub const DIRENT_SIZE: usize = 64;
+
+/// Name length marking a deleted entry.
+pub(crate) const DIRENT_DELETED: u8 = 0xE5;
+
+/// Name lengths marking the end of a directory. Formatted directory clusters are
+/// filled with 0xFF, but some tools zero them instead.
+pub(crate) const DIRENT_END: u8 = 0xFF;
+pub(crate) const DIRENT_END_ZERO: u8 = 0x00;
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct FatxAttributes: u8 {
+        const ReadOnly = 0x01;
+        const Hidden = 0x02;
+        const System = 0x04;
+        const Directory = 0x10;
+        const Archive = 0x20;
+    }
+}
+
+/// A file or directory, as stored in its parent directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FatxDirEntry {
+    pub name: String,
+    pub attributes: FatxAttributes,
+    /// First cluster of the data, or 0 for empty files.
+    pub first_cluster: u32,
+    /// Size in bytes. Always 0 for directories.
+    pub size: u32,
+    pub creation_time: FatxTimestamp,
+    pub last_write_time: FatxTimestamp,
+    pub last_access_time: FatxTimestamp,
+}
+
+/// Raw state of a directory slot.
+pub(crate) enum Slot {
+    Entry(FatxDirEntry),
+    Deleted,
+    End,
+}
+
+impl FatxDirEntry {
+    pub fn is_dir(&self) -> bool {
+        self.attributes.contains(FatxAttributes::Directory)
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.is_dir()
+    }
+
+    /// Parses a 64 byte directory slot. Returns None if the name length is invalid.
+    pub(crate) fn parse(bytes: &[u8]) -> Option<Slot> {
+        let name_length = bytes[0];
+
+        match name_length {
+            DIRENT_END | DIRENT_END_ZERO => return Some(Slot::End),
+            DIRENT_DELETED => return Some(Slot::Deleted),
+            length if length as usize > MAX_NAME_LENGTH => return None,
+            _ => {}
+        }
+
+        let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        Some(Slot::Entry(FatxDirEntry {
+            name: String::from_utf8_lossy(&bytes[2..2 + name_length as usize]).into(),
+            attributes: FatxAttributes::from_bits_retain(bytes[1]),
+            first_cluster: u32_at(0x2C),
+            size: u32_at(0x30),
+            creation_time: FatxTimestamp::from_raw(u32_at(0x34)),
+            last_write_time: FatxTimestamp::from_raw(u32_at(0x38)),
+            last_access_time: FatxTimestamp::from_raw(u32_at(0x3C)),
+        }))
+    }
+
+    /// Serializes this entry to a directory slot. The name must already be valid.
+    pub(crate) fn to_bytes(&self) -> [u8; DIRENT_SIZE] {
+        let mut bytes = [0xFFu8; DIRENT_SIZE];
+        let name = self.name.as_bytes();
+
+        bytes[0] = name.len() as u8;
+        bytes[1] = self.attributes.bits();
+        bytes[2..2 + name.len()].copy_from_slice(name);
+        bytes[0x2C..0x30].copy_from_slice(&self.first_cluster.to_le_bytes());
+        bytes[0x30..0x34].copy_from_slice(&self.size.to_le_bytes());
+        bytes[0x34..0x38].copy_from_slice(&self.creation_time.into_raw().to_le_bytes());
+        bytes[0x38..0x3C].copy_from_slice(&self.last_write_time.into_raw().to_le_bytes());
+        bytes[0x3C..0x40].copy_from_slice(&self.last_access_time.into_raw().to_le_bytes());
+
+        bytes
+    }
+}
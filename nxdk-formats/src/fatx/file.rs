@@ -0,0 +1,215 @@
+use crate::fatx::dirent::FatxDirEntry;
+use crate::fatx::volume::FatxVolume;
+use crate::fatx::FatxError;
+use alloc::vec;
+use embedded_io::{ErrorType, Read, Seek, SeekFrom, Write};
+
+/// An open file on a `FatxVolume`.
+///
+/// The directory entry is updated on every write that changes the file size or
+/// its clusters, so dropping the file never loses anything.
+#[derive(Debug)]
+pub struct FatxFile<'a, D> {
+    volume: &'a mut FatxVolume<D>,
+    entry: FatxDirEntry,
+    entry_offset: u64,
+    position: u64,
+    /// Index and number of the last cluster used, so sequential access doesn't
+    /// walk the chain from the start every time.
+    cursor: Option<(u64, u32)>,
+}
+
+impl<'a, D: Read + Write + Seek> FatxFile<'a, D> {
+    pub(crate) fn new(volume: &'a mut FatxVolume<D>, entry: FatxDirEntry, entry_offset: u64) -> Self {
+        Self {
+            volume,
+            entry,
+            entry_offset,
+            position: 0,
+            cursor: None,
+        }
+    }
+
+    /// Directory entry of this file, as of the last write.
+    pub fn entry(&self) -> &FatxDirEntry {
+        &self.entry
+    }
+
+    pub fn len(&self) -> u64 {
+        self.entry.size as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entry.size == 0
+    }
+
+    /// Truncates or extends the file. Extended space is filled with zeros.
+    /// The position is left as it is.
+    pub fn set_len(&mut self, len: u64) -> Result<(), FatxError<D::Error>> {
+        if len > u32::MAX as u64 {
+            return Err(FatxError::FileTooLarge);
+        }
+
+        let size = self.len();
+
+        if len > size {
+            let position = self.position;
+            self.position = size;
+
+            let result = self.write_zeros(len - size);
+            self.position = position;
+
+            return result;
+        }
+
+        if len == size {
+            return Ok(());
+        }
+
+        if len == 0 {
+            self.volume.free_chain(self.entry.first_cluster)?;
+            self.entry.first_cluster = 0;
+        } else {
+            let last = (len - 1) / self.volume.cluster_size();
+            let cluster = self.cluster_at(last, false)?.ok_or(FatxError::Corrupt("file is shorter than its size"))?;
+            self.volume.truncate_chain(cluster)?;
+        }
+
+        self.cursor = None;
+        self.entry.size = len as u32;
+        self.entry.last_write_time = self.volume.now();
+
+        self.volume.write_entry(self.entry_offset, &self.entry)
+    }
+
+    /// Number of the cluster at `index` in the chain. With `allocate`, the chain
+    /// is extended as needed, otherwise None is returned past its end.
+    fn cluster_at(&mut self, index: u64, allocate: bool) -> Result<Option<u32>, FatxError<D::Error>> {
+        if self.entry.first_cluster == 0 {
+            if !allocate {
+                return Ok(None);
+            }
+
+            self.entry.first_cluster = self.volume.allocate_cluster(None)?;
+            self.volume.write_entry(self.entry_offset, &self.entry)?;
+        }
+
+        let (mut current_index, mut cluster) = match self.cursor {
+            Some((cursor_index, cursor_cluster)) if cursor_index <= index => (cursor_index, cursor_cluster),
+            _ => (0, self.entry.first_cluster),
+        };
+
+        while current_index < index {
+            cluster = match self.volume.next_cluster(cluster)? {
+                Some(next) => next,
+                None if allocate => self.volume.allocate_cluster(Some(cluster))?,
+                None => return Ok(None),
+            };
+
+            current_index += 1;
+        }
+
+        self.cursor = Some((index, cluster));
+
+        Ok(Some(cluster))
+    }
+
+    fn write_zeros(&mut self, mut len: u64) -> Result<(), FatxError<D::Error>> {
+        let zeros = vec![0u8; self.volume.cluster_size() as usize];
+
+        while len > 0 {
+            let chunk = len.min(zeros.len() as u64) as usize;
+            self.write_all(&zeros[..chunk])?;
+            len -= chunk as u64;
+        }
+
+        Ok(())
+    }
+}
+
+impl<D: Read + Write + Seek> ErrorType for FatxFile<'_, D> {
+    type Error = FatxError<D::Error>;
+}
+
+impl<D: Read + Write + Seek> Read for FatxFile<'_, D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let size = self.len();
+
+        if buf.is_empty() || self.position >= size {
+            return Ok(0);
+        }
+
+        let cluster_size = self.volume.cluster_size();
+        let within = self.position % cluster_size;
+        let len = (buf.len() as u64).min(cluster_size - within).min(size - self.position) as usize;
+
+        let cluster = self
+            .cluster_at(self.position / cluster_size, false)?
+            .ok_or(FatxError::Corrupt("file is shorter than its size"))?;
+
+        let offset = self.volume.cluster_offset(cluster) + within;
+        self.volume.read_at(offset, &mut buf[..len])?;
+        self.position += len as u64;
+
+        Ok(len)
+    }
+}
+
+impl<D: Read + Write + Seek> Write for FatxFile<'_, D> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.position >= u32::MAX as u64 {
+            return Err(FatxError::FileTooLarge);
+        }
+
+        // Writing past the end leaves a gap, which must read back as zeros
+        if self.position > self.len() {
+            let target = self.position;
+            let gap = target - self.len();
+
+            self.position = self.len();
+            self.write_zeros(gap)?;
+        }
+
+        let cluster_size = self.volume.cluster_size();
+        let within = self.position % cluster_size;
+        let len = (buf.len() as u64)
+            .min(cluster_size - within)
+            .min(u32::MAX as u64 - self.position) as usize;
+
+        let cluster = self
+            .cluster_at(self.position / cluster_size, true)?
+            .ok_or(FatxError::Corrupt("cluster chain ends early"))?;
+
+        let offset = self.volume.cluster_offset(cluster) + within;
+        self.volume.write_at(offset, &buf[..len])?;
+        self.position += len as u64;
+
+        self.entry.size = self.entry.size.max(self.position as u32);
+        self.entry.last_write_time = self.volume.now();
+        self.volume.write_entry(self.entry_offset, &self.entry)?;
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.volume.flush()
+    }
+}
+
+impl<D: Read + Write + Seek> Seek for FatxFile<'_, D> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Self::Error> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        self.position = position.ok_or(FatxError::InvalidSeek)?;
+
+        Ok(self.position)
+    }
+}
@@ -0,0 +1,125 @@
+//! FATX16 and FATX32 filesystem driver, over any `embedded_io` block device.
+//!
+//! FATX is the FAT variant used by the Xbox hard drive and memory units. It
+//! drops the BIOS parameter block for a 4KiB superblock, has no short names,
+//! and allows names up to 42 characters.
+//!
+//! ```ignore
+//! let mut volume = FatxVolume::open(image, E_PARTITION_OFFSET, E_PARTITION_SIZE)?;
+//!
+//! for entry in volume.read_dir("UDATA")? {
+//!     println!("{} ({} bytes)", entry.name, entry.size);
+//! }
+//! ```
+
+pub mod dirent;
+pub mod file;
+pub mod time;
+pub mod volume;
+
+pub use dirent::{FatxAttributes, FatxDirEntry};
+pub use file::FatxFile;
+pub use time::FatxTimestamp;
+pub use volume::{FatType, FatxVolume, Geometry, Superblock};
+
+use core::error::Error;
+use core::fmt::{Debug, Display, Formatter};
+use embedded_io::{ErrorKind, ReadExactError};
+
+/// Longest file or directory name FATX can store.
+pub const MAX_NAME_LENGTH: usize = 42;
+
+/// Characters FATX doesn't allow in file or directory names.
+pub const FORBIDDEN_CHARACTERS: &[char] = &['"', '*', '/', ':', '<', '>', '?', '\\', '|'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatxError<E> {
+    /// Error from the underlying device.
+    Io(E),
+    /// The device ended before the volume did.
+    UnexpectedEof,
+    /// No FATX superblock was found, or its values make no sense.
+    InvalidSuperblock,
+    /// The volume structures are inconsistent, such as a looping cluster chain.
+    Corrupt(&'static str),
+    NotFound,
+    AlreadyExists,
+    NotADirectory,
+    IsADirectory,
+    DirectoryNotEmpty,
+    /// The name is empty, too long, or has forbidden characters.
+    InvalidName,
+    /// There are no free clusters left.
+    NoSpace,
+    /// FATX file sizes are 32 bits.
+    FileTooLarge,
+    /// Seeking before the start of a file.
+    InvalidSeek,
+}
+
+impl<E> From<E> for FatxError<E> {
+    fn from(value: E) -> Self {
+        FatxError::Io(value)
+    }
+}
+
+impl<E> From<ReadExactError<E>> for FatxError<E> {
+    fn from(value: ReadExactError<E>) -> Self {
+        match value {
+            ReadExactError::UnexpectedEof => FatxError::UnexpectedEof,
+            ReadExactError::Other(error) => FatxError::Io(error),
+        }
+    }
+}
+
+impl<E: Debug> Display for FatxError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FatxError::Io(error) => write!(f, "IO error: {:?}", error),
+            FatxError::UnexpectedEof => write!(f, "Unexpected end of device"),
+            FatxError::InvalidSuperblock => write!(f, "Invalid FATX superblock"),
+            FatxError::Corrupt(reason) => write!(f, "Corrupt volume: {}", reason),
+            FatxError::NotFound => write!(f, "File not found"),
+            FatxError::AlreadyExists => write!(f, "File already exists"),
+            FatxError::NotADirectory => write!(f, "Not a directory"),
+            FatxError::IsADirectory => write!(f, "Is a directory"),
+            FatxError::DirectoryNotEmpty => write!(f, "Directory not empty"),
+            FatxError::InvalidName => write!(f, "Invalid FATX name"),
+            FatxError::NoSpace => write!(f, "No free space left on volume"),
+            FatxError::FileTooLarge => write!(f, "File too large for FATX"),
+            FatxError::InvalidSeek => write!(f, "Invalid seek"),
+        }
+    }
+}
+
+impl<E: Debug> Error for FatxError<E> {}
+
+impl<E: embedded_io::Error> embedded_io::Error for FatxError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            FatxError::Io(error) => error.kind(),
+            FatxError::NotFound => ErrorKind::NotFound,
+            FatxError::AlreadyExists => ErrorKind::AlreadyExists,
+            FatxError::NoSpace => ErrorKind::OutOfMemory,
+            FatxError::InvalidName | FatxError::FileTooLarge | FatxError::InvalidSeek => ErrorKind::InvalidInput,
+            FatxError::UnexpectedEof | FatxError::InvalidSuperblock | FatxError::Corrupt(_) => {
+                ErrorKind::InvalidData
+            }
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// Checks a single file or directory name against the FATX rules.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name != "."
+        && name != ".."
+        && name.chars().all(|c| c.is_ascii() && !c.is_ascii_control() && !FORBIDDEN_CHARACTERS.contains(&c))
+}
+
+/// Splits a volume relative path into its names. Both `\` and `/` are separators.
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['\\', '/']).filter(|name| !name.is_empty() && *name != ".")
+}
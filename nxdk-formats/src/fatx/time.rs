@@ -0,0 +1,162 @@
+use core::fmt::{Display, Formatter};
+
+/// Seconds between the Unix epoch and the FATX epoch, 2000-01-01.
+const FATX_EPOCH_UNIX: u64 = 946_684_800;
+
+/// A FATX timestamp. Like FAT, it packs a date and a time with two second
+/// precision in 32 bits, but years count from 2000 instead of 1980.
+///
+/// Timestamps are local time, as the Xbox has no time zone notion of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FatxTimestamp(u32);
+
+impl FatxTimestamp {
+    pub const fn from_raw(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    pub const fn into_raw(self) -> u32 {
+        self.0
+    }
+
+    /// Builds a timestamp from its parts. Out of range values are clamped,
+    /// and seconds are rounded down to an even number.
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Self {
+        let year = year.clamp(2000, 2127) - 2000;
+        let month = month.clamp(1, 12) as u32;
+        let day = day.clamp(1, 31) as u32;
+
+        let date = ((year as u32) << 9) | (month << 5) | day;
+        let time = ((hour.min(23) as u32) << 11) | ((minute.min(59) as u32) << 5) | (second.min(59) as u32 / 2);
+
+        Self((date << 16) | time)
+    }
+
+    pub fn year(&self) -> u16 {
+        2000 + (self.0 >> 25) as u16
+    }
+
+    pub fn month(&self) -> u8 {
+        ((self.0 >> 21) & 0xF) as u8
+    }
+
+    pub fn day(&self) -> u8 {
+        ((self.0 >> 16) & 0x1F) as u8
+    }
+
+    pub fn hour(&self) -> u8 {
+        ((self.0 >> 11) & 0x1F) as u8
+    }
+
+    pub fn minute(&self) -> u8 {
+        ((self.0 >> 5) & 0x3F) as u8
+    }
+
+    pub fn second(&self) -> u8 {
+        ((self.0 & 0x1F) * 2) as u8
+    }
+
+    /// Converts seconds since the Unix epoch. No time zone conversion is done.
+    pub fn from_unix(timestamp: u64) -> Self {
+        // Past 2127 the year is clamped anyway, so don't bother counting further
+        let seconds = timestamp.saturating_sub(FATX_EPOCH_UNIX).min(128 * 366 * 86_400);
+        let days = seconds / 86_400;
+        let seconds_of_day = seconds % 86_400;
+        let (year, month, day) = civil_from_days(days);
+
+        Self::new(
+            year.min(u16::MAX as u64) as u16,
+            month,
+            day,
+            (seconds_of_day / 3600) as u8,
+            (seconds_of_day / 60 % 60) as u8,
+            (seconds_of_day % 60) as u8,
+        )
+    }
+
+    /// Seconds since the Unix epoch. No time zone conversion is done.
+    pub fn to_unix(&self) -> u64 {
+        let days = days_from_civil(self.year() as u64, self.month().max(1), self.day().max(1));
+
+        FATX_EPOCH_UNIX
+            + days * 86_400
+            + self.hour() as u64 * 3600
+            + self.minute() as u64 * 60
+            + self.second() as u64
+    }
+}
+
+impl Default for FatxTimestamp {
+    /// 2000-01-01 00:00:00, the FATX epoch.
+    fn default() -> Self {
+        Self::new(2000, 1, 1, 0, 0, 0)
+    }
+}
+
+impl Display for FatxTimestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year(),
+            self.month(),
+            self.day(),
+            self.hour(),
+            self.minute(),
+            self.second()
+        )
+    }
+}
+
+fn is_leap_year(year: u64) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u64, month: u8) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 2000-01-01 to a date.
+fn civil_from_days(mut days: u64) -> (u64, u8, u8) {
+    let mut year = 2000;
+
+    loop {
+        let year_days = if is_leap_year(year) { 366 } else { 365 };
+
+        if days < year_days {
+            break;
+        }
+
+        days -= year_days;
+        year += 1;
+    }
+
+    let mut month = 1;
+
+    while days >= days_in_month(year, month) {
+        days -= days_in_month(year, month);
+        month += 1;
+    }
+
+    (year, month, days as u8 + 1)
+}
+
+/// Date to days since 2000-01-01.
+fn days_from_civil(year: u64, month: u8, day: u8) -> u64 {
+    let mut days = 0;
+
+    for y in 2000..year {
+        days += if is_leap_year(y) { 366 } else { 365 };
+    }
+
+    for m in 1..month.min(12) {
+        days += days_in_month(year, m);
+    }
+
+    days + day as u64 - 1
+}
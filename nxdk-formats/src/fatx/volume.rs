@@ -0,0 +1,681 @@
+use crate::fatx::dirent::{FatxAttributes, FatxDirEntry, Slot, DIRENT_DELETED, DIRENT_SIZE};
+use crate::fatx::file::FatxFile;
+use crate::fatx::time::FatxTimestamp;
+use crate::fatx::{is_valid_name, split_path, FatxError};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use embedded_io::{Read, Seek, SeekFrom, Write};
+
+pub const FATX_MAGIC: &[u8; 4] = b"FATX";
+
+pub const SECTOR_SIZE: u64 = 512;
+
+/// Size of the superblock, which is followed by the FAT.
+pub const SUPERBLOCK_SIZE: u64 = 0x1000;
+
+/// The FAT is padded to a multiple of this.
+const FAT_ALIGNMENT: u64 = 0x1000;
+
+/// Volumes with at least this many clusters use 32 bit FAT entries.
+const FATX32_MIN_CLUSTERS: u64 = 0xFFF0;
+
+const FAT_FREE: u32 = 0;
+
+/// FAT entries are kept in memory as 32 bit values; FATX16 values at or above
+/// these are extended to their FATX32 equivalent.
+const FAT_BAD: u32 = 0xFFFF_FFF7;
+const FAT_MEDIA: u32 = 0xFFFF_FFF8;
+const FAT_END: u32 = 0xFFFF_FFFF;
+const FAT16_RESERVED: u32 = 0xFFF0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatType {
+    Fatx16,
+    Fatx32,
+}
+
+impl FatType {
+    /// Size of a FAT entry, in bytes.
+    pub fn entry_size(&self) -> u64 {
+        match self {
+            FatType::Fatx16 => 2,
+            FatType::Fatx32 => 4,
+        }
+    }
+}
+
+/// The FATX superblock, found at the start of every partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Superblock {
+    pub volume_id: u32,
+    pub sectors_per_cluster: u32,
+    pub root_cluster: u32,
+    pub fat_copies: u16,
+}
+
+impl Superblock {
+    /// Parses a superblock. Returns None if the magic doesn't match.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 18 || &bytes[..4] != FATX_MAGIC {
+            return None;
+        }
+
+        let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        Some(Self {
+            volume_id: u32_at(0x04),
+            sectors_per_cluster: u32_at(0x08),
+            root_cluster: u32_at(0x0C),
+            fat_copies: u16::from_le_bytes([bytes[0x10], bytes[0x11]]),
+        })
+    }
+
+    /// Serializes the superblock, padded to `SUPERBLOCK_SIZE` with 0xFF.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0xFFu8; SUPERBLOCK_SIZE as usize];
+
+        bytes[..4].copy_from_slice(FATX_MAGIC);
+        bytes[0x04..0x08].copy_from_slice(&self.volume_id.to_le_bytes());
+        bytes[0x08..0x0C].copy_from_slice(&self.sectors_per_cluster.to_le_bytes());
+        bytes[0x0C..0x10].copy_from_slice(&self.root_cluster.to_le_bytes());
+        bytes[0x10..0x12].copy_from_slice(&self.fat_copies.to_le_bytes());
+
+        bytes
+    }
+}
+
+/// Where everything lives in a partition, derived from its size and cluster size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Geometry {
+    pub fat_type: FatType,
+    pub cluster_size: u64,
+    /// Number of usable data clusters. Valid cluster numbers go from 1 to this, inclusive.
+    pub cluster_count: u32,
+    pub fat_offset: u64,
+    pub fat_size: u64,
+    /// Offset of cluster 1, relative to the start of the partition.
+    pub data_offset: u64,
+}
+
+impl Geometry {
+    /// Returns None if the partition is too small for even a single cluster,
+    /// or the cluster size isn't a power of two.
+    pub fn new(partition_size: u64, sectors_per_cluster: u32) -> Option<Self> {
+        if sectors_per_cluster == 0 || !sectors_per_cluster.is_power_of_two() {
+            return None;
+        }
+
+        let cluster_size = sectors_per_cluster as u64 * SECTOR_SIZE;
+        let total_clusters = partition_size / cluster_size;
+
+        let fat_type = if total_clusters < FATX32_MIN_CLUSTERS {
+            FatType::Fatx16
+        } else {
+            FatType::Fatx32
+        };
+
+        let fat_size = (total_clusters * fat_type.entry_size()).div_ceil(FAT_ALIGNMENT) * FAT_ALIGNMENT;
+        let data_offset = SUPERBLOCK_SIZE + fat_size;
+
+        // Entry 0 is reserved, and the FAT size is counted from the whole partition,
+        // so the last few clusters may not actually fit after it
+        let cluster_count = total_clusters
+            .saturating_sub(1)
+            .min(partition_size.saturating_sub(data_offset) / cluster_size)
+            .min(u32::MAX as u64 - 16) as u32;
+
+        if cluster_count == 0 {
+            return None;
+        }
+
+        Some(Self {
+            fat_type,
+            cluster_size,
+            cluster_count,
+            fat_offset: SUPERBLOCK_SIZE,
+            fat_size,
+            data_offset,
+        })
+    }
+}
+
+/// A mounted FATX volume, over a device such as a disk image file.
+///
+/// The FAT is kept in memory, and every change to it is written through to the
+/// device right away, so there's nothing to flush.
+#[derive(Debug)]
+pub struct FatxVolume<D> {
+    device: D,
+    partition_offset: u64,
+    superblock: Superblock,
+    geometry: Geometry,
+    fat: Vec<u32>,
+    next_free: u32,
+    clock: fn() -> FatxTimestamp,
+}
+
+impl<D: Read + Write + Seek> FatxVolume<D> {
+    /// Mounts the FATX volume at `partition_offset`, which spans `partition_size` bytes.
+    pub fn open(device: D, partition_offset: u64, partition_size: u64) -> Result<Self, FatxError<D::Error>> {
+        let mut device = device;
+        let mut superblock = [0u8; 18];
+
+        device.seek(SeekFrom::Start(partition_offset))?;
+        device.read_exact(&mut superblock)?;
+
+        let superblock = Superblock::parse(&superblock).ok_or(FatxError::InvalidSuperblock)?;
+        let geometry =
+            Geometry::new(partition_size, superblock.sectors_per_cluster).ok_or(FatxError::InvalidSuperblock)?;
+
+        let mut volume = Self {
+            device,
+            partition_offset,
+            superblock,
+            geometry,
+            fat: Vec::new(),
+            next_free: 1,
+            clock: FatxTimestamp::default,
+        };
+
+        if !volume.is_valid_cluster(superblock.root_cluster) {
+            return Err(FatxError::InvalidSuperblock);
+        }
+
+        volume.load_fat()?;
+
+        Ok(volume)
+    }
+
+    /// Creates an empty FATX volume at `partition_offset`, and mounts it.
+    ///
+    /// The Xbox uses 32 sectors per cluster (16KiB) for its retail partitions.
+    pub fn format(
+        device: D,
+        partition_offset: u64,
+        partition_size: u64,
+        sectors_per_cluster: u32,
+        volume_id: u32,
+    ) -> Result<Self, FatxError<D::Error>> {
+        let geometry = Geometry::new(partition_size, sectors_per_cluster).ok_or(FatxError::InvalidSuperblock)?;
+
+        let superblock = Superblock {
+            volume_id,
+            sectors_per_cluster,
+            root_cluster: 1,
+            fat_copies: 1,
+        };
+
+        let mut volume = Self {
+            device,
+            partition_offset,
+            superblock,
+            geometry,
+            fat: vec![FAT_FREE; geometry.cluster_count as usize + 1],
+            next_free: 1,
+            clock: FatxTimestamp::default,
+        };
+
+        volume.write_at(0, &superblock.to_bytes())?;
+
+        // Clear the whole FAT, in page sized chunks
+        let zeroes = vec![0u8; FAT_ALIGNMENT as usize];
+        for page in 0..geometry.fat_size / FAT_ALIGNMENT {
+            volume.write_at(geometry.fat_offset + page * FAT_ALIGNMENT, &zeroes)?;
+        }
+
+        volume.set_fat_entry(0, FAT_MEDIA)?;
+        volume.set_fat_entry(superblock.root_cluster, FAT_END)?;
+        volume.clear_directory_cluster(superblock.root_cluster)?;
+
+        Ok(volume)
+    }
+
+    pub fn superblock(&self) -> &Superblock {
+        &self.superblock
+    }
+
+    pub fn geometry(&self) -> &Geometry {
+        &self.geometry
+    }
+
+    /// Sets the function used to timestamp created and modified files.
+    /// Without one, everything is stamped with the FATX epoch.
+    pub fn set_clock(&mut self, clock: fn() -> FatxTimestamp) {
+        self.clock = clock;
+    }
+
+    pub(crate) fn now(&self) -> FatxTimestamp {
+        (self.clock)()
+    }
+
+    /// Flushes the underlying device.
+    pub fn flush(&mut self) -> Result<(), FatxError<D::Error>> {
+        Ok(self.device.flush()?)
+    }
+
+    /// Unmounts the volume, returning the device.
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    /// Total space available for data, in bytes.
+    pub fn total_space(&self) -> u64 {
+        self.geometry.cluster_count as u64 * self.geometry.cluster_size
+    }
+
+    /// Free space, in bytes.
+    pub fn free_space(&self) -> u64 {
+        let free = self.fat[1..].iter().filter(|&&entry| entry == FAT_FREE).count();
+
+        free as u64 * self.geometry.cluster_size
+    }
+
+    /// Lists a directory, given its path relative to the volume root.
+    pub fn read_dir(&mut self, path: &str) -> Result<Vec<FatxDirEntry>, FatxError<D::Error>> {
+        let cluster = self.resolve_dir(path)?;
+
+        Ok(self
+            .dir_slots(cluster)?
+            .into_iter()
+            .map(|(entry, _)| entry)
+            .collect())
+    }
+
+    /// Looks up a file or directory. The root directory is reported with an empty name.
+    pub fn metadata(&mut self, path: &str) -> Result<FatxDirEntry, FatxError<D::Error>> {
+        match self.lookup(path)? {
+            Some((entry, _)) => Ok(entry),
+            None => Ok(FatxDirEntry {
+                name: String::new(),
+                attributes: FatxAttributes::Directory,
+                first_cluster: self.superblock.root_cluster,
+                size: 0,
+                creation_time: FatxTimestamp::default(),
+                last_write_time: FatxTimestamp::default(),
+                last_access_time: FatxTimestamp::default(),
+            }),
+        }
+    }
+
+    /// Opens an existing file for reading and writing.
+    pub fn open_file(&mut self, path: &str) -> Result<FatxFile<'_, D>, FatxError<D::Error>> {
+        let (entry, offset) = self.lookup(path)?.ok_or(FatxError::IsADirectory)?;
+
+        if entry.is_dir() {
+            return Err(FatxError::IsADirectory);
+        }
+
+        Ok(FatxFile::new(self, entry, offset))
+    }
+
+    /// Creates a file, or truncates it if it already exists.
+    pub fn create_file(&mut self, path: &str) -> Result<FatxFile<'_, D>, FatxError<D::Error>> {
+        match self.lookup(path) {
+            Ok(Some((mut entry, offset))) => {
+                if entry.is_dir() {
+                    return Err(FatxError::IsADirectory);
+                }
+
+                self.free_chain(entry.first_cluster)?;
+
+                entry.first_cluster = 0;
+                entry.size = 0;
+                entry.last_write_time = self.now();
+                self.write_entry(offset, &entry)?;
+
+                Ok(FatxFile::new(self, entry, offset))
+            }
+            Ok(None) => Err(FatxError::IsADirectory),
+            Err(FatxError::NotFound) => {
+                let (entry, offset) = self.insert_new(path, FatxAttributes::empty(), 0)?;
+
+                Ok(FatxFile::new(self, entry, offset))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Creates a directory. Its parent must already exist.
+    pub fn create_dir(&mut self, path: &str) -> Result<(), FatxError<D::Error>> {
+        match self.lookup(path) {
+            Ok(_) => return Err(FatxError::AlreadyExists),
+            Err(FatxError::NotFound) => {}
+            Err(error) => return Err(error),
+        }
+
+        let cluster = self.allocate_cluster(None)?;
+
+        if let Err(error) = self
+            .clear_directory_cluster(cluster)
+            .and_then(|_| self.insert_new(path, FatxAttributes::Directory, cluster))
+        {
+            let _ = self.free_chain(cluster);
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Removes a file, releasing its clusters.
+    pub fn remove_file(&mut self, path: &str) -> Result<(), FatxError<D::Error>> {
+        let (entry, offset) = self.lookup(path)?.ok_or(FatxError::IsADirectory)?;
+
+        if entry.is_dir() {
+            return Err(FatxError::IsADirectory);
+        }
+
+        self.write_at(offset, &[DIRENT_DELETED])?;
+        self.free_chain(entry.first_cluster)
+    }
+
+    /// Removes an empty directory.
+    pub fn remove_dir(&mut self, path: &str) -> Result<(), FatxError<D::Error>> {
+        // The root can't be removed
+        let (entry, offset) = self.lookup(path)?.ok_or(FatxError::DirectoryNotEmpty)?;
+
+        if !entry.is_dir() {
+            return Err(FatxError::NotADirectory);
+        }
+
+        if !self.dir_slots(entry.first_cluster)?.is_empty() {
+            return Err(FatxError::DirectoryNotEmpty);
+        }
+
+        self.write_at(offset, &[DIRENT_DELETED])?;
+        self.free_chain(entry.first_cluster)
+    }
+
+    /// Updates the timestamps of a file or directory. None leaves a time as it is.
+    pub fn set_times(
+        &mut self,
+        path: &str,
+        creation_time: Option<FatxTimestamp>,
+        last_write_time: Option<FatxTimestamp>,
+        last_access_time: Option<FatxTimestamp>,
+    ) -> Result<(), FatxError<D::Error>> {
+        let (mut entry, offset) = self.lookup(path)?.ok_or(FatxError::NotFound)?;
+
+        entry.creation_time = creation_time.unwrap_or(entry.creation_time);
+        entry.last_write_time = last_write_time.unwrap_or(entry.last_write_time);
+        entry.last_access_time = last_access_time.unwrap_or(entry.last_access_time);
+
+        self.write_entry(offset, &entry)
+    }
+
+    /// Updates the attributes of a file or directory. The directory flag can't be changed.
+    pub fn set_attributes(&mut self, path: &str, attributes: FatxAttributes) -> Result<(), FatxError<D::Error>> {
+        let (mut entry, offset) = self.lookup(path)?.ok_or(FatxError::NotFound)?;
+
+        let directory = entry.attributes & FatxAttributes::Directory;
+        entry.attributes = (attributes - FatxAttributes::Directory) | directory;
+
+        self.write_entry(offset, &entry)
+    }
+
+    pub(crate) fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> Result<(), FatxError<D::Error>> {
+        self.device.seek(SeekFrom::Start(self.partition_offset + offset))?;
+        self.device.read_exact(buf)?;
+
+        Ok(())
+    }
+
+    pub(crate) fn write_at(&mut self, offset: u64, buf: &[u8]) -> Result<(), FatxError<D::Error>> {
+        self.device.seek(SeekFrom::Start(self.partition_offset + offset))?;
+        self.device.write_all(buf)?;
+
+        Ok(())
+    }
+
+    pub(crate) fn cluster_size(&self) -> u64 {
+        self.geometry.cluster_size
+    }
+
+    /// Offset of a cluster, relative to the start of the partition.
+    pub(crate) fn cluster_offset(&self, cluster: u32) -> u64 {
+        self.geometry.data_offset + (cluster as u64 - 1) * self.geometry.cluster_size
+    }
+
+    fn is_valid_cluster(&self, cluster: u32) -> bool {
+        cluster >= 1 && cluster <= self.geometry.cluster_count
+    }
+
+    fn load_fat(&mut self) -> Result<(), FatxError<D::Error>> {
+        let entry_count = self.geometry.cluster_count as usize + 1;
+        let entry_size = self.geometry.fat_type.entry_size() as usize;
+
+        let mut raw = vec![0u8; entry_count * entry_size];
+        self.read_at(self.geometry.fat_offset, &mut raw)?;
+
+        self.fat = raw
+            .chunks_exact(entry_size)
+            .map(|entry| match self.geometry.fat_type {
+                FatType::Fatx16 => {
+                    let value = u16::from_le_bytes([entry[0], entry[1]]) as u32;
+
+                    if value >= FAT16_RESERVED {
+                        value | 0xFFFF_0000
+                    } else {
+                        value
+                    }
+                }
+                FatType::Fatx32 => u32::from_le_bytes(entry.try_into().unwrap()),
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    fn set_fat_entry(&mut self, cluster: u32, value: u32) -> Result<(), FatxError<D::Error>> {
+        self.fat[cluster as usize] = value;
+
+        let entry_size = self.geometry.fat_type.entry_size();
+        let offset = self.geometry.fat_offset + cluster as u64 * entry_size;
+
+        match self.geometry.fat_type {
+            FatType::Fatx16 => self.write_at(offset, &(value as u16).to_le_bytes()),
+            FatType::Fatx32 => self.write_at(offset, &value.to_le_bytes()),
+        }
+    }
+
+    /// The cluster following `cluster` in its chain, or None at the end of it.
+    pub(crate) fn next_cluster(&self, cluster: u32) -> Result<Option<u32>, FatxError<D::Error>> {
+        match self.fat[cluster as usize] {
+            FAT_FREE => Err(FatxError::Corrupt("cluster chain runs into a free cluster")),
+            FAT_BAD => Err(FatxError::Corrupt("cluster chain runs into a bad cluster")),
+            next if next >= FAT_MEDIA => Ok(None),
+            next if self.is_valid_cluster(next) => Ok(Some(next)),
+            _ => Err(FatxError::Corrupt("cluster chain points outside the volume")),
+        }
+    }
+
+    /// Every cluster of the chain starting at `first`. Empty if `first` is 0.
+    pub(crate) fn chain(&self, first: u32) -> Result<Vec<u32>, FatxError<D::Error>> {
+        let mut chain = Vec::new();
+
+        if first == 0 {
+            return Ok(chain);
+        }
+
+        if !self.is_valid_cluster(first) {
+            return Err(FatxError::Corrupt("cluster chain points outside the volume"));
+        }
+
+        let mut cluster = Some(first);
+
+        while let Some(current) = cluster {
+            if chain.len() > self.geometry.cluster_count as usize {
+                return Err(FatxError::Corrupt("cluster chain loops"));
+            }
+
+            chain.push(current);
+            cluster = self.next_cluster(current)?;
+        }
+
+        Ok(chain)
+    }
+
+    /// Allocates a free cluster, and links it after `previous` if given.
+    pub(crate) fn allocate_cluster(&mut self, previous: Option<u32>) -> Result<u32, FatxError<D::Error>> {
+        let count = self.geometry.cluster_count;
+
+        let cluster = (self.next_free..=count)
+            .chain(1..self.next_free)
+            .find(|&cluster| self.fat[cluster as usize] == FAT_FREE)
+            .ok_or(FatxError::NoSpace)?;
+
+        self.set_fat_entry(cluster, FAT_END)?;
+
+        if let Some(previous) = previous {
+            self.set_fat_entry(previous, cluster)?;
+        }
+
+        self.next_free = if cluster < count { cluster + 1 } else { 1 };
+
+        Ok(cluster)
+    }
+
+    /// Releases every cluster of the chain starting at `first`.
+    pub(crate) fn free_chain(&mut self, first: u32) -> Result<(), FatxError<D::Error>> {
+        for cluster in self.chain(first)? {
+            self.set_fat_entry(cluster, FAT_FREE)?;
+        }
+
+        Ok(())
+    }
+
+    /// Ends the chain at `last`, releasing every cluster after it.
+    pub(crate) fn truncate_chain(&mut self, last: u32) -> Result<(), FatxError<D::Error>> {
+        if let Some(next) = self.next_cluster(last)? {
+            self.free_chain(next)?;
+            self.set_fat_entry(last, FAT_END)?;
+        }
+
+        Ok(())
+    }
+
+    fn clear_directory_cluster(&mut self, cluster: u32) -> Result<(), FatxError<D::Error>> {
+        let empty = vec![0xFFu8; self.geometry.cluster_size as usize];
+        self.write_at(self.cluster_offset(cluster), &empty)
+    }
+
+    pub(crate) fn write_entry(&mut self, offset: u64, entry: &FatxDirEntry) -> Result<(), FatxError<D::Error>> {
+        self.write_at(offset, &entry.to_bytes())
+    }
+
+    /// Every live entry of a directory, along with the offset of its slot.
+    fn dir_slots(&mut self, first: u32) -> Result<Vec<(FatxDirEntry, u64)>, FatxError<D::Error>> {
+        let mut entries = Vec::new();
+        let mut buffer = vec![0u8; self.geometry.cluster_size as usize];
+
+        for cluster in self.chain(first)? {
+            let cluster_offset = self.cluster_offset(cluster);
+            self.read_at(cluster_offset, &mut buffer)?;
+
+            for (index, slot) in buffer.chunks_exact(DIRENT_SIZE).enumerate() {
+                match FatxDirEntry::parse(slot).ok_or(FatxError::Corrupt("invalid directory entry"))? {
+                    Slot::Entry(entry) => entries.push((entry, cluster_offset + (index * DIRENT_SIZE) as u64)),
+                    Slot::Deleted => {}
+                    Slot::End => return Ok(entries),
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Finds a file or directory. Returns None for the root directory.
+    fn lookup(&mut self, path: &str) -> Result<Option<(FatxDirEntry, u64)>, FatxError<D::Error>> {
+        let mut cluster = self.superblock.root_cluster;
+        let mut found: Option<(FatxDirEntry, u64)> = None;
+
+        for name in split_path(path) {
+            if let Some((entry, _)) = &found {
+                if !entry.is_dir() {
+                    return Err(FatxError::NotADirectory);
+                }
+
+                cluster = entry.first_cluster;
+            }
+
+            // FATX names are case insensitive
+            let entry = self
+                .dir_slots(cluster)?
+                .into_iter()
+                .find(|(entry, _)| entry.name.eq_ignore_ascii_case(name))
+                .ok_or(FatxError::NotFound)?;
+
+            found = Some(entry);
+        }
+
+        Ok(found)
+    }
+
+    /// First cluster of a directory.
+    fn resolve_dir(&mut self, path: &str) -> Result<u32, FatxError<D::Error>> {
+        match self.lookup(path)? {
+            None => Ok(self.superblock.root_cluster),
+            Some((entry, _)) if entry.is_dir() => Ok(entry.first_cluster),
+            Some(_) => Err(FatxError::NotADirectory),
+        }
+    }
+
+    /// Adds a new entry to the parent directory of `path`.
+    fn insert_new(
+        &mut self,
+        path: &str,
+        attributes: FatxAttributes,
+        first_cluster: u32,
+    ) -> Result<(FatxDirEntry, u64), FatxError<D::Error>> {
+        let path = path.trim_end_matches(['\\', '/']);
+        let (parent, name) = match path.rfind(['\\', '/']) {
+            Some(index) => (&path[..index], &path[index + 1..]),
+            None => ("", path),
+        };
+
+        if !is_valid_name(name) {
+            return Err(FatxError::InvalidName);
+        }
+
+        let directory = self.resolve_dir(parent)?;
+        let now = self.now();
+
+        let entry = FatxDirEntry {
+            name: name.into(),
+            attributes,
+            first_cluster,
+            size: 0,
+            creation_time: now,
+            last_write_time: now,
+            last_access_time: now,
+        };
+
+        let offset = self.free_slot(directory)?;
+        self.write_entry(offset, &entry)?;
+
+        Ok((entry, offset))
+    }
+
+    /// Offset of a free slot in a directory, growing it if it's full.
+    fn free_slot(&mut self, first: u32) -> Result<u64, FatxError<D::Error>> {
+        let chain = self.chain(first)?;
+        let mut buffer = vec![0u8; self.geometry.cluster_size as usize];
+
+        for &cluster in &chain {
+            let cluster_offset = self.cluster_offset(cluster);
+            self.read_at(cluster_offset, &mut buffer)?;
+
+            for (index, slot) in buffer.chunks_exact(DIRENT_SIZE).enumerate() {
+                if let Some(Slot::Deleted | Slot::End) = FatxDirEntry::parse(slot) {
+                    return Ok(cluster_offset + (index * DIRENT_SIZE) as u64);
+                }
+            }
+        }
+
+        let last = *chain.last().ok_or(FatxError::Corrupt("directory has no clusters"))?;
+        let cluster = self.allocate_cluster(Some(last))?;
+        self.clear_directory_cluster(cluster)?;
+
+        Ok(self.cluster_offset(cluster))
+    }
+}
@@ -0,0 +1,123 @@
+//! Plain RGBA images, as decoded from XPR textures and XBE logos.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+const BMP_HEADER_SIZE: usize = 14 + 40;
+
+/// An 8 bit per channel RGBA image, stored row by row from the top left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height` pixels, 4 bytes each, in R, G, B, A order.
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// A transparent black image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[offset..offset + 4].try_into().unwrap()
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[offset..offset + 4].copy_from_slice(&rgba);
+    }
+
+    /// Halves both dimensions, down to 1, averaging each 2x2 block of pixels.
+    pub fn downsample(&self) -> RgbaImage {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut image = RgbaImage::new(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0u32; 4];
+                let mut count = 0;
+
+                for (source_x, source_y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let (source_x, source_y) = (x * 2 + source_x, y * 2 + source_y);
+
+                    if source_x < self.width && source_y < self.height {
+                        let pixel = self.pixel(source_x, source_y);
+                        sum.iter_mut().zip(pixel).for_each(|(sum, channel)| *sum += channel as u32);
+                        count += 1;
+                    }
+                }
+
+                image.set_pixel(x, y, sum.map(|channel| ((channel + count / 2) / count) as u8));
+            }
+        }
+
+        image
+    }
+
+    /// This image followed by up to `levels - 1` downsampled ones, stopping at 1x1.
+    pub fn mip_chain(&self, levels: u32) -> Vec<RgbaImage> {
+        let mut chain = vec![self.clone()];
+
+        while (chain.len() as u32) < levels {
+            let last = chain.last().unwrap();
+
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+
+            chain.push(last.downsample());
+        }
+
+        chain
+    }
+
+    /// Pixels as B, G, R, A bytes, the memory layout of an A8R8G8B8 framebuffer.
+    pub fn to_bgra(&self) -> Vec<u8> {
+        self.pixels
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+            .collect()
+    }
+
+    /// Encodes the image as a 32 bit BMP file, keeping alpha.
+    pub fn to_bmp(&self) -> Vec<u8> {
+        let row_size = self.width as usize * 4;
+        let file_size = BMP_HEADER_SIZE + row_size * self.height as usize;
+        let mut bmp = Vec::with_capacity(file_size);
+
+        // BITMAPFILEHEADER
+        bmp.extend_from_slice(b"BM");
+        bmp.extend_from_slice(&(file_size as u32).to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+        bmp.extend_from_slice(&(BMP_HEADER_SIZE as u32).to_le_bytes());
+
+        // BITMAPINFOHEADER, bottom-up and uncompressed
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&(self.width as i32).to_le_bytes());
+        bmp.extend_from_slice(&(self.height as i32).to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&32u16.to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+        bmp.extend_from_slice(&((row_size * self.height as usize) as u32).to_le_bytes());
+        bmp.extend_from_slice(&2835i32.to_le_bytes());
+        bmp.extend_from_slice(&2835i32.to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+
+        let bgra = self.to_bgra();
+
+        for row in bgra.chunks_exact(row_size.max(1)).rev() {
+            bmp.extend_from_slice(row);
+        }
+
+        bmp
+    }
+}
@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: MIT
+//! Readers and writers for Xbox data formats.
+//!
+//! Everything here is plain Rust over `embedded_io`, with no dependency on the nxdk,
+//! so it can be used both on the Xbox and in host tools.
+#![no_std]
+
+extern crate alloc;
+
+pub mod fatx;
+pub mod image;
+pub mod meta;
+pub mod partition;
+pub mod sha1;
+pub mod signature;
+pub mod xbe;
+pub mod xdvdfs;
+pub mod xpr;
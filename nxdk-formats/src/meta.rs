@@ -0,0 +1,242 @@
+//! Metadata files of saved games, `SaveMeta.xbx` and `TitleMeta.xbx`.
+//!
+//! Both are UTF-16LE text, starting with a byte order mark, holding
+//! `Key=Value` lines separated by CRLF. Lines before any `[Section]` header
+//! belong to the default section; titles may add a section per language.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+
+pub const SAVE_META_FILE_NAME: &str = "SaveMeta.xbx";
+pub const SAVE_IMAGE_FILE_NAME: &str = "SaveImage.xbx";
+pub const TITLE_META_FILE_NAME: &str = "TitleMeta.xbx";
+pub const TITLE_IMAGE_FILE_NAME: &str = "TitleImage.xbx";
+
+/// Key holding the name of a save, in `SaveMeta.xbx`.
+pub const SAVE_NAME_KEY: &str = "Name";
+
+/// Key holding the name of a title, in `TitleMeta.xbx`.
+pub const TITLE_NAME_KEY: &str = "TitleName";
+
+/// Key that, set to 1, stops the dashboard from copying a save to a memory unit.
+pub const NO_COPY_KEY: &str = "NoCopy";
+
+/// Longest save name the dashboard shows, in UTF-16 code units.
+pub const MAX_SAVE_NAME_LENGTH: usize = 128;
+
+const BYTE_ORDER_MARK: u16 = 0xFEFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetaError {
+    /// UTF-16 text must have an even number of bytes.
+    OddLength,
+    /// The text has unpaired surrogates.
+    InvalidUtf16,
+}
+
+impl Display for MetaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MetaError::OddLength => write!(f, "Metadata has an odd number of bytes"),
+            MetaError::InvalidUtf16 => write!(f, "Metadata is not valid UTF-16"),
+        }
+    }
+}
+
+impl Error for MetaError {}
+
+/// A section of a metadata file, as (key, value) pairs in file order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetaSection {
+    /// Name between the brackets; empty for the default section.
+    pub name: String,
+    pub entries: Vec<(String, String)>,
+}
+
+impl MetaSection {
+    /// Value of `key`, ignoring its case.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _)| entry_key.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets `key`, replacing any previous value in place.
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.entries.iter_mut().find(|(entry_key, _)| entry_key.eq_ignore_ascii_case(key)) {
+            Some((_, entry_value)) => *entry_value = value.into(),
+            None => self.entries.push((key.into(), value.into())),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(entry_key, _)| entry_key.eq_ignore_ascii_case(key))?;
+
+        Some(self.entries.remove(index).1)
+    }
+}
+
+/// A parsed `SaveMeta.xbx` or `TitleMeta.xbx` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XbxMeta {
+    /// All sections, with the default one always first.
+    sections: Vec<MetaSection>,
+}
+
+impl Default for XbxMeta {
+    fn default() -> Self {
+        Self {
+            sections: alloc::vec![MetaSection::default()],
+        }
+    }
+}
+
+impl XbxMeta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Metadata of a save called `name`.
+    pub fn for_save(name: &str) -> Self {
+        let mut meta = Self::new();
+        meta.set(SAVE_NAME_KEY, name);
+        meta
+    }
+
+    /// Metadata of a title called `name`.
+    pub fn for_title(name: &str) -> Self {
+        let mut meta = Self::new();
+        meta.set(TITLE_NAME_KEY, name);
+        meta
+    }
+
+    /// Parses a metadata file. The byte order mark is optional, and lines that
+    /// aren't headers or `Key=Value` pairs are skipped.
+    pub fn parse(bytes: &[u8]) -> Result<Self, MetaError> {
+        if !bytes.len().is_multiple_of(2) {
+            return Err(MetaError::OddLength);
+        }
+
+        let units = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .skip_while(|&unit| unit == BYTE_ORDER_MARK);
+
+        let text: String = char::decode_utf16(units)
+            .collect::<Result<_, _>>()
+            .map_err(|_| MetaError::InvalidUtf16)?;
+
+        let mut meta = Self::new();
+
+        for line in text.lines() {
+            let line = line.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                meta.sections.push(MetaSection {
+                    name: name.into(),
+                    entries: Vec::new(),
+                });
+            } else if let Some((key, value)) = line.split_once('=') {
+                let section = meta.sections.last_mut().unwrap();
+                section.entries.push((key.trim_end().into(), value.trim_start().into()));
+            }
+        }
+
+        Ok(meta)
+    }
+
+    /// Serializes to UTF-16LE with a byte order mark and CRLF line endings.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut text = String::new();
+
+        for section in &self.sections {
+            if !section.name.is_empty() {
+                text.push('[');
+                text.push_str(&section.name);
+                text.push_str("]\r\n");
+            }
+
+            for (key, value) in &section.entries {
+                text.push_str(key);
+                text.push('=');
+                text.push_str(value);
+                text.push_str("\r\n");
+            }
+        }
+
+        core::iter::once(BYTE_ORDER_MARK)
+            .chain(text.encode_utf16())
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    /// The default section, holding keys outside of any `[Section]`.
+    pub fn default_section(&self) -> &MetaSection {
+        &self.sections[0]
+    }
+
+    pub fn default_section_mut(&mut self) -> &mut MetaSection {
+        &mut self.sections[0]
+    }
+
+    /// Named sections, such as per language ones.
+    pub fn sections(&self) -> &[MetaSection] {
+        &self.sections[1..]
+    }
+
+    /// Finds a named section, ignoring case.
+    pub fn section(&self, name: &str) -> Option<&MetaSection> {
+        self.sections().iter().find(|section| section.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Finds a named section, adding it if missing.
+    pub fn section_mut(&mut self, name: &str) -> &mut MetaSection {
+        let index = match self.sections.iter().skip(1).position(|section| section.name.eq_ignore_ascii_case(name)) {
+            Some(index) => index + 1,
+            None => {
+                self.sections.push(MetaSection {
+                    name: name.into(),
+                    entries: Vec::new(),
+                });
+                self.sections.len() - 1
+            }
+        };
+
+        &mut self.sections[index]
+    }
+
+    /// Value of `key` in the default section.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.default_section().get(key)
+    }
+
+    /// Sets `key` in the default section.
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.default_section_mut().set(key, value)
+    }
+
+    /// Value of `key` in the `language` section, falling back to the default section.
+    pub fn get_localized(&self, language: &str, key: &str) -> Option<&str> {
+        self.section(language)
+            .and_then(|section| section.get(key))
+            .or_else(|| self.get(key))
+    }
+
+    /// The `Name` of a save.
+    pub fn save_name(&self) -> Option<&str> {
+        self.get(SAVE_NAME_KEY)
+    }
+
+    /// The `TitleName` of a title.
+    pub fn title_name(&self) -> Option<&str> {
+        self.get(TITLE_NAME_KEY)
+    }
+
+    /// Whether the save is marked as not copyable.
+    pub fn no_copy(&self) -> bool {
+        self.get(NO_COPY_KEY).is_some_and(|value| value.trim() == "1")
+    }
+}
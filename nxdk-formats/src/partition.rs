@@ -0,0 +1,175 @@
+//! Xbox hard drive partition layouts.
+//!
+//! Retail drives have no partition table; the kernel hardcodes five partitions
+//! at fixed offsets. Larger drives commonly carry a "****PARTINFO****" table in
+//! sector 0 instead, which adds F: and G: after the retail partitions.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use embedded_io::{Read, ReadExactError, Seek, SeekFrom};
+
+pub const SECTOR_SIZE: u64 = 512;
+
+pub const PARTINFO_MAGIC: &[u8; 16] = b"****PARTINFO****";
+
+/// Size of the PARTINFO table, which lives in the first sector of the drive.
+pub const PARTINFO_SIZE: usize = 512;
+
+/// Number of entries in a PARTINFO table.
+pub const PARTINFO_ENTRIES: usize = 14;
+
+/// Entry flag marking a partition as in use.
+const PARTINFO_IN_USE: u32 = 0x8000_0000;
+
+/// Offset of the first PARTINFO entry.
+const PARTINFO_TABLE_OFFSET: usize = 0x30;
+
+const PARTINFO_ENTRY_SIZE: usize = 0x20;
+
+/// First sector after the retail E: partition, where F: starts.
+pub const RETAIL_END_LBA: u64 = 0xEE8AB0;
+
+/// Retail partitions, as (kernel partition number, drive letter, name, first sector, sectors).
+const RETAIL_PARTITIONS: [(u32, char, &str, u64, u64); 5] = [
+    (1, 'E', "XBOX DATA", 0x55F400, 0x9896B0),
+    (2, 'C', "XBOX SHELL", 0x465400, 0xFA000),
+    (3, 'X', "XBOX GAME SWAP 1", 0x400, 0x177000),
+    (4, 'Y', "XBOX GAME SWAP 2", 0x177400, 0x177000),
+    (5, 'Z', "XBOX GAME SWAP 3", 0x2EE400, 0x177000),
+];
+
+/// Conventional drive letters for each kernel partition number, starting at Partition1.
+const DRIVE_LETTERS: [char; 7] = ['E', 'C', 'X', 'Y', 'Z', 'F', 'G'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionLayout {
+    /// The fixed retail layout, with no partition table.
+    Retail,
+    /// A "****PARTINFO****" table in sector 0.
+    Extended,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    /// Kernel partition number, as in `\Device\Harddisk0\Partition1`.
+    pub number: u32,
+    /// Drive letter this partition is conventionally mounted to, if any.
+    pub drive_letter: Option<char>,
+    pub name: String,
+    pub lba_start: u64,
+    pub lba_size: u64,
+}
+
+impl Partition {
+    /// Offset of the partition from the start of the drive, in bytes.
+    pub fn offset(&self) -> u64 {
+        self.lba_start * SECTOR_SIZE
+    }
+
+    /// Size of the partition, in bytes.
+    pub fn size(&self) -> u64 {
+        self.lba_size * SECTOR_SIZE
+    }
+
+    /// NT device path of this partition.
+    pub fn device_path(&self) -> String {
+        device_path(self.number)
+    }
+}
+
+/// Drive letter a kernel partition number is conventionally mounted to, if any.
+pub fn drive_letter(number: u32) -> Option<char> {
+    DRIVE_LETTERS.get(number.checked_sub(1)? as usize).copied()
+}
+
+/// NT device path of a kernel partition number.
+pub fn device_path(number: u32) -> String {
+    alloc::format!("\\Device\\Harddisk0\\Partition{}", number)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionTable {
+    pub layout: PartitionLayout,
+    /// Partitions in use, ordered by partition number.
+    pub partitions: Vec<Partition>,
+}
+
+impl PartitionTable {
+    /// The retail layout, found on any stock drive.
+    pub fn retail() -> Self {
+        Self {
+            layout: PartitionLayout::Retail,
+            partitions: RETAIL_PARTITIONS
+                .iter()
+                .map(|&(number, letter, name, lba_start, lba_size)| Partition {
+                    number,
+                    drive_letter: Some(letter),
+                    name: name.into(),
+                    lba_start,
+                    lba_size,
+                })
+                .collect(),
+        }
+    }
+
+    /// Detects the layout from the first sector of a drive. Anything without
+    /// a PARTINFO table is taken to be retail.
+    pub fn parse(sector: &[u8]) -> Self {
+        if sector.len() < PARTINFO_SIZE || &sector[..PARTINFO_MAGIC.len()] != PARTINFO_MAGIC {
+            return Self::retail();
+        }
+
+        let partitions = sector[PARTINFO_TABLE_OFFSET..]
+            .chunks_exact(PARTINFO_ENTRY_SIZE)
+            .take(PARTINFO_ENTRIES)
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let u32_at = |offset: usize| u32::from_le_bytes(entry[offset..offset + 4].try_into().unwrap());
+
+                if u32_at(0x10) & PARTINFO_IN_USE == 0 {
+                    return None;
+                }
+
+                let name = String::from_utf8_lossy(&entry[..0x10]);
+
+                Some(Partition {
+                    number: index as u32 + 1,
+                    drive_letter: drive_letter(index as u32 + 1),
+                    name: name.trim_end_matches([' ', '\0']).into(),
+                    lba_start: u32_at(0x14) as u64,
+                    lba_size: u32_at(0x18) as u64,
+                })
+            })
+            .collect();
+
+        Self {
+            layout: PartitionLayout::Extended,
+            partitions,
+        }
+    }
+
+    /// Reads and detects the layout of a drive, or drive image.
+    pub fn read<D: Read + Seek>(device: &mut D) -> Result<Self, ReadExactError<D::Error>> {
+        let mut sector = [0u8; PARTINFO_SIZE];
+
+        device.seek(SeekFrom::Start(0))?;
+        device.read_exact(&mut sector)?;
+
+        Ok(Self::parse(&sector))
+    }
+
+    pub fn by_number(&self, number: u32) -> Option<&Partition> {
+        self.partitions.iter().find(|partition| partition.number == number)
+    }
+
+    pub fn by_drive_letter(&self, drive_letter: char) -> Option<&Partition> {
+        self.partitions
+            .iter()
+            .find(|partition| partition.drive_letter == Some(drive_letter.to_ascii_uppercase()))
+    }
+
+    /// Partitions past the retail ones, such as F: and G:.
+    pub fn extended_partitions(&self) -> impl Iterator<Item = &Partition> {
+        self.partitions.iter().filter(|partition| partition.number > 5)
+    }
+}
@@ -0,0 +1,178 @@
+//! SHA-1 and HMAC-SHA1, as used by XBE digests and save signatures.
+
+/// Size of a SHA-1 digest.
+pub const DIGEST_LENGTH: usize = 20;
+
+const BLOCK_LENGTH: usize = 64;
+
+const INITIAL_STATE: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+
+/// Incremental SHA-1 hasher.
+#[derive(Debug, Clone)]
+pub struct Sha1 {
+    state: [u32; 5],
+    block: [u8; BLOCK_LENGTH],
+    block_len: usize,
+    /// Total bytes hashed so far.
+    len: u64,
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha1 {
+    pub fn new() -> Self {
+        Self {
+            state: INITIAL_STATE,
+            block: [0; BLOCK_LENGTH],
+            block_len: 0,
+            len: 0,
+        }
+    }
+
+    /// Digest of `data`, in one go.
+    pub fn digest(data: &[u8]) -> [u8; DIGEST_LENGTH] {
+        let mut sha1 = Self::new();
+        sha1.update(data);
+        sha1.finish()
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+
+        if self.block_len > 0 {
+            let take = data.len().min(BLOCK_LENGTH - self.block_len);
+            self.block[self.block_len..self.block_len + take].copy_from_slice(&data[..take]);
+            self.block_len += take;
+            data = &data[take..];
+
+            if self.block_len < BLOCK_LENGTH {
+                return;
+            }
+
+            let block = self.block;
+            self.compress(&block);
+            self.block_len = 0;
+        }
+
+        let mut blocks = data.chunks_exact(BLOCK_LENGTH);
+
+        for block in &mut blocks {
+            self.compress(block.try_into().unwrap());
+        }
+
+        let rest = blocks.remainder();
+        self.block[..rest.len()].copy_from_slice(rest);
+        self.block_len = rest.len();
+    }
+
+    pub fn finish(mut self) -> [u8; DIGEST_LENGTH] {
+        let bit_len = self.len.wrapping_mul(8);
+
+        // A single 1 bit, zeros up to 8 bytes before a block boundary, then the length
+        let padding_len = (BLOCK_LENGTH + 55 - self.block_len) % BLOCK_LENGTH + 1;
+        let mut padding = [0u8; BLOCK_LENGTH + 8];
+        padding[0] = 0x80;
+        padding[padding_len..padding_len + 8].copy_from_slice(&bit_len.to_be_bytes());
+
+        let len = self.len;
+        self.update(&padding[..padding_len + 8]);
+        self.len = len;
+
+        let mut digest = [0u8; DIGEST_LENGTH];
+
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8; BLOCK_LENGTH]) {
+        let mut w = [0u32; 80];
+
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = self.state;
+
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..20 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..40 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..60 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+/// Incremental HMAC-SHA1.
+#[derive(Debug, Clone)]
+pub struct HmacSha1 {
+    inner: Sha1,
+    outer: Sha1,
+}
+
+impl HmacSha1 {
+    pub fn new(key: &[u8]) -> Self {
+        let mut block = [0u8; BLOCK_LENGTH];
+
+        // Keys longer than a block are hashed first
+        if key.len() > BLOCK_LENGTH {
+            block[..DIGEST_LENGTH].copy_from_slice(&Sha1::digest(key));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = Sha1::new();
+        let mut outer = Sha1::new();
+
+        inner.update(&block.map(|byte| byte ^ 0x36));
+        outer.update(&block.map(|byte| byte ^ 0x5C));
+
+        Self { inner, outer }
+    }
+
+    /// HMAC of `data` under `key`, in one go.
+    pub fn mac(key: &[u8], data: &[u8]) -> [u8; DIGEST_LENGTH] {
+        let mut hmac = Self::new(key);
+        hmac.update(data);
+        hmac.finish()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finish(self) -> [u8; DIGEST_LENGTH] {
+        let mut outer = self.outer;
+        outer.update(&self.inner.finish());
+        outer.finish()
+    }
+}
@@ -0,0 +1,111 @@
+//! Save data signatures, compatible with `XCalculateSignature`.
+//!
+//! A signature is the HMAC-SHA1 of the data, keyed with the title's
+//! authentication key. That key is the first 16 bytes of the HMAC-SHA1 of the
+//! signature key in the XBE certificate, keyed with the kernel's
+//! `XboxSignatureKey`. Roamable signatures verify on any console, non roamable
+//! ones are further keyed with the console's `XboxHDKey`.
+//!
+//! ```ignore
+//! let key = TitleSignatureKey::roamable(&xbox_signature_key, &certificate.signature_key);
+//! let signature = key.sign(&save_data);
+//! assert!(key.verify(&save_data, &signature));
+//! ```
+
+use crate::sha1::{HmacSha1, DIGEST_LENGTH};
+
+/// Size of Xbox keys, such as `XboxSignatureKey` or certificate keys.
+pub const KEY_LENGTH: usize = 16;
+
+/// Size of a signature.
+pub const SIGNATURE_LENGTH: usize = DIGEST_LENGTH;
+
+pub type XboxKey = [u8; KEY_LENGTH];
+
+pub type Signature = [u8; SIGNATURE_LENGTH];
+
+/// Derives a key as the first 16 bytes of the HMAC-SHA1 of `data` under `key`.
+fn derive_key(key: &XboxKey, data: &XboxKey) -> XboxKey {
+    let mac = HmacSha1::mac(key, data);
+    mac[..KEY_LENGTH].try_into().unwrap()
+}
+
+/// The key a title signs its data with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TitleSignatureKey {
+    key: XboxKey,
+}
+
+impl TitleSignatureKey {
+    /// Key for signatures that verify on any console.
+    ///
+    /// # Arguments
+    ///  - `console_key` The kernel's `XboxSignatureKey`
+    ///  - `certificate_key` The signature key of the XBE certificate
+    pub fn roamable(console_key: &XboxKey, certificate_key: &XboxKey) -> Self {
+        Self {
+            key: derive_key(console_key, certificate_key),
+        }
+    }
+
+    /// Key for signatures that only verify on the console with `hd_key`, the
+    /// kernel's `XboxHDKey`.
+    pub fn non_roamable(console_key: &XboxKey, hd_key: &XboxKey, certificate_key: &XboxKey) -> Self {
+        let roamable = Self::roamable(console_key, certificate_key);
+
+        Self {
+            key: derive_key(hd_key, &roamable.key),
+        }
+    }
+
+    /// Uses an already derived authentication key.
+    pub fn from_raw(key: XboxKey) -> Self {
+        Self { key }
+    }
+
+    pub fn as_raw(&self) -> &XboxKey {
+        &self.key
+    }
+
+    /// Starts an incremental signature, for data too large to hold at once.
+    pub fn signer(&self) -> Signer {
+        Signer {
+            hmac: HmacSha1::new(&self.key),
+        }
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Signature {
+        HmacSha1::mac(&self.key, data)
+    }
+
+    /// Checks a signature, in constant time.
+    pub fn verify(&self, data: &[u8], signature: &Signature) -> bool {
+        signatures_match(&self.sign(data), signature)
+    }
+}
+
+/// Incremental signature, from `TitleSignatureKey::signer`.
+#[derive(Debug, Clone)]
+pub struct Signer {
+    hmac: HmacSha1,
+}
+
+impl Signer {
+    pub fn update(&mut self, data: &[u8]) {
+        self.hmac.update(data);
+    }
+
+    pub fn finish(self) -> Signature {
+        self.hmac.finish()
+    }
+
+    /// Checks the data so far against a signature, in constant time.
+    pub fn verify(self, signature: &Signature) -> bool {
+        signatures_match(&self.finish(), signature)
+    }
+}
+
+/// Compares signatures without leaking where they differ through timing.
+fn signatures_match(a: &Signature, b: &Signature) -> bool {
+    a.iter().zip(b).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
@@ -0,0 +1,446 @@
+use crate::sha1::Sha1;
+use crate::xbe::certificate::{CERTIFICATE_SIZE, TITLE_NAME_LENGTH};
+use crate::xbe::header::{IMAGE_HEADER_SIZE, SIGNATURE_SIZE};
+use crate::xbe::pe::{PeImage, SCN_MEM_DISCARDABLE, SCN_MEM_EXECUTE, SCN_MEM_WRITE};
+use crate::xbe::section::SECTION_HEADER_SIZE;
+use crate::xbe::{
+    AllowedMedia, Certificate, GameRegion, ImageHeader, InitFlags, LibraryVersion, SectionFlags, SectionHeader,
+    XbeKind, XBE_BASE_ADDRESS,
+};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+
+/// Title ID used when none is given.
+pub const DEFAULT_TITLE_ID: u32 = 0xFFFF_0002;
+
+/// Name of the section holding the title image shown by the dashboard.
+pub const TITLE_IMAGE_SECTION: &str = "$$XTIMAGE";
+
+/// Name of the section holding the default save image.
+pub const SAVE_IMAGE_SECTION: &str = "$$XSIMAGE";
+
+const PAGE_SIZE: u32 = 0x1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// The input isn't a valid x86 PE32 image.
+    InvalidPe(&'static str),
+    /// The PE isn't linked at `XBE_BASE_ADDRESS`. Relocating it isn't supported.
+    UnsupportedBaseAddress(u32),
+    /// The PE has no import address table to use as the kernel thunk table.
+    NoKernelThunk,
+    /// The XBE headers don't fit below the first section.
+    HeadersTooLarge,
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BuildError::InvalidPe(reason) => write!(f, "Invalid PE image: {}", reason),
+            BuildError::UnsupportedBaseAddress(base) => {
+                write!(f, "PE image is based at {:#x}, not {:#x}", base, XBE_BASE_ADDRESS)
+            }
+            BuildError::NoKernelThunk => write!(f, "PE image has no import address table"),
+            BuildError::HeadersTooLarge => write!(f, "XBE headers overlap the first section"),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// A section on its way into the XBE.
+struct PendingSection<'a> {
+    name: String,
+    flags: SectionFlags,
+    virtual_address: u32,
+    virtual_size: u32,
+    data: &'a [u8],
+}
+
+/// Converts a linked PE32 executable into an XBE, like nxdk's cxbe.
+///
+/// The PE must be linked at `XBE_BASE_ADDRESS` and import the kernel through
+/// its import address table, which is how nxdk links executables.
+///
+/// ```ignore
+/// let mut builder = XbeBuilder::from_pe(&pe)?;
+/// builder.title_id(0x4E580001).title_name("My Homebrew").title_image(xpr);
+///
+/// std::fs::write("default.xbe", builder.build()?)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct XbeBuilder<'a> {
+    pe_bytes: &'a [u8],
+    pe: PeImage,
+    certificate: Certificate,
+    init_flags: InitFlags,
+    kind: XbeKind,
+    title_image: Option<Vec<u8>>,
+    save_image: Option<Vec<u8>>,
+    logo_bitmap: Vec<u8>,
+    library_versions: Vec<LibraryVersion>,
+    debug_path: String,
+}
+
+impl<'a> XbeBuilder<'a> {
+    pub fn from_pe(pe_bytes: &'a [u8]) -> Result<Self, BuildError> {
+        let pe = PeImage::parse(pe_bytes)?;
+
+        if pe.image_base != XBE_BASE_ADDRESS {
+            return Err(BuildError::UnsupportedBaseAddress(pe.image_base));
+        }
+
+        if pe.iat.virtual_address == 0 {
+            return Err(BuildError::NoKernelThunk);
+        }
+
+        let certificate = Certificate {
+            size: CERTIFICATE_SIZE as u32,
+            time_date: pe.time_date,
+            title_id: DEFAULT_TITLE_ID,
+            title_name: [0; TITLE_NAME_LENGTH],
+            alternate_title_ids: [0; 16],
+            allowed_media: AllowedMedia::HardDisk
+                | AllowedMedia::DvdX2
+                | AllowedMedia::DvdCd
+                | AllowedMedia::Cd
+                | AllowedMedia::Dvd5Ro
+                | AllowedMedia::Dvd9Ro
+                | AllowedMedia::Dvd5Rw
+                | AllowedMedia::Dvd9Rw,
+            game_region: GameRegion::NorthAmerica | GameRegion::Japan | GameRegion::RestOfWorld,
+            game_ratings: 0xFFFF_FFFF,
+            disk_number: 0,
+            version: 0,
+            lan_key: [0; 16],
+            signature_key: [0; 16],
+            alternate_signature_keys: [[0; 16]; 16],
+        };
+
+        Ok(Self {
+            pe_bytes,
+            pe,
+            certificate,
+            init_flags: InitFlags::Limit64Megabytes,
+            kind: XbeKind::Retail,
+            title_image: None,
+            save_image: None,
+            logo_bitmap: Vec::new(),
+            library_versions: Vec::new(),
+            debug_path: String::new(),
+        })
+    }
+
+    pub fn title_id(&mut self, title_id: u32) -> &mut Self {
+        self.certificate.title_id = title_id;
+        self
+    }
+
+    /// Sets the title name, truncated to `TITLE_NAME_LENGTH` UTF-16 code units.
+    pub fn title_name(&mut self, name: &str) -> &mut Self {
+        self.certificate.set_title_name(name);
+        self
+    }
+
+    pub fn version(&mut self, version: u32) -> &mut Self {
+        self.certificate.version = version;
+        self
+    }
+
+    pub fn allowed_media(&mut self, media: AllowedMedia) -> &mut Self {
+        self.certificate.allowed_media = media;
+        self
+    }
+
+    pub fn game_region(&mut self, region: GameRegion) -> &mut Self {
+        self.certificate.game_region = region;
+        self
+    }
+
+    pub fn init_flags(&mut self, flags: InitFlags) -> &mut Self {
+        self.init_flags = flags;
+        self
+    }
+
+    /// Kind of XBE, deciding the keys the entry point and kernel thunk are encoded with.
+    pub fn kind(&mut self, kind: XbeKind) -> &mut Self {
+        self.kind = kind;
+        self
+    }
+
+    /// The certificate, for fields without a setter, such as keys.
+    pub fn certificate_mut(&mut self) -> &mut Certificate {
+        &mut self.certificate
+    }
+
+    /// Title image shown by the dashboard, as an XPR, inserted as `$$XTIMAGE`.
+    pub fn title_image(&mut self, xpr: Vec<u8>) -> &mut Self {
+        self.title_image = Some(xpr);
+        self
+    }
+
+    /// Default save image, as an XPR, inserted as `$$XSIMAGE`.
+    pub fn save_image(&mut self, xpr: Vec<u8>) -> &mut Self {
+        self.save_image = Some(xpr);
+        self
+    }
+
+    /// RLE encoded logo bitmap shown while booting.
+    pub fn logo_bitmap(&mut self, bitmap: Vec<u8>) -> &mut Self {
+        self.logo_bitmap = bitmap;
+        self
+    }
+
+    /// Adds a library version. `XBOXKRNL` and `XAPILIB` are also referenced
+    /// from the image header.
+    pub fn library_version(&mut self, version: LibraryVersion) -> &mut Self {
+        self.library_versions.push(version);
+        self
+    }
+
+    /// Path of the source executable, kept in the debug fields.
+    pub fn debug_path(&mut self, path: &str) -> &mut Self {
+        self.debug_path = path.into();
+        self
+    }
+
+    fn sections(&self) -> Result<Vec<PendingSection<'_>>, BuildError> {
+        let base = self.pe.image_base;
+        let mut sections = Vec::new();
+
+        for section in &self.pe.sections {
+            if section.characteristics & SCN_MEM_DISCARDABLE != 0 {
+                continue;
+            }
+
+            let virtual_size = match section.virtual_size {
+                0 => section.raw_size,
+                size => size,
+            };
+
+            if virtual_size == 0 {
+                continue;
+            }
+
+            let raw_size = section.raw_size.min(virtual_size) as usize;
+            let data = self
+                .pe_bytes
+                .get(section.raw_offset as usize..)
+                .and_then(|data| data.get(..raw_size))
+                .ok_or(BuildError::InvalidPe("section data outside of file"))?;
+
+            let mut flags = SectionFlags::Preload;
+
+            if section.characteristics & SCN_MEM_WRITE != 0 {
+                flags |= SectionFlags::Writable;
+            }
+
+            if section.characteristics & SCN_MEM_EXECUTE != 0 {
+                flags |= SectionFlags::Executable;
+            }
+
+            sections.push(PendingSection {
+                name: section.name.clone(),
+                flags,
+                virtual_address: base + section.virtual_address,
+                virtual_size,
+                data,
+            });
+        }
+
+        let inserted = [
+            (TITLE_IMAGE_SECTION, &self.title_image),
+            (SAVE_IMAGE_SECTION, &self.save_image),
+        ];
+
+        for (name, data) in inserted {
+            let Some(data) = data else {
+                continue;
+            };
+
+            let end = sections
+                .iter()
+                .map(|section| section.virtual_address + section.virtual_size)
+                .max()
+                .unwrap_or(base);
+
+            // Inserted files aren't loaded, but still get their own pages
+            sections.push(PendingSection {
+                name: name.into(),
+                flags: SectionFlags::InsertedFile | SectionFlags::HeadPageReadOnly | SectionFlags::TailPageReadOnly,
+                virtual_address: end.next_multiple_of(PAGE_SIZE),
+                virtual_size: data.len() as u32,
+                data,
+            });
+        }
+
+        Ok(sections)
+    }
+
+    /// Builds the XBE.
+    pub fn build(&self) -> Result<Vec<u8>, BuildError> {
+        let base = self.pe.image_base;
+        let sections = self.sections()?;
+
+        // Everything the headers point to follows the image header
+        let mut headers = vec![0u8; IMAGE_HEADER_SIZE];
+        let address = |headers: &[u8]| base + headers.len() as u32;
+        let align = |headers: &mut Vec<u8>| headers.resize(headers.len().next_multiple_of(4), 0);
+
+        let certificate_address = address(&headers);
+        headers.extend_from_slice(&self.certificate.to_bytes());
+
+        let section_headers_address = address(&headers);
+        headers.resize(headers.len() + sections.len() * SECTION_HEADER_SIZE, 0);
+
+        // One shared page counter per section boundary; neighbours share the one between them
+        let page_counters_address = address(&headers);
+        headers.resize(headers.len() + (sections.len() + 1) * 2, 0);
+        align(&mut headers);
+
+        let mut name_addresses = Vec::with_capacity(sections.len());
+
+        for section in &sections {
+            name_addresses.push(address(&headers));
+            headers.extend_from_slice(section.name.as_bytes());
+            headers.push(0);
+        }
+
+        align(&mut headers);
+
+        let library_versions_address = match self.library_versions.is_empty() {
+            true => 0,
+            false => address(&headers),
+        };
+
+        let mut kernel_library_version_address = 0;
+        let mut xapi_library_version_address = 0;
+
+        for version in &self.library_versions {
+            match version.name.as_str() {
+                "XBOXKRNL" => kernel_library_version_address = address(&headers),
+                "XAPILIB" => xapi_library_version_address = address(&headers),
+                _ => {}
+            }
+
+            headers.extend_from_slice(&version.to_bytes());
+        }
+
+        let file_name = self.debug_path.rsplit(['\\', '/']).next().unwrap_or_default();
+
+        let debug_unicode_filename_address = address(&headers);
+
+        for unit in file_name.encode_utf16().chain([0]) {
+            headers.extend_from_slice(&unit.to_le_bytes());
+        }
+
+        let debug_pathname_address = address(&headers);
+        headers.extend_from_slice(self.debug_path.as_bytes());
+        headers.push(0);
+
+        // The file name is the tail of the path, as with PE debug directories
+        let debug_filename_address = debug_pathname_address + (self.debug_path.len() - file_name.len()) as u32;
+
+        align(&mut headers);
+
+        let logo_bitmap_address = match self.logo_bitmap.is_empty() {
+            true => 0,
+            false => address(&headers),
+        };
+
+        headers.extend_from_slice(&self.logo_bitmap);
+        align(&mut headers);
+
+        let size_of_headers = headers.len() as u32;
+
+        if let Some(first) = sections.iter().map(|section| section.virtual_address).min() {
+            if base + size_of_headers > first {
+                return Err(BuildError::HeadersTooLarge);
+            }
+        }
+
+        // Section data follows the headers, page aligned
+        let mut image = headers;
+        let mut section_headers = Vec::with_capacity(sections.len());
+
+        for (index, (section, name_address)) in sections.iter().zip(name_addresses).enumerate() {
+            image.resize(image.len().next_multiple_of(PAGE_SIZE as usize), 0);
+
+            let mut digest = Sha1::new();
+            digest.update(&(section.data.len() as u32).to_le_bytes());
+            digest.update(section.data);
+
+            section_headers.push(SectionHeader {
+                flags: section.flags,
+                virtual_address: section.virtual_address,
+                virtual_size: section.virtual_size,
+                raw_address: image.len() as u32,
+                raw_size: section.data.len() as u32,
+                name_address,
+                name_reference_count: 0,
+                head_shared_page_reference_count_address: page_counters_address + index as u32 * 2,
+                tail_shared_page_reference_count_address: page_counters_address + (index as u32 + 1) * 2,
+                digest: digest.finish(),
+                name: section.name.clone(),
+            });
+
+            image.extend_from_slice(section.data);
+        }
+
+        for (index, section) in section_headers.iter().enumerate() {
+            let offset = (section_headers_address - base) as usize + index * SECTION_HEADER_SIZE;
+            image[offset..offset + SECTION_HEADER_SIZE].copy_from_slice(&section.to_bytes());
+        }
+
+        let size_of_image = sections
+            .iter()
+            .map(|section| section.virtual_address + section.virtual_size)
+            .max()
+            .unwrap_or(base + size_of_headers)
+            .next_multiple_of(PAGE_SIZE)
+            - base;
+
+        let header = ImageHeader {
+            signature: [0; SIGNATURE_SIZE],
+            base_address: base,
+            size_of_headers,
+            size_of_image,
+            size_of_image_header: IMAGE_HEADER_SIZE as u32,
+            time_date: self.pe.time_date,
+            certificate_address,
+            number_of_sections: sections.len() as u32,
+            section_headers_address,
+            init_flags: self.init_flags,
+            encoded_entry_point: (base + self.pe.entry_point) ^ self.kind.entry_point_key(),
+            tls_address: match self.pe.tls.virtual_address {
+                0 => 0,
+                tls => base + tls,
+            },
+            pe_stack_commit: self.pe.stack_commit,
+            pe_heap_reserve: self.pe.heap_reserve,
+            pe_heap_commit: self.pe.heap_commit,
+            pe_base_address: self.pe.image_base,
+            pe_size_of_image: self.pe.size_of_image,
+            pe_checksum: self.pe.checksum,
+            pe_time_date: self.pe.time_date,
+            debug_pathname_address,
+            debug_filename_address,
+            debug_unicode_filename_address,
+            encoded_kernel_thunk_address: (base + self.pe.iat.virtual_address) ^ self.kind.kernel_thunk_key(),
+            non_kernel_import_directory_address: 0,
+            number_of_library_versions: self.library_versions.len() as u32,
+            library_versions_address,
+            kernel_library_version_address,
+            xapi_library_version_address,
+            logo_bitmap_address,
+            logo_bitmap_size: self.logo_bitmap.len() as u32,
+        };
+
+        image[..IMAGE_HEADER_SIZE].copy_from_slice(&header.to_bytes());
+
+        Ok(image)
+    }
+}
@@ -0,0 +1,141 @@
+use crate::xbe::{u32_at, XbeError};
+use alloc::string::String;
+use bitflags::bitflags;
+
+/// Size of the original certificate. Newer XBEs may have a larger one.
+pub const CERTIFICATE_SIZE: usize = 0x1D0;
+
+/// Longest title name, in UTF-16 code units.
+pub const TITLE_NAME_LENGTH: usize = 40;
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct AllowedMedia: u32 {
+        const HardDisk = 0x0000_0001;
+        const DvdX2 = 0x0000_0002;
+        const DvdCd = 0x0000_0004;
+        const Cd = 0x0000_0008;
+        const Dvd5Ro = 0x0000_0010;
+        const Dvd9Ro = 0x0000_0020;
+        const Dvd5Rw = 0x0000_0040;
+        const Dvd9Rw = 0x0000_0080;
+        const Dongle = 0x0000_0100;
+        const MediaBoard = 0x0000_0200;
+        const NonsecureHardDisk = 0x4000_0000;
+        const NonsecureMode = 0x8000_0000;
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct GameRegion: u32 {
+        const NorthAmerica = 0x0000_0001;
+        const Japan = 0x0000_0002;
+        const RestOfWorld = 0x0000_0004;
+        const Manufacturing = 0x8000_0000;
+    }
+}
+
+/// The certificate, holding the identity of the title and its keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    pub size: u32,
+    pub time_date: u32,
+    pub title_id: u32,
+    /// Title name, as UTF-16 code units padded with zeros.
+    pub title_name: [u16; TITLE_NAME_LENGTH],
+    pub alternate_title_ids: [u32; 16],
+    pub allowed_media: AllowedMedia,
+    pub game_region: GameRegion,
+    pub game_ratings: u32,
+    pub disk_number: u32,
+    pub version: u32,
+    pub lan_key: [u8; 16],
+    /// Key save signatures are derived from; see `signature::TitleSignatureKey`.
+    pub signature_key: [u8; 16],
+    pub alternate_signature_keys: [[u8; 16]; 16],
+}
+
+impl Certificate {
+    /// Parses a certificate. Fields past the original certificate are ignored.
+    pub fn parse<E>(bytes: &[u8]) -> Result<Self, XbeError<E>> {
+        if bytes.len() < CERTIFICATE_SIZE {
+            return Err(XbeError::UnexpectedEof);
+        }
+
+        let field = |offset: usize| u32_at(bytes, offset).unwrap();
+        let key = |offset: usize| -> [u8; 16] { bytes[offset..offset + 16].try_into().unwrap() };
+
+        let mut title_name = [0u16; TITLE_NAME_LENGTH];
+
+        for (unit, bytes) in title_name.iter_mut().zip(bytes[0x00C..0x05C].chunks_exact(2)) {
+            *unit = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+
+        Ok(Self {
+            size: field(0x000),
+            time_date: field(0x004),
+            title_id: field(0x008),
+            title_name,
+            alternate_title_ids: core::array::from_fn(|index| field(0x05C + index * 4)),
+            allowed_media: AllowedMedia::from_bits_retain(field(0x09C)),
+            game_region: GameRegion::from_bits_retain(field(0x0A0)),
+            game_ratings: field(0x0A4),
+            disk_number: field(0x0A8),
+            version: field(0x0AC),
+            lan_key: key(0x0B0),
+            signature_key: key(0x0C0),
+            alternate_signature_keys: core::array::from_fn(|index| key(0x0D0 + index * 16)),
+        })
+    }
+
+    /// Serializes the original certificate.
+    pub fn to_bytes(&self) -> [u8; CERTIFICATE_SIZE] {
+        let mut bytes = [0u8; CERTIFICATE_SIZE];
+        let mut put = |offset: usize, value: &[u8]| bytes[offset..offset + value.len()].copy_from_slice(value);
+
+        put(0x000, &self.size.to_le_bytes());
+        put(0x004, &self.time_date.to_le_bytes());
+        put(0x008, &self.title_id.to_le_bytes());
+
+        for (index, unit) in self.title_name.iter().enumerate() {
+            put(0x00C + index * 2, &unit.to_le_bytes());
+        }
+
+        for (index, title_id) in self.alternate_title_ids.iter().enumerate() {
+            put(0x05C + index * 4, &title_id.to_le_bytes());
+        }
+
+        put(0x09C, &self.allowed_media.bits().to_le_bytes());
+        put(0x0A0, &self.game_region.bits().to_le_bytes());
+        put(0x0A4, &self.game_ratings.to_le_bytes());
+        put(0x0A8, &self.disk_number.to_le_bytes());
+        put(0x0AC, &self.version.to_le_bytes());
+        put(0x0B0, &self.lan_key);
+        put(0x0C0, &self.signature_key);
+
+        for (index, key) in self.alternate_signature_keys.iter().enumerate() {
+            put(0x0D0 + index * 16, key);
+        }
+
+        bytes
+    }
+
+    /// The title name, up to its first NUL.
+    pub fn title_name(&self) -> String {
+        let len = self.title_name.iter().position(|&unit| unit == 0).unwrap_or(TITLE_NAME_LENGTH);
+
+        char::decode_utf16(self.title_name[..len].iter().copied())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    /// Sets the title name, truncated to `TITLE_NAME_LENGTH` code units.
+    pub fn set_title_name(&mut self, name: &str) {
+        self.title_name = [0; TITLE_NAME_LENGTH];
+
+        for (unit, value) in self.title_name.iter_mut().zip(name.encode_utf16()) {
+            *unit = value;
+        }
+    }
+}
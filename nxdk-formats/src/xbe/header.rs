@@ -0,0 +1,150 @@
+use crate::xbe::{u32_at, XbeError, XBE_MAGIC};
+use bitflags::bitflags;
+
+/// Size of the image header, up to the logo bitmap fields.
+pub const IMAGE_HEADER_SIZE: usize = 0x178;
+
+/// Size of the RSA signature following the magic.
+pub const SIGNATURE_SIZE: usize = 256;
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct InitFlags: u32 {
+        const MountUtilityDrive = 0x0000_0001;
+        const FormatUtilityDrive = 0x0000_0002;
+        const Limit64Megabytes = 0x0000_0004;
+        const DontSetupHarddisk = 0x0000_0008;
+    }
+}
+
+/// The image header, at the start of every XBE.
+///
+/// Addresses are virtual, relative to `base_address`. The entry point and
+/// kernel thunk are stored XOR encoded; see `Xbe` for the decoded values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageHeader {
+    pub signature: [u8; SIGNATURE_SIZE],
+    pub base_address: u32,
+    pub size_of_headers: u32,
+    pub size_of_image: u32,
+    pub size_of_image_header: u32,
+    pub time_date: u32,
+    pub certificate_address: u32,
+    pub number_of_sections: u32,
+    pub section_headers_address: u32,
+    pub init_flags: InitFlags,
+    pub encoded_entry_point: u32,
+    pub tls_address: u32,
+    pub pe_stack_commit: u32,
+    pub pe_heap_reserve: u32,
+    pub pe_heap_commit: u32,
+    pub pe_base_address: u32,
+    pub pe_size_of_image: u32,
+    pub pe_checksum: u32,
+    pub pe_time_date: u32,
+    pub debug_pathname_address: u32,
+    pub debug_filename_address: u32,
+    pub debug_unicode_filename_address: u32,
+    pub encoded_kernel_thunk_address: u32,
+    pub non_kernel_import_directory_address: u32,
+    pub number_of_library_versions: u32,
+    pub library_versions_address: u32,
+    pub kernel_library_version_address: u32,
+    pub xapi_library_version_address: u32,
+    pub logo_bitmap_address: u32,
+    pub logo_bitmap_size: u32,
+}
+
+impl ImageHeader {
+    /// Parses the image header from the start of an XBE.
+    pub fn parse<E>(bytes: &[u8]) -> Result<Self, XbeError<E>> {
+        if bytes.len() < IMAGE_HEADER_SIZE {
+            return Err(XbeError::UnexpectedEof);
+        }
+
+        if &bytes[..XBE_MAGIC.len()] != XBE_MAGIC {
+            return Err(XbeError::InvalidMagic);
+        }
+
+        let field = |offset: usize| u32_at(bytes, offset).unwrap();
+
+        Ok(Self {
+            signature: bytes[0x004..0x104].try_into().unwrap(),
+            base_address: field(0x104),
+            size_of_headers: field(0x108),
+            size_of_image: field(0x10C),
+            size_of_image_header: field(0x110),
+            time_date: field(0x114),
+            certificate_address: field(0x118),
+            number_of_sections: field(0x11C),
+            section_headers_address: field(0x120),
+            init_flags: InitFlags::from_bits_retain(field(0x124)),
+            encoded_entry_point: field(0x128),
+            tls_address: field(0x12C),
+            pe_stack_commit: field(0x130),
+            pe_heap_reserve: field(0x134),
+            pe_heap_commit: field(0x138),
+            pe_base_address: field(0x13C),
+            pe_size_of_image: field(0x140),
+            pe_checksum: field(0x144),
+            pe_time_date: field(0x148),
+            debug_pathname_address: field(0x14C),
+            debug_filename_address: field(0x150),
+            debug_unicode_filename_address: field(0x154),
+            encoded_kernel_thunk_address: field(0x158),
+            non_kernel_import_directory_address: field(0x15C),
+            number_of_library_versions: field(0x160),
+            library_versions_address: field(0x164),
+            kernel_library_version_address: field(0x168),
+            xapi_library_version_address: field(0x16C),
+            logo_bitmap_address: field(0x170),
+            logo_bitmap_size: field(0x174),
+        })
+    }
+
+    /// Serializes the image header.
+    pub fn to_bytes(&self) -> [u8; IMAGE_HEADER_SIZE] {
+        let mut bytes = [0u8; IMAGE_HEADER_SIZE];
+
+        bytes[..XBE_MAGIC.len()].copy_from_slice(XBE_MAGIC);
+        bytes[0x004..0x104].copy_from_slice(&self.signature);
+
+        let fields = [
+            self.base_address,
+            self.size_of_headers,
+            self.size_of_image,
+            self.size_of_image_header,
+            self.time_date,
+            self.certificate_address,
+            self.number_of_sections,
+            self.section_headers_address,
+            self.init_flags.bits(),
+            self.encoded_entry_point,
+            self.tls_address,
+            self.pe_stack_commit,
+            self.pe_heap_reserve,
+            self.pe_heap_commit,
+            self.pe_base_address,
+            self.pe_size_of_image,
+            self.pe_checksum,
+            self.pe_time_date,
+            self.debug_pathname_address,
+            self.debug_filename_address,
+            self.debug_unicode_filename_address,
+            self.encoded_kernel_thunk_address,
+            self.non_kernel_import_directory_address,
+            self.number_of_library_versions,
+            self.library_versions_address,
+            self.kernel_library_version_address,
+            self.xapi_library_version_address,
+            self.logo_bitmap_address,
+            self.logo_bitmap_size,
+        ];
+
+        for (chunk, field) in bytes[0x104..].chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+
+        bytes
+    }
+}
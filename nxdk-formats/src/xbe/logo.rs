@@ -0,0 +1,49 @@
+//! The logo bitmap shown while an XBE boots, run length encoded in its headers.
+//!
+//! The logo is 100x17 pixels of 4 bit intensity. Runs come in two sizes: a
+//! byte with bit 0 set holds a 3 bit length and the intensity, and a 16 bit
+//! word with bit 1 set holds a 10 bit length and the intensity.
+
+use crate::image::RgbaImage;
+use crate::xbe::XbeError;
+
+pub const LOGO_WIDTH: u32 = 100;
+
+pub const LOGO_HEIGHT: u32 = 17;
+
+/// Decodes a logo bitmap. Pixels are gray, of their intensity, and opaque,
+/// as shown on the black boot screen.
+pub fn decode_logo(rle: &[u8]) -> Result<RgbaImage, XbeError> {
+    let mut image = RgbaImage::new(LOGO_WIDTH, LOGO_HEIGHT);
+    let mut pixels = image.pixels.chunks_exact_mut(4);
+    let mut offset = 0;
+
+    while offset < rle.len() {
+        let byte = rle[offset];
+
+        let (len, intensity) = if byte & 0x1 != 0 {
+            offset += 1;
+            ((byte >> 1) as usize & 0x7, byte >> 4)
+        } else if byte & 0x2 != 0 {
+            let word = u16::from_le_bytes([byte, *rle.get(offset + 1).ok_or(XbeError::UnexpectedEof)?]);
+            offset += 2;
+            ((word >> 2) as usize & 0x3FF, (word >> 12) as u8)
+        } else {
+            return Err(XbeError::Corrupt("invalid logo run"));
+        };
+
+        let value = intensity << 4 | intensity;
+
+        // Runs past the end of the bitmap are dropped
+        for pixel in pixels.by_ref().take(len) {
+            pixel.copy_from_slice(&[value, value, value, 255]);
+        }
+    }
+
+    // Anything the runs didn't reach is black
+    for pixel in pixels {
+        pixel.copy_from_slice(&[0, 0, 0, 255]);
+    }
+
+    Ok(image)
+}
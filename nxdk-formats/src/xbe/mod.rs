@@ -0,0 +1,447 @@
+//! XBE, the executable format of the Xbox.
+//!
+//! An XBE starts with its headers, loaded as is at `base_address`: the image
+//! header, the certificate, section headers and library versions. Sections
+//! follow, each mapped to its own virtual address. The entry point and kernel
+//! thunk addresses are XOR encoded with keys that depend on the kind of XBE.
+//!
+//! ```ignore
+//! let xbe = Xbe::parse(&image)?;
+//! println!("{:08X} {}", xbe.certificate.title_id, xbe.certificate.title_name());
+//! ```
+
+pub mod builder;
+pub mod certificate;
+pub mod header;
+pub mod logo;
+pub mod pe;
+pub mod section;
+
+pub use builder::{BuildError, XbeBuilder};
+pub use certificate::{AllowedMedia, Certificate, GameRegion};
+pub use header::{ImageHeader, InitFlags};
+pub use section::{LibraryVersion, SectionFlags, SectionHeader, TlsDirectory};
+
+use crate::image::RgbaImage;
+use crate::xbe::builder::{SAVE_IMAGE_SECTION, TITLE_IMAGE_SECTION};
+use crate::xbe::header::IMAGE_HEADER_SIZE;
+use crate::xbe::logo::decode_logo;
+use crate::xbe::section::{LIBRARY_VERSION_SIZE, SECTION_HEADER_SIZE, TLS_DIRECTORY_SIZE};
+use crate::xpr::{Xpr, XprError};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::error::Error;
+use core::fmt::{Debug, Display, Formatter};
+use embedded_io::{ErrorKind, Read, ReadExactError, Seek, SeekFrom};
+
+pub const XBE_MAGIC: &[u8; 4] = b"XBEH";
+
+/// Address XBEs are loaded at.
+pub const XBE_BASE_ADDRESS: u32 = 0x10000;
+
+/// Headers larger than this are taken to be corrupt.
+const MAX_HEADERS_SIZE: u32 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XbeError<E = Infallible> {
+    /// Error from the underlying device.
+    Io(E),
+    /// The image ended before its headers did.
+    UnexpectedEof,
+    /// The image doesn't start with `XBEH`.
+    InvalidMagic,
+    /// The headers are inconsistent, such as addresses pointing outside of them.
+    Corrupt(&'static str),
+    /// The title or save image couldn't be decoded.
+    Image(XprError),
+}
+
+impl<E> From<E> for XbeError<E> {
+    fn from(value: E) -> Self {
+        XbeError::Io(value)
+    }
+}
+
+impl<E> From<ReadExactError<E>> for XbeError<E> {
+    fn from(value: ReadExactError<E>) -> Self {
+        match value {
+            ReadExactError::UnexpectedEof => XbeError::UnexpectedEof,
+            ReadExactError::Other(error) => XbeError::Io(error),
+        }
+    }
+}
+
+impl<E: Debug> Display for XbeError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            XbeError::Io(error) => write!(f, "IO error: {:?}", error),
+            XbeError::UnexpectedEof => write!(f, "Unexpected end of XBE"),
+            XbeError::InvalidMagic => write!(f, "Not an XBE"),
+            XbeError::Corrupt(reason) => write!(f, "Corrupt XBE: {}", reason),
+            XbeError::Image(error) => write!(f, "Invalid image: {}", error),
+        }
+    }
+}
+
+impl<E: Debug> Error for XbeError<E> {}
+
+impl<E: embedded_io::Error> embedded_io::Error for XbeError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            XbeError::Io(error) => error.kind(),
+            _ => ErrorKind::InvalidData,
+        }
+    }
+}
+
+/// Kind of XBE, which decides the keys the entry point and kernel thunk are encoded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum XbeKind {
+    Retail,
+    Debug,
+    Chihiro,
+}
+
+impl XbeKind {
+    pub const ALL: [XbeKind; 3] = [XbeKind::Retail, XbeKind::Debug, XbeKind::Chihiro];
+
+    pub fn entry_point_key(&self) -> u32 {
+        match self {
+            XbeKind::Retail => 0xA8FC_57AB,
+            XbeKind::Debug => 0x9485_9D4B,
+            XbeKind::Chihiro => 0x40B5_C16E,
+        }
+    }
+
+    pub fn kernel_thunk_key(&self) -> u32 {
+        match self {
+            XbeKind::Retail => 0x5B6D_40B6,
+            XbeKind::Debug => 0xEFB1_F152,
+            XbeKind::Chihiro => 0x2290_059D,
+        }
+    }
+}
+
+/// A parsed XBE.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xbe {
+    pub header: ImageHeader,
+    pub certificate: Certificate,
+    pub sections: Vec<SectionHeader>,
+    pub library_versions: Vec<LibraryVersion>,
+    pub kernel_library_version: Option<LibraryVersion>,
+    pub xapi_library_version: Option<LibraryVersion>,
+    /// The TLS directory. Only found by `parse_headers` if it lives in the headers.
+    pub tls: Option<TlsDirectory>,
+    /// Kind detected from the encoded entry point.
+    pub kind: XbeKind,
+    /// Decoded entry point address.
+    pub entry_point: u32,
+    /// Decoded address of the kernel thunk table.
+    pub kernel_thunk_address: u32,
+    pub debug_pathname: String,
+    pub debug_filename: String,
+    pub debug_unicode_filename: String,
+    /// The RLE encoded logo bitmap; see `logo::decode_logo`. Empty if the
+    /// header points it outside of the headers.
+    pub logo_bitmap: Vec<u8>,
+}
+
+impl Xbe {
+    /// Parses a whole XBE file.
+    pub fn parse(image: &[u8]) -> Result<Self, XbeError> {
+        let header = ImageHeader::parse(image)?;
+        let headers = image
+            .get(..header.size_of_headers as usize)
+            .ok_or(XbeError::UnexpectedEof)?;
+
+        let mut xbe = Self::parse_headers(headers)?;
+
+        if xbe.tls.is_none() && xbe.header.tls_address != 0 {
+            let offset = xbe.file_offset(xbe.header.tls_address).ok_or(XbeError::Corrupt("TLS directory outside of image"))?;
+            let tls = image.get(offset as usize..).and_then(TlsDirectory::parse);
+
+            xbe.tls = Some(tls.ok_or(XbeError::UnexpectedEof)?);
+        }
+
+        Ok(xbe)
+    }
+
+    /// Reads and parses an XBE from a device, such as a file.
+    pub fn read<D: Read + Seek>(device: &mut D) -> Result<Self, XbeError<D::Error>> {
+        let mut image_header = [0u8; IMAGE_HEADER_SIZE];
+
+        device.seek(SeekFrom::Start(0))?;
+        device.read_exact(&mut image_header)?;
+
+        let header = ImageHeader::parse(&image_header)?;
+
+        if header.size_of_headers > MAX_HEADERS_SIZE {
+            return Err(XbeError::Corrupt("headers are too large"));
+        }
+
+        let mut headers = vec![0u8; header.size_of_headers as usize];
+
+        device.seek(SeekFrom::Start(0))?;
+        device.read_exact(&mut headers)?;
+
+        let mut xbe = Self::parse_headers(&headers)?;
+
+        if xbe.tls.is_none() && xbe.header.tls_address != 0 {
+            let offset = xbe.file_offset(xbe.header.tls_address).ok_or(XbeError::Corrupt("TLS directory outside of image"))?;
+            let mut tls = [0u8; TLS_DIRECTORY_SIZE];
+
+            device.seek(SeekFrom::Start(offset as u64))?;
+            device.read_exact(&mut tls)?;
+
+            xbe.tls = TlsDirectory::parse(&tls);
+        }
+
+        Ok(xbe)
+    }
+
+    /// Parses the headers of an XBE, as found in a file or loaded in memory.
+    ///
+    /// Nothing past the headers is read, so the TLS directory is only
+    /// decoded if it's within them.
+    pub fn parse_headers<E>(headers: &[u8]) -> Result<Self, XbeError<E>> {
+        let header = ImageHeader::parse(headers)?;
+        let base = header.base_address;
+
+        let at = |address: u32, len: usize| -> Result<&[u8], XbeError<E>> {
+            address
+                .checked_sub(base)
+                .and_then(|offset| headers.get(offset as usize..)?.get(..len))
+                .ok_or(XbeError::Corrupt("address outside of headers"))
+        };
+
+        let certificate_size = u32_at(at(header.certificate_address, 4)?, 0).unwrap() as usize;
+        let certificate = Certificate::parse(at(
+            header.certificate_address,
+            certificate_size.max(certificate::CERTIFICATE_SIZE),
+        )?)?;
+
+        let table_size = |count: u32, entry_size: usize| -> Result<usize, XbeError<E>> {
+            (count as usize)
+                .checked_mul(entry_size)
+                .ok_or(XbeError::Corrupt("table larger than the headers"))
+        };
+
+        let section_headers = at(
+            header.section_headers_address,
+            table_size(header.number_of_sections, SECTION_HEADER_SIZE)?,
+        )?;
+
+        // Sized from the bounded slice, not the count in the header
+        let mut sections = Vec::with_capacity(section_headers.len() / SECTION_HEADER_SIZE);
+
+        for bytes in section_headers.chunks_exact(SECTION_HEADER_SIZE) {
+            let mut section = SectionHeader::parse(bytes).unwrap();
+            section.name = c_string_at(headers, base, section.name_address);
+            sections.push(section);
+        }
+
+        let library_versions = match header.library_versions_address {
+            0 => Vec::new(),
+            address => at(address, table_size(header.number_of_library_versions, LIBRARY_VERSION_SIZE)?)?
+                .chunks_exact(LIBRARY_VERSION_SIZE)
+                .filter_map(LibraryVersion::parse)
+                .collect(),
+        };
+
+        let library_version = |address: u32| -> Result<Option<LibraryVersion>, XbeError<E>> {
+            match address {
+                0 => Ok(None),
+                address => Ok(LibraryVersion::parse(at(address, LIBRARY_VERSION_SIZE)?)),
+            }
+        };
+
+        let kernel_library_version = library_version(header.kernel_library_version_address)?;
+        let xapi_library_version = library_version(header.xapi_library_version_address)?;
+
+        let tls = at(header.tls_address, TLS_DIRECTORY_SIZE)
+            .ok()
+            .filter(|_| header.tls_address != 0)
+            .and_then(TlsDirectory::parse);
+
+        // A bad logo shouldn't keep the rest from being read; `logo` reports it
+        let logo_bitmap = match header.logo_bitmap_address {
+            0 => Vec::new(),
+            address => at(address, header.logo_bitmap_size as usize).map(<[u8]>::to_vec).unwrap_or_default(),
+        };
+
+        let in_image = |address: u32| address >= base && address - base < header.size_of_image;
+
+        // The right keys decode both addresses into the image
+        let kind = XbeKind::ALL
+            .into_iter()
+            .filter(|kind| in_image(header.encoded_entry_point ^ kind.entry_point_key()))
+            .max_by_key(|kind| in_image(header.encoded_kernel_thunk_address ^ kind.kernel_thunk_key()))
+            .ok_or(XbeError::Corrupt("entry point outside of image"))?;
+
+        Ok(Self {
+            entry_point: header.encoded_entry_point ^ kind.entry_point_key(),
+            kernel_thunk_address: header.encoded_kernel_thunk_address ^ kind.kernel_thunk_key(),
+            kind,
+            debug_pathname: c_string_at(headers, base, header.debug_pathname_address),
+            debug_filename: c_string_at(headers, base, header.debug_filename_address),
+            debug_unicode_filename: wide_string_at(headers, base, header.debug_unicode_filename_address),
+            header,
+            certificate,
+            sections,
+            library_versions,
+            kernel_library_version,
+            xapi_library_version,
+            tls,
+            logo_bitmap,
+        })
+    }
+
+    pub fn title_id(&self) -> u32 {
+        self.certificate.title_id
+    }
+
+    pub fn title_name(&self) -> String {
+        self.certificate.title_name()
+    }
+
+    /// Finds a section by name, such as `$$XTIMAGE`.
+    pub fn section(&self, name: &str) -> Option<&SectionHeader> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    /// Finds the section a virtual address falls in.
+    pub fn section_at(&self, address: u32) -> Option<&SectionHeader> {
+        self.sections.iter().find(|section| section.contains(address))
+    }
+
+    /// File offset of a virtual address, if it's backed by data in the file.
+    pub fn file_offset(&self, address: u32) -> Option<u32> {
+        let header_offset = address.checked_sub(self.header.base_address)?;
+
+        if header_offset < self.header.size_of_headers {
+            return Some(header_offset);
+        }
+
+        self.section_at(address)?.file_offset(address)
+    }
+
+    /// Decodes the boot logo, if there's one.
+    ///
+    /// A logo the header points outside of the headers is `Corrupt`; only
+    /// this fails, parsing the rest of the XBE doesn't.
+    pub fn logo(&self) -> Result<Option<RgbaImage>, XbeError> {
+        if !self.logo_bitmap.is_empty() {
+            return decode_logo(&self.logo_bitmap).map(Some);
+        }
+
+        match self.header.logo_bitmap_address != 0 && self.header.logo_bitmap_size != 0 {
+            true => Err(XbeError::Corrupt("logo bitmap outside of headers")),
+            false => Ok(None),
+        }
+    }
+
+    /// Decodes the title image from the `$$XTIMAGE` section of a whole XBE file.
+    pub fn title_image(&self, image: &[u8]) -> Result<Option<RgbaImage>, XbeError> {
+        self.inserted_image(image, TITLE_IMAGE_SECTION)
+    }
+
+    /// Decodes the default save image from the `$$XSIMAGE` section of a whole XBE file.
+    pub fn save_image(&self, image: &[u8]) -> Result<Option<RgbaImage>, XbeError> {
+        self.inserted_image(image, SAVE_IMAGE_SECTION)
+    }
+
+    fn inserted_image(&self, image: &[u8], name: &str) -> Result<Option<RgbaImage>, XbeError> {
+        let Some(section) = self.section(name) else {
+            return Ok(None);
+        };
+
+        let data = self.section_data(image, section).ok_or(XbeError::UnexpectedEof)?;
+
+        decode_xpr(data).map(Some)
+    }
+
+    /// Reads and decodes the title image from a device.
+    pub fn read_title_image<D: Read + Seek>(&self, device: &mut D) -> Result<Option<RgbaImage>, XbeError<D::Error>> {
+        self.read_inserted_image(device, TITLE_IMAGE_SECTION)
+    }
+
+    /// Reads and decodes the default save image from a device.
+    pub fn read_save_image<D: Read + Seek>(&self, device: &mut D) -> Result<Option<RgbaImage>, XbeError<D::Error>> {
+        self.read_inserted_image(device, SAVE_IMAGE_SECTION)
+    }
+
+    fn read_inserted_image<D: Read + Seek>(
+        &self,
+        device: &mut D,
+        name: &str,
+    ) -> Result<Option<RgbaImage>, XbeError<D::Error>> {
+        let Some(section) = self.section(name) else {
+            return Ok(None);
+        };
+
+        let data = self.read_section(device, section)?;
+
+        decode_xpr(&data).map(Some)
+    }
+
+    /// Raw data of a section, from a whole XBE file.
+    pub fn section_data<'a>(&self, image: &'a [u8], section: &SectionHeader) -> Option<&'a [u8]> {
+        image.get(section.raw_address as usize..)?.get(..section.raw_size as usize)
+    }
+
+    /// Reads the raw data of a section from a device.
+    pub fn read_section<D: Read + Seek>(
+        &self,
+        device: &mut D,
+        section: &SectionHeader,
+    ) -> Result<Vec<u8>, XbeError<D::Error>> {
+        let mut data = vec![0u8; section.raw_size as usize];
+
+        device.seek(SeekFrom::Start(section.raw_address as u64))?;
+        device.read_exact(&mut data)?;
+
+        Ok(data)
+    }
+}
+
+/// Decodes the first texture of an XPR0, as inserted title and save images hold.
+fn decode_xpr<E>(data: &[u8]) -> Result<RgbaImage, XbeError<E>> {
+    Xpr::parse(data)
+        .and_then(|xpr| xpr.decode_first())
+        .map_err(XbeError::Image)
+}
+
+pub(crate) fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().unwrap()))
+}
+
+/// NUL terminated ASCII string at a virtual address in the headers. Empty if
+/// the address is 0 or out of bounds.
+fn c_string_at(headers: &[u8], base: u32, address: u32) -> String {
+    let Some(bytes) = address.checked_sub(base).and_then(|offset| headers.get(offset as usize..)) else {
+        return String::new();
+    };
+
+    let len = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+
+    String::from_utf8_lossy(&bytes[..len]).into()
+}
+
+/// NUL terminated UTF-16 string at a virtual address in the headers.
+fn wide_string_at(headers: &[u8], base: u32, address: u32) -> String {
+    let Some(bytes) = address.checked_sub(base).and_then(|offset| headers.get(offset as usize..)) else {
+        return String::new();
+    };
+
+    let units = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|&unit| unit != 0);
+
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
@@ -0,0 +1,138 @@
+//! Just enough of PE32 to turn a linked executable into an XBE.
+
+use crate::xbe::builder::BuildError;
+use crate::xbe::u32_at;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const PE_MAGIC: &[u8; 4] = b"PE\0\0";
+
+const MACHINE_I386: u16 = 0x014C;
+
+const OPTIONAL_HEADER_MAGIC_PE32: u16 = 0x010B;
+
+const SECTION_HEADER_SIZE: usize = 0x28;
+
+const DIRECTORY_TLS: usize = 9;
+
+const DIRECTORY_IAT: usize = 12;
+
+pub const SCN_MEM_DISCARDABLE: u32 = 0x0200_0000;
+
+pub const SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+
+pub const SCN_MEM_WRITE: u32 = 0x8000_0000;
+
+/// A section of a PE image. Addresses are relative to the image base.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeSection {
+    pub name: String,
+    pub virtual_size: u32,
+    pub virtual_address: u32,
+    pub raw_size: u32,
+    pub raw_offset: u32,
+    pub characteristics: u32,
+}
+
+/// A data directory entry, relative to the image base.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DataDirectory {
+    pub virtual_address: u32,
+    pub size: u32,
+}
+
+/// The parts of a PE32 image an XBE is built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeImage {
+    pub time_date: u32,
+    pub entry_point: u32,
+    pub image_base: u32,
+    pub size_of_image: u32,
+    pub size_of_headers: u32,
+    pub checksum: u32,
+    pub stack_commit: u32,
+    pub heap_reserve: u32,
+    pub heap_commit: u32,
+    pub tls: DataDirectory,
+    /// The import address table, which becomes the kernel thunk table.
+    pub iat: DataDirectory,
+    pub sections: Vec<PeSection>,
+}
+
+impl PeImage {
+    pub fn parse(bytes: &[u8]) -> Result<Self, BuildError> {
+        let u16_at = |offset: usize| -> Result<u16, BuildError> {
+            let value = bytes.get(offset..offset + 2).ok_or(BuildError::InvalidPe("truncated"))?;
+            Ok(u16::from_le_bytes([value[0], value[1]]))
+        };
+        let u32_at = |offset: usize| u32_at(bytes, offset).ok_or(BuildError::InvalidPe("truncated"));
+
+        if bytes.get(..2) != Some(b"MZ") {
+            return Err(BuildError::InvalidPe("missing MZ header"));
+        }
+
+        let pe = u32_at(0x3C)? as usize;
+
+        if bytes.get(pe..pe + 4) != Some(PE_MAGIC) {
+            return Err(BuildError::InvalidPe("missing PE header"));
+        }
+
+        if u16_at(pe + 4)? != MACHINE_I386 {
+            return Err(BuildError::InvalidPe("not an x86 image"));
+        }
+
+        let number_of_sections = u16_at(pe + 6)? as usize;
+        let time_date = u32_at(pe + 8)?;
+        let size_of_optional_header = u16_at(pe + 20)? as usize;
+
+        let optional = pe + 24;
+
+        if u16_at(optional)? != OPTIONAL_HEADER_MAGIC_PE32 {
+            return Err(BuildError::InvalidPe("not a PE32 image"));
+        }
+
+        let number_of_directories = u32_at(optional + 92)? as usize;
+        let directory = |index: usize| -> Result<DataDirectory, BuildError> {
+            if index >= number_of_directories {
+                return Ok(DataDirectory::default());
+            }
+
+            Ok(DataDirectory {
+                virtual_address: u32_at(optional + 96 + index * 8)?,
+                size: u32_at(optional + 100 + index * 8)?,
+            })
+        };
+
+        let mut sections = Vec::with_capacity(number_of_sections);
+
+        for index in 0..number_of_sections {
+            let header = optional + size_of_optional_header + index * SECTION_HEADER_SIZE;
+            let name = bytes.get(header..header + 8).ok_or(BuildError::InvalidPe("truncated"))?;
+            let name_len = name.iter().position(|&c| c == 0).unwrap_or(8);
+
+            sections.push(PeSection {
+                name: String::from_utf8_lossy(&name[..name_len]).into(),
+                virtual_size: u32_at(header + 8)?,
+                virtual_address: u32_at(header + 12)?,
+                raw_size: u32_at(header + 16)?,
+                raw_offset: u32_at(header + 20)?,
+                characteristics: u32_at(header + 36)?,
+            });
+        }
+
+        Ok(Self {
+            time_date,
+            entry_point: u32_at(optional + 16)?,
+            image_base: u32_at(optional + 28)?,
+            size_of_image: u32_at(optional + 56)?,
+            size_of_headers: u32_at(optional + 60)?,
+            checksum: u32_at(optional + 64)?,
+            stack_commit: u32_at(optional + 76)?,
+            heap_reserve: u32_at(optional + 80)?,
+            heap_commit: u32_at(optional + 84)?,
+            tls: directory(DIRECTORY_TLS)?,
+            iat: directory(DIRECTORY_IAT)?,
+            sections,
+        })
+    }
+}
@@ -0,0 +1,199 @@
+use crate::sha1::DIGEST_LENGTH;
+use crate::xbe::u32_at;
+use alloc::string::String;
+use bitflags::bitflags;
+
+pub const SECTION_HEADER_SIZE: usize = 0x38;
+
+pub const LIBRARY_VERSION_SIZE: usize = 0x10;
+
+pub const TLS_DIRECTORY_SIZE: usize = 0x18;
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct SectionFlags: u32 {
+        const Writable = 0x0000_0001;
+        const Preload = 0x0000_0002;
+        const Executable = 0x0000_0004;
+        const InsertedFile = 0x0000_0008;
+        const HeadPageReadOnly = 0x0000_0010;
+        const TailPageReadOnly = 0x0000_0020;
+    }
+}
+
+/// A section header, along with its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionHeader {
+    pub flags: SectionFlags,
+    pub virtual_address: u32,
+    pub virtual_size: u32,
+    /// Offset of the section data in the file.
+    pub raw_address: u32,
+    pub raw_size: u32,
+    pub name_address: u32,
+    pub name_reference_count: u32,
+    pub head_shared_page_reference_count_address: u32,
+    pub tail_shared_page_reference_count_address: u32,
+    pub digest: [u8; DIGEST_LENGTH],
+    /// Name read from `name_address`, such as `.text` or `$$XTIMAGE`.
+    pub name: String,
+}
+
+impl SectionHeader {
+    /// Parses a section header. The name is left empty, as it lives elsewhere.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.get(..SECTION_HEADER_SIZE)?;
+        let field = |offset: usize| u32_at(bytes, offset).unwrap();
+
+        Some(Self {
+            flags: SectionFlags::from_bits_retain(field(0x00)),
+            virtual_address: field(0x04),
+            virtual_size: field(0x08),
+            raw_address: field(0x0C),
+            raw_size: field(0x10),
+            name_address: field(0x14),
+            name_reference_count: field(0x18),
+            head_shared_page_reference_count_address: field(0x1C),
+            tail_shared_page_reference_count_address: field(0x20),
+            digest: bytes[0x24..0x38].try_into().unwrap(),
+            name: String::new(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; SECTION_HEADER_SIZE] {
+        let mut bytes = [0u8; SECTION_HEADER_SIZE];
+
+        let fields = [
+            self.flags.bits(),
+            self.virtual_address,
+            self.virtual_size,
+            self.raw_address,
+            self.raw_size,
+            self.name_address,
+            self.name_reference_count,
+            self.head_shared_page_reference_count_address,
+            self.tail_shared_page_reference_count_address,
+        ];
+
+        for (chunk, field) in bytes.chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+
+        bytes[0x24..0x38].copy_from_slice(&self.digest);
+
+        bytes
+    }
+
+    /// Whether `address` falls within this section once loaded.
+    pub fn contains(&self, address: u32) -> bool {
+        address >= self.virtual_address && address - self.virtual_address < self.virtual_size
+    }
+
+    /// File offset of the virtual `address`, if it's backed by data in the file.
+    pub fn file_offset(&self, address: u32) -> Option<u32> {
+        let offset = address.checked_sub(self.virtual_address)?;
+
+        (offset < self.raw_size && offset < self.virtual_size).then(|| self.raw_address + offset)
+    }
+}
+
+/// Version of a library the XBE was linked against, such as XAPILIB.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryVersion {
+    /// Library name, up to 8 ASCII characters.
+    pub name: String,
+    pub major: u16,
+    pub minor: u16,
+    pub build: u16,
+    /// QFE version, approval status and debug flag, packed.
+    pub flags: u16,
+}
+
+impl LibraryVersion {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.get(..LIBRARY_VERSION_SIZE)?;
+        let name_len = bytes[..8].iter().position(|&c| c == 0).unwrap_or(8);
+        let field = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+
+        Some(Self {
+            name: String::from_utf8_lossy(&bytes[..name_len]).into(),
+            major: field(0x8),
+            minor: field(0xA),
+            build: field(0xC),
+            flags: field(0xE),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; LIBRARY_VERSION_SIZE] {
+        let mut bytes = [0u8; LIBRARY_VERSION_SIZE];
+        let name = self.name.as_bytes();
+        let name_len = name.len().min(8);
+
+        bytes[..name_len].copy_from_slice(&name[..name_len]);
+        bytes[0x8..0xA].copy_from_slice(&self.major.to_le_bytes());
+        bytes[0xA..0xC].copy_from_slice(&self.minor.to_le_bytes());
+        bytes[0xC..0xE].copy_from_slice(&self.build.to_le_bytes());
+        bytes[0xE..0x10].copy_from_slice(&self.flags.to_le_bytes());
+
+        bytes
+    }
+
+    pub fn qfe_version(&self) -> u16 {
+        self.flags & 0x1FFF
+    }
+
+    /// Approval status: 0 for none, 1 for possibly, 2 for approved.
+    pub fn approved(&self) -> u8 {
+        ((self.flags >> 13) & 0x3) as u8
+    }
+
+    pub fn is_debug_build(&self) -> bool {
+        self.flags & 0x8000 != 0
+    }
+}
+
+/// The TLS directory, as in PE images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlsDirectory {
+    pub raw_data_start_address: u32,
+    pub raw_data_end_address: u32,
+    pub tls_index_address: u32,
+    pub tls_callback_address: u32,
+    pub size_of_zero_fill: u32,
+    pub characteristics: u32,
+}
+
+impl TlsDirectory {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.get(..TLS_DIRECTORY_SIZE)?;
+        let field = |offset: usize| u32_at(bytes, offset).unwrap();
+
+        Some(Self {
+            raw_data_start_address: field(0x00),
+            raw_data_end_address: field(0x04),
+            tls_index_address: field(0x08),
+            tls_callback_address: field(0x0C),
+            size_of_zero_fill: field(0x10),
+            characteristics: field(0x14),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; TLS_DIRECTORY_SIZE] {
+        let mut bytes = [0u8; TLS_DIRECTORY_SIZE];
+
+        let fields = [
+            self.raw_data_start_address,
+            self.raw_data_end_address,
+            self.tls_index_address,
+            self.tls_callback_address,
+            self.size_of_zero_fill,
+            self.characteristics,
+        ];
+
+        for (chunk, field) in bytes.chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+
+        bytes
+    }
+}
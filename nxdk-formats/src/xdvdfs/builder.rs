@@ -0,0 +1,415 @@
+use crate::xdvdfs::{
+    compare_names, RawEntry, VolumeDescriptor, XdvdfsEntry, ATTRIBUTE_DIRECTORY, ATTRIBUTE_NORMAL, MAX_NAME_LENGTH,
+    MAX_TABLE_SIZE, SECTOR_SIZE, VOLUME_DESCRIPTOR_SECTOR,
+};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Debug, Display, Formatter};
+use embedded_io::{ErrorKind, Read, Write};
+
+/// Buffer size used when copying file contents into the image.
+const COPY_BUFFER_SIZE: usize = 32 * SECTOR_SIZE as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError<E> {
+    /// Error writing the image.
+    Io(E),
+    /// Error reading a file source.
+    Source(ErrorKind),
+    /// A file source ended before the size it was added with.
+    SourceTooShort,
+    /// The name is empty, too long, or contains a separator.
+    InvalidName,
+    AlreadyExists,
+    /// A path goes through a file.
+    NotADirectory,
+    /// XDVDFS file sizes are 32 bits.
+    FileTooLarge,
+    /// A directory has more entries than its table can link.
+    DirectoryTooLarge,
+    /// The image needs more sectors than XDVDFS can address.
+    ImageTooLarge,
+}
+
+impl<E> From<E> for BuildError<E> {
+    fn from(value: E) -> Self {
+        BuildError::Io(value)
+    }
+}
+
+impl<E: Debug> Display for BuildError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BuildError::Io(error) => write!(f, "IO error: {:?}", error),
+            BuildError::Source(kind) => write!(f, "Error reading source file: {:?}", kind),
+            BuildError::SourceTooShort => write!(f, "Source file is shorter than expected"),
+            BuildError::InvalidName => write!(f, "Invalid XDVDFS name"),
+            BuildError::AlreadyExists => write!(f, "File already exists"),
+            BuildError::NotADirectory => write!(f, "Not a directory"),
+            BuildError::FileTooLarge => write!(f, "File too large for XDVDFS"),
+            BuildError::DirectoryTooLarge => write!(f, "Directory has too many entries"),
+            BuildError::ImageTooLarge => write!(f, "Image too large for XDVDFS"),
+        }
+    }
+}
+
+impl<E: Debug> Error for BuildError<E> {}
+
+#[derive(Debug)]
+enum Node<S> {
+    Directory(Vec<(String, Node<S>)>),
+    File { size: u32, source: S },
+}
+
+/// A node of the tree, with its place in the image worked out.
+#[derive(Debug)]
+struct Planned {
+    entry: XdvdfsEntry,
+    /// Indices of the children in `Plan::nodes`, sorted by name.
+    children: Vec<usize>,
+    path: String,
+}
+
+#[derive(Debug)]
+struct Plan<S> {
+    nodes: Vec<Planned>,
+    /// Directories in the order their tables are written.
+    directories: Vec<usize>,
+    /// Files in the order their data is written, along with their sources.
+    files: Vec<(usize, S)>,
+}
+
+/// Builds an XDVDFS image from a tree of files.
+///
+/// Files are added with a `source` to read their contents from, which is only
+/// read while the image is written. The layout is fully determined by the tree,
+/// the priority list and the creation time, so building the same tree twice
+/// gives identical images.
+///
+/// ```ignore
+/// let mut builder = XdvdfsBuilder::new();
+/// builder.add_file("default.xbe", xbe_size, xbe_file)?;
+/// builder.add_dir("media")?;
+/// builder.prioritize("default.xbe");
+/// builder.build(&mut image)?;
+/// ```
+#[derive(Debug)]
+pub struct XdvdfsBuilder<S> {
+    root: Vec<(String, Node<S>)>,
+    priority: Vec<String>,
+    creation_time: u64,
+}
+
+impl<S> Default for XdvdfsBuilder<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> XdvdfsBuilder<S> {
+    pub fn new() -> Self {
+        Self {
+            root: Vec::new(),
+            priority: Vec::new(),
+            creation_time: 0,
+        }
+    }
+
+    /// Sets the creation time stored in the volume descriptor, as a Windows FILETIME.
+    /// Defaults to 0, which keeps builds reproducible.
+    pub fn set_creation_time(&mut self, creation_time: u64) {
+        self.creation_time = creation_time;
+    }
+
+    /// Places a file's data right after the directory tables, ahead of files that
+    /// weren't prioritized. Files are placed in the order they were prioritized,
+    /// so files loaded together can be read back to back, without seeking.
+    ///
+    /// Paths that don't name a file are ignored.
+    pub fn prioritize(&mut self, path: &str) {
+        self.priority.push(normalize(path));
+    }
+
+    /// Adds a directory, and any missing parents.
+    pub fn add_dir<E>(&mut self, path: &str) -> Result<(), BuildError<E>> {
+        self.directory_mut(path).map(|_| ())
+    }
+
+    /// Adds a file of `size` bytes, whose contents will be read from `source`.
+    /// Missing parent directories are added as well.
+    pub fn add_file<E>(&mut self, path: &str, size: u64, source: S) -> Result<(), BuildError<E>> {
+        let size = u32::try_from(size).map_err(|_| BuildError::FileTooLarge)?;
+
+        let path = normalize(path);
+        let (parent, name) = path.rsplit_once('\\').unwrap_or(("", &path));
+        check_name(name)?;
+
+        let directory = self.directory_mut(parent)?;
+
+        if directory.iter().any(|(existing, _)| compare_names(existing, name).is_eq()) {
+            return Err(BuildError::AlreadyExists);
+        }
+
+        directory.push((name.into(), Node::File { size, source }));
+
+        Ok(())
+    }
+
+    /// The children of the directory at `path`, creating it if needed.
+    fn directory_mut<E>(&mut self, path: &str) -> Result<&mut Vec<(String, Node<S>)>, BuildError<E>> {
+        let mut directory = &mut self.root;
+
+        for name in normalize(path).split('\\').filter(|name| !name.is_empty()) {
+            check_name(name)?;
+
+            let index = match directory.iter().position(|(existing, _)| compare_names(existing, name).is_eq()) {
+                Some(index) => index,
+                None => {
+                    directory.push((name.into(), Node::Directory(Vec::new())));
+                    directory.len() - 1
+                }
+            };
+
+            directory = match &mut directory[index].1 {
+                Node::Directory(children) => children,
+                Node::File { .. } => return Err(BuildError::NotADirectory),
+            };
+        }
+
+        Ok(directory)
+    }
+
+    /// Lays out the image, and writes it to `out` front to back.
+    pub fn build<W>(self, out: &mut W) -> Result<(), BuildError<W::Error>>
+    where
+        W: Write,
+        S: Read,
+    {
+        let creation_time = self.creation_time;
+        let plan = self.plan()?;
+
+        // Everything before the volume descriptor is left empty
+        let empty = [0u8; SECTOR_SIZE as usize];
+        for _ in 0..VOLUME_DESCRIPTOR_SECTOR {
+            out.write_all(&empty)?;
+        }
+
+        let volume = VolumeDescriptor {
+            root_sector: plan.nodes[0].entry.sector,
+            root_size: plan.nodes[0].entry.size,
+            creation_time,
+        };
+        out.write_all(&volume.to_bytes())?;
+
+        // Tables and files were given consecutive sectors, in the order they're written
+        for &directory in &plan.directories {
+            out.write_all(&encode_table(&plan.nodes, directory))?;
+        }
+
+        let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+
+        for (index, mut source) in plan.files {
+            let mut remaining = plan.nodes[index].entry.size as usize;
+
+            while remaining > 0 {
+                let len = remaining.min(buffer.len());
+                source.read_exact(&mut buffer[..len]).map_err(|error| match error {
+                    embedded_io::ReadExactError::UnexpectedEof => BuildError::SourceTooShort,
+                    embedded_io::ReadExactError::Other(error) => BuildError::Source(embedded_io::Error::kind(&error)),
+                })?;
+
+                out.write_all(&buffer[..len])?;
+                remaining -= len;
+            }
+
+            let padding = (plan.nodes[index].entry.size as u64).next_multiple_of(SECTOR_SIZE)
+                - plan.nodes[index].entry.size as u64;
+            out.write_all(&empty[..padding as usize])?;
+        }
+
+        out.flush()?;
+
+        Ok(())
+    }
+
+    /// Flattens the tree, and assigns sectors to every directory table and file.
+    fn plan<E>(self) -> Result<Plan<S>, BuildError<E>> {
+        let mut nodes = vec![Planned {
+            entry: XdvdfsEntry {
+                name: String::new(),
+                attributes: ATTRIBUTE_DIRECTORY,
+                sector: 0,
+                size: 0,
+            },
+            children: Vec::new(),
+            path: String::new(),
+        }];
+
+        let mut sources = Vec::new();
+        let mut directories = Vec::new();
+
+        // Breadth first, so each directory's table sits close to its parent's
+        let mut pending = vec![(0, self.root)];
+        let mut next_pending = 0;
+
+        while next_pending < pending.len() {
+            let (parent, mut children) = core::mem::take(&mut pending[next_pending]);
+            next_pending += 1;
+
+            directories.push(parent);
+            children.sort_by(|(a, _), (b, _)| compare_names(a, b));
+
+            for (name, node) in children {
+                let path = match nodes[parent].path.is_empty() {
+                    true => name.clone(),
+                    false => alloc::format!("{}\\{}", nodes[parent].path, name),
+                };
+
+                let index = nodes.len();
+                let (attributes, size) = match node {
+                    Node::Directory(grandchildren) => {
+                        pending.push((index, grandchildren));
+                        (ATTRIBUTE_DIRECTORY, 0)
+                    }
+                    Node::File { size, source } => {
+                        sources.push((index, source));
+                        (ATTRIBUTE_NORMAL, size)
+                    }
+                };
+
+                nodes.push(Planned {
+                    entry: XdvdfsEntry {
+                        name,
+                        attributes,
+                        sector: 0,
+                        size,
+                    },
+                    children: Vec::new(),
+                    path,
+                });
+                nodes[parent].children.push(index);
+            }
+        }
+
+        let mut next_sector = VOLUME_DESCRIPTOR_SECTOR + 1;
+
+        for &directory in &directories {
+            let size = table_layout(&nodes, directory)?.1;
+
+            nodes[directory].entry.sector = sector_number(next_sector)?;
+            nodes[directory].entry.size = size as u32;
+            next_sector += size as u64 / SECTOR_SIZE;
+        }
+
+        // Prioritized files first, then everything else in directory order
+        let priority = |path: &str| self.priority.iter().position(|p| compare_names(p, path).is_eq());
+        sources.sort_by_key(|(index, _)| priority(&nodes[*index].path).unwrap_or(usize::MAX));
+
+        for (index, _) in &sources {
+            let size = nodes[*index].entry.size as u64;
+
+            nodes[*index].entry.sector = sector_number(next_sector)?;
+            next_sector += size.div_ceil(SECTOR_SIZE);
+        }
+
+        sector_number::<E>(next_sector)?;
+
+        Ok(Plan {
+            nodes,
+            directories,
+            files: sources,
+        })
+    }
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('/', "\\").trim_matches('\\').into()
+}
+
+fn check_name<E>(name: &str) -> Result<(), BuildError<E>> {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH || name.contains(['\\', '/']) || name == "." || name == ".." {
+        return Err(BuildError::InvalidName);
+    }
+
+    Ok(())
+}
+
+fn sector_number<E>(sector: u64) -> Result<u32, BuildError<E>> {
+    u32::try_from(sector).map_err(|_| BuildError::ImageTooLarge)
+}
+
+/// Arranges sorted entries as a balanced tree, in preorder. Each item is an
+/// index into `sorted`, plus the positions of its left and right children.
+fn balanced_preorder(sorted: &[usize], out: &mut Vec<(usize, Option<usize>, Option<usize>)>) -> Option<usize> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let middle = sorted.len() / 2;
+    let position = out.len();
+    out.push((sorted[middle], None, None));
+
+    let left = balanced_preorder(&sorted[..middle], out);
+    let right = balanced_preorder(&sorted[middle + 1..], out);
+    out[position].1 = left;
+    out[position].2 = right;
+
+    Some(position)
+}
+
+/// Works out where each entry of a directory table goes. Returns the tree in
+/// preorder along with the byte offset of each entry, and the table size.
+#[allow(clippy::type_complexity)]
+fn table_layout<E>(
+    nodes: &[Planned],
+    directory: usize,
+) -> Result<(Vec<(usize, Option<usize>, Option<usize>)>, usize, Vec<usize>), BuildError<E>> {
+    let mut tree = Vec::new();
+    balanced_preorder(&nodes[directory].children, &mut tree);
+
+    let mut offsets = Vec::with_capacity(tree.len());
+    let mut offset = 0;
+
+    for &(index, _, _) in &tree {
+        let len = nodes[index].entry.encoded_len();
+
+        // Entries never straddle a sector boundary
+        if offset % SECTOR_SIZE as usize + len > SECTOR_SIZE as usize {
+            offset = offset.next_multiple_of(SECTOR_SIZE as usize);
+        }
+
+        offsets.push(offset);
+        offset += len;
+    }
+
+    if offset > MAX_TABLE_SIZE {
+        return Err(BuildError::DirectoryTooLarge);
+    }
+
+    // Empty directories still get a sector, filled with padding
+    let size = offset.next_multiple_of(SECTOR_SIZE as usize).max(SECTOR_SIZE as usize);
+
+    Ok((tree, size, offsets))
+}
+
+fn encode_table(nodes: &[Planned], directory: usize) -> Vec<u8> {
+    // The layout was already checked while planning
+    let (tree, size, offsets) = table_layout::<()>(nodes, directory).unwrap();
+    let mut table = vec![0xFFu8; size];
+
+    for (position, &(index, left, right)) in tree.iter().enumerate() {
+        let link = |child: Option<usize>| child.map_or(0, |child| (offsets[child] / 4) as u16);
+
+        let raw = RawEntry {
+            left: link(left),
+            right: link(right),
+            entry: nodes[index].entry.clone(),
+        };
+
+        raw.write(&mut table[offsets[position]..]);
+    }
+
+    table
+}
@@ -0,0 +1,245 @@
+//! XDVDFS, the filesystem of Xbox game discs and XISO images.
+//!
+//! An image is a volume descriptor at sector 32, followed by directories and
+//! file data, in 2KiB sectors. Each directory is a binary search tree of
+//! entries, stored as a table and linked through offsets into that table.
+
+pub mod builder;
+pub mod reader;
+
+pub use builder::{BuildError, XdvdfsBuilder};
+pub use reader::{XdvdfsFile, XdvdfsReader};
+
+use alloc::string::String;
+use core::cmp::Ordering;
+use core::error::Error;
+use core::fmt::{Debug, Display, Formatter};
+use embedded_io::{ErrorKind, ReadExactError};
+
+pub const SECTOR_SIZE: u64 = 2048;
+
+pub const VOLUME_DESCRIPTOR_SECTOR: u64 = 32;
+
+pub const XDVDFS_MAGIC: &[u8; 20] = b"MICROSOFT*XBOX*MEDIA";
+
+/// Offsets of the game partition within full disc images, as (name, offset).
+/// Plain XISO images start at offset 0.
+pub const KNOWN_PARTITION_OFFSETS: [(&str, u64); 4] = [
+    ("XISO", 0),
+    ("XGD1", 0x1830_0000),
+    ("XGD2", 0x0FD9_0000),
+    ("XGD3", 0x0208_0000),
+];
+
+/// Size of an entry header, before its name.
+const DIRENT_HEADER_SIZE: usize = 14;
+
+/// Entry attribute marking a directory.
+pub const ATTRIBUTE_DIRECTORY: u8 = 0x10;
+
+/// Entry attribute set on regular files.
+pub const ATTRIBUTE_NORMAL: u8 = 0x80;
+
+/// Longest name an entry can hold.
+pub const MAX_NAME_LENGTH: usize = 255;
+
+/// Directory tables link entries through 16 bit offsets in 4 byte units.
+pub(crate) const MAX_TABLE_SIZE: usize = 0x10000 * 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XdvdfsError<E> {
+    /// Error from the underlying device.
+    Io(E),
+    /// The image ended before the filesystem did.
+    UnexpectedEof,
+    /// No volume descriptor was found.
+    InvalidVolume,
+    /// The filesystem structures are inconsistent, such as a looping tree.
+    Corrupt(&'static str),
+    NotFound,
+    NotADirectory,
+    IsADirectory,
+    /// Seeking before the start of a file.
+    InvalidSeek,
+}
+
+impl<E> From<E> for XdvdfsError<E> {
+    fn from(value: E) -> Self {
+        XdvdfsError::Io(value)
+    }
+}
+
+impl<E> From<ReadExactError<E>> for XdvdfsError<E> {
+    fn from(value: ReadExactError<E>) -> Self {
+        match value {
+            ReadExactError::UnexpectedEof => XdvdfsError::UnexpectedEof,
+            ReadExactError::Other(error) => XdvdfsError::Io(error),
+        }
+    }
+}
+
+impl<E: Debug> Display for XdvdfsError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            XdvdfsError::Io(error) => write!(f, "IO error: {:?}", error),
+            XdvdfsError::UnexpectedEof => write!(f, "Unexpected end of image"),
+            XdvdfsError::InvalidVolume => write!(f, "No XDVDFS volume descriptor found"),
+            XdvdfsError::Corrupt(reason) => write!(f, "Corrupt image: {}", reason),
+            XdvdfsError::NotFound => write!(f, "File not found"),
+            XdvdfsError::NotADirectory => write!(f, "Not a directory"),
+            XdvdfsError::IsADirectory => write!(f, "Is a directory"),
+            XdvdfsError::InvalidSeek => write!(f, "Invalid seek"),
+        }
+    }
+}
+
+impl<E: Debug> Error for XdvdfsError<E> {}
+
+impl<E: embedded_io::Error> embedded_io::Error for XdvdfsError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            XdvdfsError::Io(error) => error.kind(),
+            XdvdfsError::NotFound => ErrorKind::NotFound,
+            XdvdfsError::InvalidSeek => ErrorKind::InvalidInput,
+            XdvdfsError::UnexpectedEof | XdvdfsError::InvalidVolume | XdvdfsError::Corrupt(_) => {
+                ErrorKind::InvalidData
+            }
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// The volume descriptor, found at sector 32 of the game partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VolumeDescriptor {
+    pub root_sector: u32,
+    pub root_size: u32,
+    /// Windows FILETIME of when the image was built.
+    pub creation_time: u64,
+}
+
+impl VolumeDescriptor {
+    /// Parses a volume descriptor sector. Returns None if either magic doesn't match.
+    pub fn parse(sector: &[u8]) -> Option<Self> {
+        let tail = SECTOR_SIZE as usize - XDVDFS_MAGIC.len();
+
+        if sector.len() < SECTOR_SIZE as usize
+            || &sector[..XDVDFS_MAGIC.len()] != XDVDFS_MAGIC
+            || &sector[tail..SECTOR_SIZE as usize] != XDVDFS_MAGIC
+        {
+            return None;
+        }
+
+        Some(Self {
+            root_sector: u32::from_le_bytes(sector[0x14..0x18].try_into().unwrap()),
+            root_size: u32::from_le_bytes(sector[0x18..0x1C].try_into().unwrap()),
+            creation_time: u64::from_le_bytes(sector[0x1C..0x24].try_into().unwrap()),
+        })
+    }
+
+    /// Serializes the volume descriptor to a full sector.
+    pub fn to_bytes(&self) -> [u8; SECTOR_SIZE as usize] {
+        let mut sector = [0u8; SECTOR_SIZE as usize];
+        let tail = SECTOR_SIZE as usize - XDVDFS_MAGIC.len();
+
+        sector[..XDVDFS_MAGIC.len()].copy_from_slice(XDVDFS_MAGIC);
+        sector[0x14..0x18].copy_from_slice(&self.root_sector.to_le_bytes());
+        sector[0x18..0x1C].copy_from_slice(&self.root_size.to_le_bytes());
+        sector[0x1C..0x24].copy_from_slice(&self.creation_time.to_le_bytes());
+        sector[tail..].copy_from_slice(XDVDFS_MAGIC);
+
+        sector
+    }
+}
+
+/// A file or directory, as stored in its parent directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XdvdfsEntry {
+    pub name: String,
+    pub attributes: u8,
+    /// First sector of the data. Directories point at their entry table.
+    pub sector: u32,
+    /// Size in bytes. For directories, this is the size of the entry table.
+    pub size: u32,
+}
+
+impl XdvdfsEntry {
+    pub fn is_dir(&self) -> bool {
+        self.attributes & ATTRIBUTE_DIRECTORY != 0
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.is_dir()
+    }
+
+    /// Size of this entry in a directory table, including its padding.
+    pub fn encoded_len(&self) -> usize {
+        (DIRENT_HEADER_SIZE + self.name.len()).next_multiple_of(4)
+    }
+}
+
+/// A raw directory table entry, along with its tree links.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RawEntry {
+    /// Offset of the left subtree, in 4 byte units; 0 if none.
+    pub left: u16,
+    /// Offset of the right subtree, in 4 byte units; 0 if none.
+    pub right: u16,
+    pub entry: XdvdfsEntry,
+}
+
+impl RawEntry {
+    /// Parses the entry at the start of `bytes`. Returns None on padding or
+    /// truncated entries.
+    pub(crate) fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < DIRENT_HEADER_SIZE {
+            return None;
+        }
+
+        let left = u16::from_le_bytes([bytes[0], bytes[1]]);
+        let right = u16::from_le_bytes([bytes[2], bytes[3]]);
+
+        // Tables are padded with 0xFF up to each sector boundary
+        if left == 0xFFFF && right == 0xFFFF {
+            return None;
+        }
+
+        let name_length = bytes[13] as usize;
+        let name = bytes.get(DIRENT_HEADER_SIZE..DIRENT_HEADER_SIZE + name_length)?;
+
+        Some(Self {
+            left,
+            right,
+            entry: XdvdfsEntry {
+                name: String::from_utf8_lossy(name).into(),
+                attributes: bytes[12],
+                sector: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+                size: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+            },
+        })
+    }
+
+    /// Writes this entry at the start of `out`, padded to 4 bytes with 0xFF.
+    /// The name must be at most `MAX_NAME_LENGTH` bytes.
+    pub(crate) fn write(&self, out: &mut [u8]) {
+        let name = self.entry.name.as_bytes();
+        let len = self.entry.encoded_len();
+
+        out[..len].fill(0xFF);
+        out[0..2].copy_from_slice(&self.left.to_le_bytes());
+        out[2..4].copy_from_slice(&self.right.to_le_bytes());
+        out[4..8].copy_from_slice(&self.entry.sector.to_le_bytes());
+        out[8..12].copy_from_slice(&self.entry.size.to_le_bytes());
+        out[12] = self.entry.attributes;
+        out[13] = name.len() as u8;
+        out[DIRENT_HEADER_SIZE..DIRENT_HEADER_SIZE + name.len()].copy_from_slice(name);
+    }
+}
+
+/// Orders names the way directory trees are sorted: byte-wise, ignoring ASCII case.
+pub fn compare_names(a: &str, b: &str) -> Ordering {
+    let a = a.bytes().map(|c| c.to_ascii_uppercase());
+    let b = b.bytes().map(|c| c.to_ascii_uppercase());
+
+    a.cmp(b)
+}
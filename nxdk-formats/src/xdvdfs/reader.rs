@@ -0,0 +1,282 @@
+use crate::xdvdfs::{
+    compare_names, RawEntry, VolumeDescriptor, XdvdfsEntry, XdvdfsError, ATTRIBUTE_DIRECTORY,
+    KNOWN_PARTITION_OFFSETS, MAX_TABLE_SIZE, SECTOR_SIZE, VOLUME_DESCRIPTOR_SECTOR,
+};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use embedded_io::{ErrorType, Read, Seek, SeekFrom};
+
+/// Read-only access to an XDVDFS image, such as an XISO file or a full disc image.
+#[derive(Debug)]
+pub struct XdvdfsReader<D> {
+    device: D,
+    base_offset: u64,
+    volume: VolumeDescriptor,
+}
+
+impl<D: Read + Seek> XdvdfsReader<D> {
+    /// Opens an image, looking for the game partition at every known offset.
+    pub fn open(mut device: D) -> Result<Self, XdvdfsError<D::Error>> {
+        for (_, offset) in KNOWN_PARTITION_OFFSETS {
+            match Self::read_volume(&mut device, offset) {
+                Ok(volume) => {
+                    return Ok(Self {
+                        device,
+                        base_offset: offset,
+                        volume,
+                    })
+                }
+                // Too short for this layout, or nothing there
+                Err(XdvdfsError::UnexpectedEof | XdvdfsError::InvalidVolume) => continue,
+                Err(error) => return Err(error),
+            }
+        }
+
+        Err(XdvdfsError::InvalidVolume)
+    }
+
+    /// Opens an image whose game partition starts at `base_offset`.
+    pub fn open_at(mut device: D, base_offset: u64) -> Result<Self, XdvdfsError<D::Error>> {
+        let volume = Self::read_volume(&mut device, base_offset)?;
+
+        Ok(Self {
+            device,
+            base_offset,
+            volume,
+        })
+    }
+
+    fn read_volume(device: &mut D, base_offset: u64) -> Result<VolumeDescriptor, XdvdfsError<D::Error>> {
+        let mut sector = vec![0u8; SECTOR_SIZE as usize];
+
+        device.seek(SeekFrom::Start(base_offset + VOLUME_DESCRIPTOR_SECTOR * SECTOR_SIZE))?;
+        device.read_exact(&mut sector)?;
+
+        VolumeDescriptor::parse(&sector).ok_or(XdvdfsError::InvalidVolume)
+    }
+
+    pub fn volume(&self) -> &VolumeDescriptor {
+        &self.volume
+    }
+
+    /// Offset of the game partition within the image.
+    pub fn base_offset(&self) -> u64 {
+        self.base_offset
+    }
+
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    /// The root directory, with an empty name.
+    pub fn root(&self) -> XdvdfsEntry {
+        XdvdfsEntry {
+            name: String::new(),
+            attributes: ATTRIBUTE_DIRECTORY,
+            sector: self.volume.root_sector,
+            size: self.volume.root_size,
+        }
+    }
+
+    /// Lists a directory by path, sorted by name.
+    pub fn read_dir(&mut self, path: &str) -> Result<Vec<XdvdfsEntry>, XdvdfsError<D::Error>> {
+        let directory = self.metadata(path)?;
+        self.list(&directory)
+    }
+
+    /// Lists a directory entry, sorted by name.
+    pub fn list(&mut self, directory: &XdvdfsEntry) -> Result<Vec<XdvdfsEntry>, XdvdfsError<D::Error>> {
+        if !directory.is_dir() {
+            return Err(XdvdfsError::NotADirectory);
+        }
+
+        let table = self.read_table(directory)?;
+        let mut entries = Vec::new();
+
+        // In order traversal of the tree, with an explicit stack
+        let mut stack: Vec<RawEntry> = Vec::new();
+        let mut next = Self::entry_at(&table, 0)?;
+
+        loop {
+            while let Some(entry) = next {
+                if stack.len() > table.len() / 4 {
+                    return Err(XdvdfsError::Corrupt("directory tree loops"));
+                }
+
+                next = Self::child(&table, entry.left)?;
+                stack.push(entry);
+            }
+
+            let Some(entry) = stack.pop() else {
+                break;
+            };
+
+            next = Self::child(&table, entry.right)?;
+            entries.push(entry.entry);
+
+            if entries.len() > table.len() / 4 {
+                return Err(XdvdfsError::Corrupt("directory tree loops"));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Looks up a file or directory by path. Both `\` and `/` are separators.
+    pub fn metadata(&mut self, path: &str) -> Result<XdvdfsEntry, XdvdfsError<D::Error>> {
+        let mut current = self.root();
+
+        for name in path.split(['\\', '/']).filter(|name| !name.is_empty() && *name != ".") {
+            if !current.is_dir() {
+                return Err(XdvdfsError::NotADirectory);
+            }
+
+            current = self.find(&current, name)?.ok_or(XdvdfsError::NotFound)?;
+        }
+
+        Ok(current)
+    }
+
+    /// Opens a file for reading.
+    pub fn open_file(&mut self, path: &str) -> Result<XdvdfsFile<'_, D>, XdvdfsError<D::Error>> {
+        let entry = self.metadata(path)?;
+        self.open_entry(entry)
+    }
+
+    /// Opens a file from its directory entry.
+    pub fn open_entry(&mut self, entry: XdvdfsEntry) -> Result<XdvdfsFile<'_, D>, XdvdfsError<D::Error>> {
+        if entry.is_dir() {
+            return Err(XdvdfsError::IsADirectory);
+        }
+
+        Ok(XdvdfsFile {
+            reader: self,
+            entry,
+            position: 0,
+        })
+    }
+
+    /// Searches a directory tree for `name`, ignoring case.
+    fn find(&mut self, directory: &XdvdfsEntry, name: &str) -> Result<Option<XdvdfsEntry>, XdvdfsError<D::Error>> {
+        let table = self.read_table(directory)?;
+        let mut next = Self::entry_at(&table, 0)?;
+        let mut steps = 0;
+
+        while let Some(entry) = next {
+            steps += 1;
+
+            if steps > table.len() / 4 {
+                return Err(XdvdfsError::Corrupt("directory tree loops"));
+            }
+
+            next = match compare_names(name, &entry.entry.name) {
+                Ordering::Equal => return Ok(Some(entry.entry)),
+                Ordering::Less => Self::child(&table, entry.left)?,
+                Ordering::Greater => Self::child(&table, entry.right)?,
+            };
+        }
+
+        Ok(None)
+    }
+
+    fn read_table(&mut self, directory: &XdvdfsEntry) -> Result<Vec<u8>, XdvdfsError<D::Error>> {
+        // Nothing past the last linkable entry and its sector padding can be reached,
+        // so don't trust the size enough to allocate more than that
+        if directory.size as usize > MAX_TABLE_SIZE + SECTOR_SIZE as usize {
+            return Err(XdvdfsError::Corrupt("directory table too large"));
+        }
+
+        let mut table = vec![0u8; directory.size as usize];
+
+        self.device.seek(SeekFrom::Start(self.sector_offset(directory.sector)))?;
+        self.device.read_exact(&mut table)?;
+
+        Ok(table)
+    }
+
+    /// Entry at `offset` bytes into a table. An empty table, or padding at
+    /// the start of one, means an empty directory.
+    fn entry_at(table: &[u8], offset: usize) -> Result<Option<RawEntry>, XdvdfsError<D::Error>> {
+        if offset == 0 && table.len() < 4 {
+            return Ok(None);
+        }
+
+        match table.get(offset..).and_then(RawEntry::parse) {
+            Some(entry) => Ok(Some(entry)),
+            None if offset == 0 => Ok(None),
+            None => Err(XdvdfsError::Corrupt("directory entry out of bounds")),
+        }
+    }
+
+    /// Follows a tree link, given in 4 byte units.
+    fn child(table: &[u8], link: u16) -> Result<Option<RawEntry>, XdvdfsError<D::Error>> {
+        if link == 0 {
+            return Ok(None);
+        }
+
+        Self::entry_at(table, link as usize * 4)
+    }
+
+    fn sector_offset(&self, sector: u32) -> u64 {
+        self.base_offset + sector as u64 * SECTOR_SIZE
+    }
+}
+
+/// A file open for reading on an `XdvdfsReader`.
+#[derive(Debug)]
+pub struct XdvdfsFile<'a, D> {
+    reader: &'a mut XdvdfsReader<D>,
+    entry: XdvdfsEntry,
+    position: u64,
+}
+
+impl<D: Read + Seek> XdvdfsFile<'_, D> {
+    pub fn entry(&self) -> &XdvdfsEntry {
+        &self.entry
+    }
+
+    pub fn len(&self) -> u64 {
+        self.entry.size as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entry.size == 0
+    }
+}
+
+impl<D: Read + Seek> ErrorType for XdvdfsFile<'_, D> {
+    type Error = XdvdfsError<D::Error>;
+}
+
+impl<D: Read + Seek> Read for XdvdfsFile<'_, D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() || self.position >= self.len() {
+            return Ok(0);
+        }
+
+        let len = (buf.len() as u64).min(self.len() - self.position) as usize;
+        let offset = self.reader.sector_offset(self.entry.sector) + self.position;
+
+        self.reader.device.seek(SeekFrom::Start(offset))?;
+        self.reader.device.read_exact(&mut buf[..len])?;
+        self.position += len as u64;
+
+        Ok(len)
+    }
+}
+
+impl<D: Read + Seek> Seek for XdvdfsFile<'_, D> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Self::Error> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        self.position = position.ok_or(XdvdfsError::InvalidSeek)?;
+
+        Ok(self.position)
+    }
+}
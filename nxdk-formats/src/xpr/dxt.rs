@@ -0,0 +1,263 @@
+//! DXT1, DXT3 and DXT5 block compression, as sampled by the NV2A.
+//!
+//! Each 4x4 block of pixels holds two RGB565 end colors and a 2 bit index
+//! per pixel. DXT3 adds 4 bits of explicit alpha per pixel, and DXT5 two
+//! end alphas with a 3 bit index per pixel.
+
+use crate::image::RgbaImage;
+use alloc::vec::Vec;
+
+/// Block compression variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dxt {
+    Dxt1,
+    Dxt3,
+    Dxt5,
+}
+
+impl Dxt {
+    /// Size of a compressed 4x4 block.
+    pub fn block_size(&self) -> usize {
+        match self {
+            Dxt::Dxt1 => 8,
+            Dxt::Dxt3 | Dxt::Dxt5 => 16,
+        }
+    }
+
+    /// Size of a `width` by `height` image, in whole blocks.
+    pub fn data_size(&self, width: u32, height: u32) -> usize {
+        width.div_ceil(4) as usize * height.div_ceil(4) as usize * self.block_size()
+    }
+}
+
+pub(crate) fn rgb565(color: u16) -> [u8; 3] {
+    let r = (color >> 11) as u8 & 0x1F;
+    let g = (color >> 5) as u8 & 0x3F;
+    let b = color as u8 & 0x1F;
+
+    [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2]
+}
+
+fn mix(a: [u8; 3], b: [u8; 3], weight_a: u16, weight_b: u16) -> [u8; 4] {
+    let total = weight_a + weight_b;
+    let channel = |index: usize| ((a[index] as u16 * weight_a + b[index] as u16 * weight_b) / total) as u8;
+
+    [channel(0), channel(1), channel(2), 255]
+}
+
+/// The four colors of a color block. DXT1 blocks with `color0 <= color1`
+/// have three colors and transparent black.
+fn palette(block: &[u8], allow_transparent: bool) -> [[u8; 4]; 4] {
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let (c0, c1) = (rgb565(color0), rgb565(color1));
+
+    let opaque = |c: [u8; 3]| [c[0], c[1], c[2], 255];
+
+    if color0 > color1 || !allow_transparent {
+        [opaque(c0), opaque(c1), mix(c0, c1, 2, 1), mix(c0, c1, 1, 2)]
+    } else {
+        [opaque(c0), opaque(c1), mix(c0, c1, 1, 1), [0, 0, 0, 0]]
+    }
+}
+
+fn dxt5_alphas(block: &[u8]) -> [u8; 8] {
+    let (a0, a1) = (block[0] as u16, block[1] as u16);
+    let mut alphas = [a0 as u8, a1 as u8, 0, 0, 0, 0, 0, 255];
+
+    if a0 > a1 {
+        for index in 1..7 {
+            alphas[index + 1] = ((a0 * (7 - index as u16) + a1 * index as u16) / 7) as u8;
+        }
+    } else {
+        for index in 1..5 {
+            alphas[index + 1] = ((a0 * (5 - index as u16) + a1 * index as u16) / 5) as u8;
+        }
+    }
+
+    alphas
+}
+
+/// Decodes one block into its 16 pixels, row by row.
+fn decode_block(variant: Dxt, block: &[u8]) -> [[u8; 4]; 16] {
+    let color_block = match variant {
+        Dxt::Dxt1 => block,
+        Dxt::Dxt3 | Dxt::Dxt5 => &block[8..],
+    };
+
+    let colors = palette(color_block, variant == Dxt::Dxt1);
+    let indices = u32::from_le_bytes(color_block[4..8].try_into().unwrap());
+    let mut pixels = [[0u8; 4]; 16];
+
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = colors[(indices >> (index * 2)) as usize & 0x3];
+    }
+
+    match variant {
+        Dxt::Dxt1 => {}
+        Dxt::Dxt3 => {
+            let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+
+            for (index, pixel) in pixels.iter_mut().enumerate() {
+                let value = (alpha >> (index * 4)) as u8 & 0xF;
+                pixel[3] = value << 4 | value;
+            }
+        }
+        Dxt::Dxt5 => {
+            let alphas = dxt5_alphas(block);
+            let mut indices = [0u8; 8];
+            indices[..6].copy_from_slice(&block[2..8]);
+            let indices = u64::from_le_bytes(indices);
+
+            for (index, pixel) in pixels.iter_mut().enumerate() {
+                pixel[3] = alphas[(indices >> (index * 3)) as usize & 0x7];
+            }
+        }
+    }
+
+    pixels
+}
+
+pub(crate) fn to_rgb565(rgb: [u8; 3]) -> u16 {
+    let channel = |value: u8, max: u16| (value as u16 * max + 127) / 255;
+
+    channel(rgb[0], 0x1F) << 11 | channel(rgb[1], 0x3F) << 5 | channel(rgb[2], 0x1F)
+}
+
+fn distance(a: [u8; 4], b: [u8; 4]) -> u32 {
+    a.iter().zip(b).take(3).map(|(&a, b)| (a as i32 - b as i32).pow(2) as u32).sum()
+}
+
+/// Encodes a color block, using its two most distant colors as the ends.
+/// With `transparent`, pixels with alpha below half use the DXT1 transparent index.
+fn encode_color_block(pixels: &[[u8; 4]; 16], transparent: bool) -> [u8; 8] {
+    let is_transparent = |pixel: &[u8; 4]| transparent && pixel[3] < 128;
+    let opaque: Vec<[u8; 4]> = pixels.iter().filter(|pixel| !is_transparent(pixel)).copied().collect();
+
+    let start = opaque.first().copied().unwrap_or_default();
+    let mut ends = (start, start);
+
+    for (index, &a) in opaque.iter().enumerate() {
+        for &b in &opaque[index + 1..] {
+            if distance(a, b) > distance(ends.0, ends.1) {
+                ends = (a, b);
+            }
+        }
+    }
+
+    let rgb = |pixel: [u8; 4]| to_rgb565([pixel[0], pixel[1], pixel[2]]);
+    let (first, second) = (rgb(ends.0), rgb(ends.1));
+    let (high, low) = (first.max(second), first.min(second));
+    let has_transparent = pixels.iter().any(is_transparent);
+
+    // Three color mode, with transparent black, is picked by color0 <= color1
+    let (color0, color1) = match has_transparent {
+        true => (low, high),
+        false => (high, low),
+    };
+
+    let mut block = [0u8; 8];
+    block[..2].copy_from_slice(&color0.to_le_bytes());
+    block[2..4].copy_from_slice(&color1.to_le_bytes());
+
+    let colors = palette(&block, transparent);
+    // Equal colors also decode in three color mode, so keep clear of the transparent index
+    let choices = match has_transparent || color0 == color1 {
+        true => 3,
+        false => 4,
+    };
+
+    let mut indices = 0u32;
+
+    for (index, pixel) in pixels.iter().enumerate() {
+        let choice = match is_transparent(pixel) {
+            true => 3,
+            false => (0..choices).min_by_key(|&choice| distance(*pixel, colors[choice])).unwrap(),
+        };
+
+        indices |= (choice as u32) << (index * 2);
+    }
+
+    block[4..].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+fn encode_dxt5_alpha(pixels: &[[u8; 4]; 16]) -> [u8; 8] {
+    let max = pixels.iter().map(|pixel| pixel[3]).max().unwrap();
+    let min = pixels.iter().map(|pixel| pixel[3]).min().unwrap();
+
+    let mut block = [max, min, 0, 0, 0, 0, 0, 0];
+    let alphas = dxt5_alphas(&block);
+    let mut indices = 0u64;
+
+    for (index, pixel) in pixels.iter().enumerate() {
+        let choice = (0..8)
+            .min_by_key(|&choice| (alphas[choice] as i32 - pixel[3] as i32).abs())
+            .unwrap();
+
+        indices |= (choice as u64) << (index * 3);
+    }
+
+    block[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
+
+fn encode_block(variant: Dxt, pixels: &[[u8; 4]; 16], out: &mut Vec<u8>) {
+    match variant {
+        Dxt::Dxt1 => {}
+        Dxt::Dxt3 => {
+            let mut alpha = 0u64;
+
+            for (index, pixel) in pixels.iter().enumerate() {
+                alpha |= ((pixel[3] as u64 * 15 + 127) / 255) << (index * 4);
+            }
+
+            out.extend_from_slice(&alpha.to_le_bytes());
+        }
+        Dxt::Dxt5 => out.extend_from_slice(&encode_dxt5_alpha(pixels)),
+    }
+
+    out.extend_from_slice(&encode_color_block(pixels, variant == Dxt::Dxt1));
+}
+
+/// Block compresses an image. Partial blocks at the edges repeat the last row or column.
+pub fn encode(variant: Dxt, image: &RgbaImage) -> Vec<u8> {
+    let mut data = Vec::with_capacity(variant.data_size(image.width, image.height));
+
+    for block_y in (0..image.height).step_by(4) {
+        for block_x in (0..image.width).step_by(4) {
+            let pixels = core::array::from_fn(|index| {
+                let x = (block_x + index as u32 % 4).min(image.width - 1);
+                let y = (block_y + index as u32 / 4).min(image.height - 1);
+
+                image.pixel(x, y)
+            });
+
+            encode_block(variant, &pixels, &mut data);
+        }
+    }
+
+    data
+}
+
+/// Decodes a block compressed image. Blocks are stored row by row, unswizzled.
+pub fn decode(variant: Dxt, data: &[u8], width: u32, height: u32) -> Option<RgbaImage> {
+    let data = data.get(..variant.data_size(width, height))?;
+    let blocks_wide = width.div_ceil(4);
+    let mut image = RgbaImage::new(width, height);
+
+    for (block_index, block) in data.chunks_exact(variant.block_size()).enumerate() {
+        let block_x = block_index as u32 % blocks_wide * 4;
+        let block_y = block_index as u32 / blocks_wide * 4;
+
+        for (index, pixel) in decode_block(variant, block).into_iter().enumerate() {
+            let (x, y) = (block_x + index as u32 % 4, block_y + index as u32 / 4);
+
+            if x < width && y < height {
+                image.set_pixel(x, y, pixel);
+            }
+        }
+    }
+
+    Some(image)
+}
@@ -0,0 +1,604 @@
+//! XPR0 resource bundles, as produced by the XDK's bundler.
+//!
+//! An XPR0 starts with a 12 byte header, then D3D resource headers up to
+//! `header_size`, and the resource data after that. Textures are either
+//! swizzled, linear or DXT compressed.
+//!
+//! ```ignore
+//! let xpr = Xpr::parse(&bytes)?;
+//! let icon = xpr.decode_texture(&xpr.textures()[0])?;
+//!
+//! let mut builder = XprBuilder::new();
+//! builder.add_texture(&icon, TextureFormat::Dxt1, 0)?;
+//! let bytes = builder.build();
+//! ```
+
+pub mod dxt;
+pub mod swizzle;
+
+use crate::image::RgbaImage;
+use crate::xbe::u32_at;
+use crate::xpr::dxt::{rgb565, to_rgb565, Dxt};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+
+pub const XPR_MAGIC: &[u8; 4] = b"XPR0";
+
+/// Size of the XPR0 header: magic, total size and header size.
+pub const XPR_HEADER_SIZE: usize = 12;
+
+/// Size of a texture resource header: common, data, lock, format and size.
+pub const TEXTURE_HEADER_SIZE: usize = 20;
+
+/// Size of the resource headers without format and size, such as vertex buffers.
+const RESOURCE_HEADER_SIZE: usize = 12;
+
+/// Ends the list of resource headers.
+const END_OF_HEADERS: u32 = 0xFFFF_FFFF;
+
+/// Header size, and so data alignment, of bundles written by `XprBuilder`.
+const HEADER_ALIGNMENT: usize = 2048;
+
+/// Alignment of each texture's data.
+const TEXTURE_ALIGNMENT: usize = 128;
+
+/// Common field of textures written by `XprBuilder`: one reference, texture type.
+const COMMON_TEXTURE: u32 = (COMMON_TYPE_TEXTURE << COMMON_TYPE_SHIFT) | 1;
+
+/// Low bits of the format field: DMA channel A, border color and two dimensions.
+const FORMAT_2D: u32 = 0x29;
+
+const COMMON_TYPE_SHIFT: u32 = 16;
+const COMMON_TYPE_MASK: u32 = 0x7;
+const COMMON_TYPE_TEXTURE: u32 = 4;
+const COMMON_TYPE_SURFACE: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XprError {
+    /// The bundle ended before its headers or data did.
+    UnexpectedEof,
+    /// The bundle doesn't start with `XPR0`.
+    InvalidMagic,
+    /// The texture format code isn't supported.
+    UnsupportedFormat(u8),
+    /// Swizzled and compressed textures need power of two dimensions, up to 4096.
+    UnsupportedDimensions,
+    /// The mip level doesn't exist.
+    InvalidLevel,
+    /// The headers are inconsistent, such as a texture without dimensions.
+    Corrupt(&'static str),
+}
+
+impl Display for XprError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            XprError::UnexpectedEof => write!(f, "Unexpected end of XPR"),
+            XprError::InvalidMagic => write!(f, "Not an XPR0 bundle"),
+            XprError::UnsupportedFormat(code) => write!(f, "Unsupported texture format {:#04x}", code),
+            XprError::UnsupportedDimensions => write!(f, "Unsupported texture dimensions"),
+            XprError::InvalidLevel => write!(f, "No such mip level"),
+            XprError::Corrupt(reason) => write!(f, "Corrupt XPR: {}", reason),
+        }
+    }
+}
+
+impl Error for XprError {}
+
+/// Texture formats, with their `D3DFORMAT` codes on the Xbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum TextureFormat {
+    A1R5G5B5 = 0x02,
+    X1R5G5B5 = 0x03,
+    A4R4G4B4 = 0x04,
+    R5G6B5 = 0x05,
+    A8R8G8B8 = 0x06,
+    X8R8G8B8 = 0x07,
+    Dxt1 = 0x0C,
+    Dxt3 = 0x0E,
+    Dxt5 = 0x0F,
+    LinA1R5G5B5 = 0x10,
+    LinR5G6B5 = 0x11,
+    LinA8R8G8B8 = 0x12,
+    LinA4R4G4B4 = 0x1D,
+    LinX8R8G8B8 = 0x1E,
+}
+
+impl TextureFormat {
+    pub fn from_code(code: u8) -> Option<Self> {
+        use TextureFormat::*;
+
+        [
+            A1R5G5B5, X1R5G5B5, A4R4G4B4, R5G6B5, A8R8G8B8, X8R8G8B8, Dxt1, Dxt3, Dxt5, LinA1R5G5B5, LinR5G6B5,
+            LinA8R8G8B8, LinA4R4G4B4, LinX8R8G8B8,
+        ]
+        .into_iter()
+        .find(|format| *format as u8 == code)
+    }
+
+    pub fn code(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn dxt(&self) -> Option<Dxt> {
+        match self {
+            TextureFormat::Dxt1 => Some(Dxt::Dxt1),
+            TextureFormat::Dxt3 => Some(Dxt::Dxt3),
+            TextureFormat::Dxt5 => Some(Dxt::Dxt5),
+            _ => None,
+        }
+    }
+
+    /// Whether pixels are stored in Morton order.
+    pub fn is_swizzled(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::A1R5G5B5
+                | TextureFormat::X1R5G5B5
+                | TextureFormat::A4R4G4B4
+                | TextureFormat::R5G6B5
+                | TextureFormat::A8R8G8B8
+                | TextureFormat::X8R8G8B8
+        )
+    }
+
+    /// Whether pixels are stored row by row, with a pitch.
+    pub fn is_linear(&self) -> bool {
+        self.dxt().is_none() && !self.is_swizzled()
+    }
+
+    /// Bytes per pixel of uncompressed formats.
+    pub fn bytes_per_pixel(&self) -> Option<usize> {
+        match self {
+            TextureFormat::A8R8G8B8
+            | TextureFormat::X8R8G8B8
+            | TextureFormat::LinA8R8G8B8
+            | TextureFormat::LinX8R8G8B8 => Some(4),
+            TextureFormat::Dxt1 | TextureFormat::Dxt3 | TextureFormat::Dxt5 => None,
+            _ => Some(2),
+        }
+    }
+
+    /// Converts one pixel to R, G, B, A.
+    fn to_rgba(self, pixel: &[u8]) -> [u8; 4] {
+        let word = || u16::from_le_bytes([pixel[0], pixel[1]]);
+        let five = |value: u16| {
+            let value = value as u8 & 0x1F;
+            value << 3 | value >> 2
+        };
+        let four = |value: u16| (value as u8 & 0xF) * 17;
+
+        match self {
+            TextureFormat::A8R8G8B8 | TextureFormat::LinA8R8G8B8 => [pixel[2], pixel[1], pixel[0], pixel[3]],
+            TextureFormat::X8R8G8B8 | TextureFormat::LinX8R8G8B8 => [pixel[2], pixel[1], pixel[0], 255],
+            TextureFormat::R5G6B5 | TextureFormat::LinR5G6B5 => {
+                let [r, g, b] = rgb565(word());
+                [r, g, b, 255]
+            }
+            TextureFormat::A1R5G5B5 | TextureFormat::LinA1R5G5B5 | TextureFormat::X1R5G5B5 => {
+                let value = word();
+                let alpha = match self {
+                    TextureFormat::X1R5G5B5 => 255,
+                    _ => (value >> 15) as u8 * 255,
+                };
+
+                [five(value >> 10), five(value >> 5), five(value), alpha]
+            }
+            TextureFormat::A4R4G4B4 | TextureFormat::LinA4R4G4B4 => {
+                let value = word();
+                [four(value >> 8), four(value >> 4), four(value), four(value >> 12)]
+            }
+            TextureFormat::Dxt1 | TextureFormat::Dxt3 | TextureFormat::Dxt5 => [0; 4],
+        }
+    }
+
+    /// Converts one R, G, B, A pixel to this format, appending it to `out`.
+    fn encode_pixel(self, rgba: [u8; 4], out: &mut Vec<u8>) {
+        let [r, g, b, a] = rgba;
+        let scale = |value: u8, max: u16| (value as u16 * max + 127) / 255;
+
+        let word = match self {
+            TextureFormat::A8R8G8B8 | TextureFormat::LinA8R8G8B8 => return out.extend_from_slice(&[b, g, r, a]),
+            TextureFormat::X8R8G8B8 | TextureFormat::LinX8R8G8B8 => return out.extend_from_slice(&[b, g, r, 255]),
+            TextureFormat::R5G6B5 | TextureFormat::LinR5G6B5 => to_rgb565([r, g, b]),
+            TextureFormat::A1R5G5B5 | TextureFormat::LinA1R5G5B5 | TextureFormat::X1R5G5B5 => {
+                let alpha = match self {
+                    TextureFormat::X1R5G5B5 => 1,
+                    _ => (a >= 128) as u16,
+                };
+
+                alpha << 15 | scale(r, 0x1F) << 10 | scale(g, 0x1F) << 5 | scale(b, 0x1F)
+            }
+            TextureFormat::A4R4G4B4 | TextureFormat::LinA4R4G4B4 => {
+                scale(a, 0xF) << 12 | scale(r, 0xF) << 8 | scale(g, 0xF) << 4 | scale(b, 0xF)
+            }
+            TextureFormat::Dxt1 | TextureFormat::Dxt3 | TextureFormat::Dxt5 => return,
+        };
+
+        out.extend_from_slice(&word.to_le_bytes());
+    }
+}
+
+/// A texture resource of an XPR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XprTexture {
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Number of mip levels, including the full size one.
+    pub levels: u32,
+    /// Bytes per row of linear formats.
+    pub pitch: u32,
+    pub cube_map: bool,
+    /// Offset of the texture data from the start of the XPR.
+    pub data_offset: u32,
+}
+
+impl XprTexture {
+    /// Parses a texture resource header. `data_base` is the header size of the XPR.
+    fn parse(header: &[u8], data_base: u32) -> Result<Self, XprError> {
+        let field = |offset: usize| u32_at(header, offset).ok_or(XprError::UnexpectedEof);
+
+        let data = field(0x04)?;
+        let format = field(0x0C)?;
+        let size = field(0x10)?;
+
+        let code = (format >> 8) as u8;
+        let texture_format = TextureFormat::from_code(code).ok_or(XprError::UnsupportedFormat(code))?;
+
+        // Linear textures give their size directly, and have no mip levels
+        let (width, height, pitch, levels) = match size {
+            0 => {
+                let width = 1 << ((format >> 20) & 0xF);
+                let height = 1 << ((format >> 24) & 0xF);
+                let pitch = texture_format.bytes_per_pixel().unwrap_or(0) as u32 * width;
+
+                (width, height, pitch, ((format >> 16) & 0xF).max(1))
+            }
+            size => ((size & 0xFFF) + 1, ((size >> 12) & 0xFFF) + 1, ((size >> 24) + 1) * 64, 1),
+        };
+
+        // Only linear textures can have other sizes; the rest are laid out by powers of two
+        let power_of_two = width.is_power_of_two() && height.is_power_of_two();
+
+        if !texture_format.is_linear() && !power_of_two {
+            return Err(XprError::UnsupportedDimensions);
+        }
+
+        Ok(Self {
+            format: texture_format,
+            width,
+            height,
+            levels,
+            pitch,
+            cube_map: format & 0x4 != 0,
+            data_offset: data_base + data,
+        })
+    }
+
+    /// Dimensions of a mip level, halved per level down to 1.
+    pub fn level_dimensions(&self, level: u32) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    /// Size of the data of a mip level.
+    pub fn level_size(&self, level: u32) -> usize {
+        let (width, height) = self.level_dimensions(level);
+        surface_size(self.format, width, height, self.pitch)
+    }
+
+    /// Offset of a mip level from the start of the texture data. Levels
+    /// follow each other, from the full size one down.
+    pub fn level_offset(&self, level: u32) -> usize {
+        (0..level).map(|level| self.level_size(level)).sum()
+    }
+
+    /// Size of every level of one face.
+    pub fn face_size(&self) -> usize {
+        let size = self.level_offset(self.levels);
+
+        match self.cube_map {
+            true => size.next_multiple_of(TEXTURE_ALIGNMENT),
+            false => size,
+        }
+    }
+
+    /// Size of the whole texture: every level, of every face of a cube map.
+    pub fn data_size(&self) -> usize {
+        match self.cube_map {
+            true => self.face_size() * 6,
+            false => self.face_size(),
+        }
+    }
+}
+
+/// Size of a single level, in any format.
+fn surface_size(format: TextureFormat, width: u32, height: u32, pitch: u32) -> usize {
+    match (format.dxt(), format.bytes_per_pixel()) {
+        (Some(dxt), _) => dxt.data_size(width, height),
+        (None, _) if format.is_linear() => pitch as usize * height as usize,
+        (None, bytes_per_pixel) => bytes_per_pixel.unwrap_or(0) * width as usize * height as usize,
+    }
+}
+
+/// A parsed XPR0 bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xpr<'a> {
+    bytes: &'a [u8],
+    header_size: u32,
+    textures: Vec<XprTexture>,
+}
+
+impl<'a> Xpr<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, XprError> {
+        if bytes.len() < XPR_HEADER_SIZE {
+            return Err(XprError::UnexpectedEof);
+        }
+
+        if &bytes[..XPR_MAGIC.len()] != XPR_MAGIC {
+            return Err(XprError::InvalidMagic);
+        }
+
+        let header_size = u32_at(bytes, 8).unwrap();
+        let headers = bytes.get(..header_size as usize).ok_or(XprError::UnexpectedEof)?;
+
+        let mut textures = Vec::new();
+        let mut offset = XPR_HEADER_SIZE;
+
+        while let Some(common) = u32_at(headers, offset) {
+            if common == END_OF_HEADERS {
+                break;
+            }
+
+            match (common >> COMMON_TYPE_SHIFT) & COMMON_TYPE_MASK {
+                COMMON_TYPE_TEXTURE => {
+                    let header = headers.get(offset..offset + TEXTURE_HEADER_SIZE).ok_or(XprError::UnexpectedEof)?;
+                    textures.push(XprTexture::parse(header, header_size)?);
+                    offset += TEXTURE_HEADER_SIZE;
+                }
+                COMMON_TYPE_SURFACE => offset += TEXTURE_HEADER_SIZE,
+                _ => offset += RESOURCE_HEADER_SIZE,
+            }
+        }
+
+        Ok(Self {
+            bytes,
+            header_size,
+            textures,
+        })
+    }
+
+    pub fn header_size(&self) -> u32 {
+        self.header_size
+    }
+
+    pub fn textures(&self) -> &[XprTexture] {
+        &self.textures
+    }
+
+    /// Raw data of a texture, with all of its levels and faces.
+    pub fn texture_data(&self, texture: &XprTexture) -> Result<&'a [u8], XprError> {
+        self.bytes
+            .get(texture.data_offset as usize..)
+            .and_then(|data| data.get(..texture.data_size()))
+            .ok_or(XprError::UnexpectedEof)
+    }
+
+    /// Decodes the full size level of a texture, or of the first face of a cube map.
+    pub fn decode_texture(&self, texture: &XprTexture) -> Result<RgbaImage, XprError> {
+        self.decode_level(texture, 0)
+    }
+
+    /// Decodes a mip level of a texture, or of the first face of a cube map.
+    pub fn decode_level(&self, texture: &XprTexture, level: u32) -> Result<RgbaImage, XprError> {
+        decode_level(texture, self.texture_data(texture)?, level)
+    }
+
+    /// Decodes the first texture, such as the only one of a title image.
+    pub fn decode_first(&self) -> Result<RgbaImage, XprError> {
+        let texture = self.textures.first().ok_or(XprError::Corrupt("no texture"))?;
+        self.decode_texture(texture)
+    }
+}
+
+/// Decodes the full size level of texture data to RGBA.
+pub fn decode(texture: &XprTexture, data: &[u8]) -> Result<RgbaImage, XprError> {
+    decode_level(texture, data, 0)
+}
+
+/// Decodes a mip level of texture data to RGBA. `data` starts at the full size level.
+pub fn decode_level(texture: &XprTexture, data: &[u8], level: u32) -> Result<RgbaImage, XprError> {
+    if level >= texture.levels {
+        return Err(XprError::InvalidLevel);
+    }
+
+    let (width, height) = texture.level_dimensions(level);
+
+    if texture.width == 0 || texture.height == 0 {
+        return Err(XprError::Corrupt("texture without dimensions"));
+    }
+
+    let data = data
+        .get(texture.level_offset(level)..)
+        .and_then(|data| data.get(..texture.level_size(level)))
+        .ok_or(XprError::UnexpectedEof)?;
+
+    if let Some(dxt) = texture.format.dxt() {
+        return dxt::decode(dxt, data, width, height).ok_or(XprError::UnexpectedEof);
+    }
+
+    let format = texture.format;
+    let bytes_per_pixel = format.bytes_per_pixel().unwrap();
+    let mut image = RgbaImage::new(width, height);
+
+    if format.is_swizzled() {
+        let linear = swizzle::unswizzle(data, width, height, bytes_per_pixel)?;
+
+        for (pixel, rgba) in linear.chunks_exact(bytes_per_pixel).zip(image.pixels.chunks_exact_mut(4)) {
+            rgba.copy_from_slice(&format.to_rgba(pixel));
+        }
+    } else {
+        for (row, rgba_row) in data
+            .chunks(texture.pitch as usize)
+            .zip(image.pixels.chunks_exact_mut(width as usize * 4))
+        {
+            for (pixel, rgba) in row.chunks_exact(bytes_per_pixel).zip(rgba_row.chunks_exact_mut(4)) {
+                rgba.copy_from_slice(&format.to_rgba(pixel));
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+/// Encodes a single level. Linear rows are padded to `pitch`.
+fn encode_surface(image: &RgbaImage, format: TextureFormat, pitch: u32) -> Vec<u8> {
+    if let Some(dxt) = format.dxt() {
+        return dxt::encode(dxt, image);
+    }
+
+    let bytes_per_pixel = format.bytes_per_pixel().unwrap();
+    let mut data = Vec::with_capacity(surface_size(format, image.width, image.height, pitch));
+
+    if format.is_swizzled() {
+        for pixel in image.pixels.chunks_exact(4) {
+            format.encode_pixel(pixel.try_into().unwrap(), &mut data);
+        }
+
+        return swizzle::swizzle(&data, image.width, image.height, bytes_per_pixel);
+    }
+
+    for row in image.pixels.chunks_exact(image.width as usize * 4) {
+        let row_start = data.len();
+
+        for pixel in row.chunks_exact(4) {
+            format.encode_pixel(pixel.try_into().unwrap(), &mut data);
+        }
+
+        data.resize(row_start + pitch as usize, 0);
+    }
+
+    data
+}
+
+/// Describes a texture of `format` holding `image` with `levels` mip levels,
+/// 0 for a full chain. The data offset is left at 0.
+pub fn texture_for(image: &RgbaImage, format: TextureFormat, levels: u32) -> Result<XprTexture, XprError> {
+    let (width, height) = (image.width, image.height);
+
+    if width == 0 || height == 0 || width > 4096 || height > 4096 {
+        return Err(XprError::UnsupportedDimensions);
+    }
+
+    let linear = format.is_linear();
+    let power_of_two = width.is_power_of_two() && height.is_power_of_two();
+
+    if !(linear || power_of_two) {
+        return Err(XprError::UnsupportedDimensions);
+    }
+
+    let full_chain = width.max(height).ilog2() + 1;
+    let levels = match (linear, levels) {
+        (true, _) => 1,
+        (false, 0) => full_chain,
+        (false, levels) => levels.min(full_chain),
+    };
+
+    let pitch = match linear {
+        true => (width * format.bytes_per_pixel().unwrap() as u32).next_multiple_of(64),
+        false => width * format.bytes_per_pixel().unwrap_or(0) as u32,
+    };
+
+    Ok(XprTexture {
+        format,
+        width,
+        height,
+        levels,
+        pitch,
+        cube_map: false,
+        data_offset: 0,
+    })
+}
+
+/// Encodes `image` as texture data, generating its mip levels by downsampling.
+pub fn encode(image: &RgbaImage, texture: &XprTexture) -> Vec<u8> {
+    let mut data = Vec::with_capacity(texture.data_size());
+
+    for level in image.mip_chain(texture.levels) {
+        data.extend_from_slice(&encode_surface(&level, texture.format, texture.pitch));
+    }
+
+    data
+}
+
+/// Writes XPR0 bundles of textures.
+#[derive(Debug, Clone, Default)]
+pub struct XprBuilder {
+    textures: Vec<(XprTexture, Vec<u8>)>,
+}
+
+impl XprBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a texture, with `levels` mip levels generated by downsampling, or 0
+    /// for a full chain down to 1x1. Linear formats always have a single level.
+    pub fn add_texture(&mut self, image: &RgbaImage, format: TextureFormat, levels: u32) -> Result<(), XprError> {
+        let texture = texture_for(image, format, levels)?;
+        let data = encode(image, &texture);
+
+        self.textures.push((texture, data));
+
+        Ok(())
+    }
+
+    /// Builds the bundle. Data starts on a 2048 byte boundary, as the XDK
+    /// bundler lays it out, and each texture on a 128 byte one.
+    pub fn build(&self) -> Vec<u8> {
+        let header_size =
+            (XPR_HEADER_SIZE + self.textures.len() * TEXTURE_HEADER_SIZE + 4).next_multiple_of(HEADER_ALIGNMENT);
+
+        let mut bytes = vec![0u8; XPR_HEADER_SIZE];
+        let mut data = Vec::new();
+
+        for (texture, texture_data) in &self.textures {
+            data.resize(data.len().next_multiple_of(TEXTURE_ALIGNMENT), 0);
+
+            let (format, size) = match texture.format.is_linear() {
+                true => (
+                    FORMAT_2D | (texture.format.code() as u32) << 8 | 1 << 16,
+                    (texture.width - 1) | (texture.height - 1) << 12 | (texture.pitch / 64 - 1) << 24,
+                ),
+                false => (
+                    FORMAT_2D
+                        | (texture.format.code() as u32) << 8
+                        | texture.levels << 16
+                        | texture.width.ilog2() << 20
+                        | texture.height.ilog2() << 24,
+                    0,
+                ),
+            };
+
+            for field in [COMMON_TEXTURE, data.len() as u32, 0, format, size] {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+
+            data.extend_from_slice(texture_data);
+        }
+
+        bytes.extend_from_slice(&END_OF_HEADERS.to_le_bytes());
+        bytes.resize(header_size, 0);
+        bytes.extend_from_slice(&data);
+
+        let total_size = bytes.len() as u32;
+
+        bytes[..4].copy_from_slice(XPR_MAGIC);
+        bytes[4..8].copy_from_slice(&total_size.to_le_bytes());
+        bytes[8..12].copy_from_slice(&(header_size as u32).to_le_bytes());
+
+        bytes
+    }
+}
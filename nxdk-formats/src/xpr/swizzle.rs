@@ -0,0 +1,115 @@
+//! The Morton order textures are stored in by the NV2A.
+//!
+//! Bits of the x and y coordinates are interleaved, starting with x, for as
+//! long as both dimensions have bits left. The remaining bits of the larger
+//! dimension follow in order.
+
+use crate::xpr::XprError;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Bit masks spreading x and y coordinates into a swizzled offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwizzleMasks {
+    pub x: u32,
+    pub y: u32,
+}
+
+impl SwizzleMasks {
+    /// Masks for a texture of `width` by `height` pixels, both powers of two.
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut masks = SwizzleMasks { x: 0, y: 0 };
+        let mut bit = 1;
+        let mut size = 1;
+
+        while size < width || size < height {
+            if size < width {
+                masks.x |= bit;
+                bit <<= 1;
+            }
+
+            if size < height {
+                masks.y |= bit;
+                bit <<= 1;
+            }
+
+            size <<= 1;
+        }
+
+        masks
+    }
+
+    /// Index of the pixel at `x`, `y` in swizzled order.
+    pub fn offset(&self, x: u32, y: u32) -> usize {
+        (deposit(x, self.x) | deposit(y, self.y)) as usize
+    }
+}
+
+/// Spreads the low bits of `value` over the set bits of `mask`.
+fn deposit(mut value: u32, mask: u32) -> u32 {
+    let mut result = 0;
+    let mut remaining = mask;
+
+    while remaining != 0 {
+        let bit = remaining & remaining.wrapping_neg();
+
+        if value & 1 != 0 {
+            result |= bit;
+        }
+
+        value >>= 1;
+        remaining &= remaining - 1;
+    }
+
+    result
+}
+
+/// Reorders `bytes_per_pixel` sized pixels from swizzled to row major order.
+///
+/// Fails with `UnsupportedDimensions` unless both dimensions are powers of
+/// two, and with `UnexpectedEof` if `swizzled` is too short to hold them.
+pub fn unswizzle(swizzled: &[u8], width: u32, height: u32, bytes_per_pixel: usize) -> Result<Vec<u8>, XprError> {
+    if !width.is_power_of_two() || !height.is_power_of_two() {
+        return Err(XprError::UnsupportedDimensions);
+    }
+
+    let size = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(bytes_per_pixel))
+        .ok_or(XprError::UnsupportedDimensions)?;
+
+    if swizzled.len() < size {
+        return Err(XprError::UnexpectedEof);
+    }
+
+    let masks = SwizzleMasks::new(width, height);
+    let mut linear = vec![0u8; size];
+
+    for y in 0..height {
+        for x in 0..width {
+            let from = masks.offset(x, y) * bytes_per_pixel;
+            let to = (y as usize * width as usize + x as usize) * bytes_per_pixel;
+
+            linear[to..to + bytes_per_pixel].copy_from_slice(&swizzled[from..from + bytes_per_pixel]);
+        }
+    }
+
+    Ok(linear)
+}
+
+/// Reorders `bytes_per_pixel` sized pixels from row major to swizzled order.
+pub fn swizzle(linear: &[u8], width: u32, height: u32, bytes_per_pixel: usize) -> Vec<u8> {
+    let masks = SwizzleMasks::new(width, height);
+    let mut swizzled = vec![0u8; width as usize * height as usize * bytes_per_pixel];
+
+    for y in 0..height {
+        for x in 0..width {
+            let from = (y as usize * width as usize + x as usize) * bytes_per_pixel;
+            let to = masks.offset(x, y) * bytes_per_pixel;
+
+            swizzled[to..to + bytes_per_pixel].copy_from_slice(&linear[from..from + bytes_per_pixel]);
+        }
+    }
+
+    swizzled
+}
@@ -0,0 +1,62 @@
+//! The in-memory device the integration tests read and write images through.
+
+use embedded_io::{ErrorKind, ErrorType, Read, Seek, SeekFrom, Write};
+
+/// A disk or disc image in memory. Reads stop at the end of the data, and writes past it
+/// grow the image, like a file.
+pub struct Image {
+    pub data: Vec<u8>,
+    pub position: usize,
+}
+
+impl Image {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self { data, position: 0 }
+    }
+}
+
+impl ErrorType for Image {
+    type Error = ErrorKind;
+}
+
+impl Read for Image {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let rest = self.data.get(self.position..).unwrap_or_default();
+        let len = buf.len().min(rest.len());
+
+        buf[..len].copy_from_slice(&rest[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+impl Write for Image {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let end = self.position + buf.len();
+
+        if end > self.data.len() {
+            self.data.resize(end, 0);
+        }
+
+        self.data[self.position..end].copy_from_slice(buf);
+        self.position = end;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Seek for Image {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Self::Error> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => (self.data.len() as u64).checked_add_signed(offset),
+            SeekFrom::Current(offset) => (self.position as u64).checked_add_signed(offset),
+        };
+
+        self.position = position.ok_or(ErrorKind::InvalidInput)? as usize;
+        Ok(self.position as u64)
+    }
+}
@@ -0,0 +1,320 @@
+//! Formats FATX volumes in memory and reads them back.
+
+mod common;
+
+use common::Image;
+use embedded_io::{Read, Seek, SeekFrom, Write};
+use nxdk_formats::fatx::{FatType, FatxAttributes, FatxError, FatxTimestamp, FatxVolume, Geometry};
+
+/// Where the partition starts in the image, so offsets relative to it get exercised.
+const PARTITION_OFFSET: u64 = 0x2000;
+
+/// 1MiB with 512 byte clusters: a FATX16 volume where crossing clusters is cheap.
+const PARTITION_SIZE: u64 = 1024 * 1024;
+
+const CLUSTER_SIZE: usize = 512;
+
+/// A blank disk image, big enough for the partition.
+fn blank() -> Image {
+    // Anything the volume reads without writing first would show up as 0xAA
+    Image::new(vec![0xAA; (PARTITION_OFFSET + PARTITION_SIZE) as usize])
+}
+
+/// The raw FATX16 entry for `cluster`.
+fn fat_entry(image: &Image, geometry: &Geometry, cluster: u32) -> u16 {
+    let offset = (PARTITION_OFFSET + geometry.fat_offset) as usize + cluster as usize * 2;
+    u16::from_le_bytes([image.data[offset], image.data[offset + 1]])
+}
+
+/// Every cluster of the chain starting at `first`, read straight from the on-disk FAT.
+fn chain(image: &Image, geometry: &Geometry, first: u32) -> Vec<u32> {
+    let mut chain = vec![first];
+
+    loop {
+        match fat_entry(image, geometry, *chain.last().unwrap()) {
+            0xFFFF => return chain,
+            0 => panic!("chain {:?} runs into a free cluster", chain),
+            next => chain.push(next as u32),
+        }
+    }
+}
+
+fn format() -> FatxVolume<Image> {
+    FatxVolume::format(blank(), PARTITION_OFFSET, PARTITION_SIZE, 1, 0x1234_5678).unwrap()
+}
+
+fn reopen(volume: FatxVolume<Image>) -> FatxVolume<Image> {
+    FatxVolume::open(volume.into_inner(), PARTITION_OFFSET, PARTITION_SIZE).unwrap()
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+fn read_all(volume: &mut FatxVolume<Image>, path: &str) -> Vec<u8> {
+    let mut file = volume.open_file(path).unwrap();
+    let mut data = vec![0u8; file.len() as usize];
+    file.read_exact(&mut data).unwrap();
+
+    let mut rest = [0u8; 1];
+    assert_eq!(file.read(&mut rest).unwrap(), 0, "{} reads past its size", path);
+
+    data
+}
+
+fn clock() -> FatxTimestamp {
+    FatxTimestamp::new(2004, 11, 15, 13, 45, 30)
+}
+
+#[test]
+fn format_and_reopen() {
+    let volume = format();
+
+    let geometry = *volume.geometry();
+    assert_eq!(geometry.fat_type, FatType::Fatx16);
+    assert_eq!(geometry.cluster_size, CLUSTER_SIZE as u64);
+    assert_eq!(volume.free_space(), volume.total_space() - CLUSTER_SIZE as u64);
+
+    let volume = reopen(volume);
+    assert_eq!(*volume.geometry(), geometry);
+    assert_eq!(volume.superblock().volume_id, 0x1234_5678);
+    assert_eq!(volume.superblock().root_cluster, 1);
+
+    let mut volume = volume;
+    assert!(volume.read_dir("").unwrap().is_empty());
+    assert!(volume.metadata("").unwrap().is_dir());
+
+    // Nothing outside the partition is touched
+    let image = volume.into_inner();
+    assert!(image.data[..PARTITION_OFFSET as usize].iter().all(|&b| b == 0xAA));
+    assert_eq!(&image.data[PARTITION_OFFSET as usize..][..4], b"FATX");
+}
+
+#[test]
+fn open_rejects_other_data() {
+    let result = FatxVolume::open(blank(), PARTITION_OFFSET, PARTITION_SIZE);
+    assert_eq!(result.err(), Some(FatxError::InvalidSuperblock));
+
+    // The image ends before the FAT does
+    let mut image = format().into_inner();
+    image.data.truncate(PARTITION_OFFSET as usize + 0x1000 + 100);
+    let result = FatxVolume::open(image, PARTITION_OFFSET, PARTITION_SIZE);
+    assert!(matches!(result, Err(FatxError::UnexpectedEof)));
+}
+
+#[test]
+fn files_cross_cluster_boundaries() {
+    let mut volume = format();
+    volume.set_clock(clock);
+
+    volume.create_dir("UDATA").unwrap();
+    volume.create_dir("UDATA/4d530004").unwrap();
+
+    let data = pattern(CLUSTER_SIZE * 3 + 100);
+    {
+        let mut file = volume.create_file("UDATA\\4d530004\\SaveMeta.xbx").unwrap();
+
+        // Uneven writes, so some of them straddle a boundary
+        for chunk in data.chunks(300) {
+            file.write_all(chunk).unwrap();
+        }
+
+        assert_eq!(file.len(), data.len() as u64);
+    }
+
+    // Overwrite across the first boundary, and extend past the end with a gap
+    {
+        let mut file = volume.open_file("udata/4D530004/savemeta.xbx").unwrap();
+        file.seek(SeekFrom::Start(CLUSTER_SIZE as u64 - 2)).unwrap();
+        file.write_all(b"span").unwrap();
+        file.seek(SeekFrom::Start(CLUSTER_SIZE as u64 * 5)).unwrap();
+        file.write_all(b"end").unwrap();
+    }
+
+    let mut expected = data.clone();
+    expected[CLUSTER_SIZE - 2..CLUSTER_SIZE + 2].copy_from_slice(b"span");
+    expected.resize(CLUSTER_SIZE * 5, 0);
+    expected.extend_from_slice(b"end");
+
+    let mut volume = reopen(volume);
+    assert_eq!(read_all(&mut volume, "UDATA/4d530004/SaveMeta.xbx"), expected);
+
+    let entry = volume.metadata("UDATA/4d530004/SaveMeta.xbx").unwrap();
+    assert_eq!(entry.name, "SaveMeta.xbx");
+    assert_eq!(entry.size, expected.len() as u32);
+    assert!(entry.is_file());
+    assert_eq!(entry.creation_time, clock());
+    assert_eq!(entry.last_write_time, clock());
+
+    let geometry = *volume.geometry();
+    let image = volume.into_inner();
+    assert_eq!(chain(&image, &geometry, entry.first_cluster).len(), 6);
+
+    let mut volume = FatxVolume::open(image, PARTITION_OFFSET, PARTITION_SIZE).unwrap();
+    let listing = volume.read_dir("UDATA").unwrap();
+    assert_eq!(listing.len(), 1);
+    assert_eq!(listing[0].name, "4d530004");
+    assert!(listing[0].is_dir());
+}
+
+#[test]
+fn directories_grow_past_one_cluster() {
+    let mut volume = format();
+
+    // 8 entries fit in a 512 byte cluster
+    for i in 0..20 {
+        volume.create_file(&format!("file{:02}", i)).unwrap().write_all(&[i as u8]).unwrap();
+    }
+
+    let mut volume = reopen(volume);
+    let names: Vec<_> = volume.read_dir("").unwrap().into_iter().map(|entry| entry.name).collect();
+    assert_eq!(names, (0..20).map(|i| format!("file{:02}", i)).collect::<Vec<_>>());
+
+    assert_eq!(read_all(&mut volume, "file13"), [13]);
+
+    let geometry = *volume.geometry();
+    let root = volume.superblock().root_cluster;
+    assert_eq!(chain(&volume.into_inner(), &geometry, root).len(), 3);
+}
+
+#[test]
+fn removing_frees_cluster_chains() {
+    let mut volume = format();
+    let empty = volume.free_space();
+
+    volume.create_dir("saves").unwrap();
+    volume.create_file("saves/a").unwrap().write_all(&pattern(CLUSTER_SIZE * 4)).unwrap();
+    volume.create_file("saves/b").unwrap().write_all(&pattern(10)).unwrap();
+    assert_eq!(volume.free_space(), empty - 6 * CLUSTER_SIZE as u64);
+
+    let geometry = *volume.geometry();
+    let a = volume.metadata("saves/a").unwrap();
+    let saves = volume.metadata("saves").unwrap();
+
+    let mut volume = reopen(volume);
+    let chain = {
+        let image = volume.into_inner();
+        let chain = chain(&image, &geometry, a.first_cluster);
+        assert_eq!(chain.len(), 4);
+
+        volume = FatxVolume::open(image, PARTITION_OFFSET, PARTITION_SIZE).unwrap();
+        chain
+    };
+
+    // Truncating releases the tail of the chain
+    volume.open_file("saves/a").unwrap().set_len(CLUSTER_SIZE as u64 + 1).unwrap();
+    assert_eq!(volume.free_space(), empty - 4 * CLUSTER_SIZE as u64);
+
+    assert_eq!(volume.remove_dir("saves"), Err(FatxError::DirectoryNotEmpty));
+    volume.remove_file("saves/a").unwrap();
+    volume.remove_file("saves/b").unwrap();
+    volume.remove_dir("saves").unwrap();
+    assert_eq!(volume.free_space(), empty);
+
+    let volume = reopen(volume);
+    assert_eq!(volume.free_space(), empty);
+
+    let mut volume = volume;
+    assert!(volume.read_dir("").unwrap().is_empty());
+    assert_eq!(volume.metadata("saves").err(), Some(FatxError::NotFound));
+
+    let image = volume.into_inner();
+    for cluster in chain.into_iter().chain([saves.first_cluster]) {
+        assert_eq!(fat_entry(&image, &geometry, cluster), 0, "cluster {} is still in use", cluster);
+    }
+
+    // Freed clusters get reused
+    let mut volume = FatxVolume::open(image, PARTITION_OFFSET, PARTITION_SIZE).unwrap();
+    volume.create_file("again").unwrap().write_all(&pattern(CLUSTER_SIZE * 2)).unwrap();
+    assert_eq!(read_all(&mut volume, "again"), pattern(CLUSTER_SIZE * 2));
+}
+
+#[test]
+fn recreating_a_file_truncates_it() {
+    let mut volume = format();
+    let empty = volume.free_space();
+
+    volume.create_file("a").unwrap().write_all(&pattern(CLUSTER_SIZE * 2)).unwrap();
+    volume.create_file("A").unwrap().write_all(b"short").unwrap();
+
+    assert_eq!(volume.read_dir("").unwrap().len(), 1);
+    assert_eq!(read_all(&mut volume, "a"), b"short");
+    assert_eq!(volume.free_space(), empty - CLUSTER_SIZE as u64);
+}
+
+#[test]
+fn invalid_operations() {
+    let mut volume = format();
+
+    volume.create_dir("dir").unwrap();
+    volume.create_file("file").unwrap();
+
+    assert_eq!(volume.create_dir("DIR"), Err(FatxError::AlreadyExists));
+    assert_eq!(volume.create_dir("missing/dir"), Err(FatxError::NotFound));
+    assert_eq!(volume.create_dir("file/dir"), Err(FatxError::NotADirectory));
+    assert_eq!(volume.open_file("dir").err(), Some(FatxError::IsADirectory));
+    assert_eq!(volume.create_file("dir").err(), Some(FatxError::IsADirectory));
+    assert_eq!(volume.remove_file("dir"), Err(FatxError::IsADirectory));
+    assert_eq!(volume.remove_dir("file"), Err(FatxError::NotADirectory));
+    assert_eq!(volume.open_file("nope").err(), Some(FatxError::NotFound));
+
+    assert_eq!(volume.create_file("bad:name").err(), Some(FatxError::InvalidName));
+    assert_eq!(volume.create_dir(&"a".repeat(43)), Err(FatxError::InvalidName));
+    volume.create_dir(&"a".repeat(42)).unwrap();
+
+    // Attributes keep the directory flag as it is
+    volume.set_attributes("dir", FatxAttributes::Hidden).unwrap();
+    volume.set_attributes("file", FatxAttributes::Directory | FatxAttributes::ReadOnly).unwrap();
+    assert_eq!(volume.metadata("dir").unwrap().attributes, FatxAttributes::Hidden | FatxAttributes::Directory);
+    assert_eq!(volume.metadata("file").unwrap().attributes, FatxAttributes::ReadOnly);
+}
+
+#[test]
+fn large_volumes_use_fatx32() {
+    let geometry = Geometry::new(8 * 1024 * 1024 * 1024, 32).unwrap();
+    assert_eq!(geometry.fat_type, FatType::Fatx32);
+    assert!(geometry.data_offset + geometry.cluster_count as u64 * geometry.cluster_size <= 8 * 1024 * 1024 * 1024);
+
+    assert_eq!(Geometry::new(PARTITION_SIZE, 3), None);
+    assert_eq!(Geometry::new(4096, 1), None);
+}
+
+#[test]
+fn timestamp_packing() {
+    let time = FatxTimestamp::new(2004, 11, 15, 13, 45, 31);
+
+    // Years since 2000 in 7 bits, then month, day, hours, minutes and seconds / 2
+    let raw = (4 << 25) | (11 << 21) | (15 << 16) | (13 << 11) | (45 << 5) | 15;
+    assert_eq!(time.into_raw(), raw);
+    assert_eq!(FatxTimestamp::from_raw(raw), time);
+
+    assert_eq!(
+        (time.year(), time.month(), time.day(), time.hour(), time.minute(), time.second()),
+        (2004, 11, 15, 13, 45, 30)
+    );
+    assert_eq!(time.to_string(), "2004-11-15 13:45:30");
+
+    assert_eq!(FatxTimestamp::default().into_raw(), (1 << 21) | (1 << 16));
+    assert_eq!(FatxTimestamp::new(1999, 0, 0, 30, 70, 70), FatxTimestamp::new(2000, 1, 1, 23, 59, 58));
+    assert_eq!(FatxTimestamp::new(2200, 13, 32, 0, 0, 0).to_string(), "2127-12-31 00:00:00");
+}
+
+#[test]
+fn timestamp_unix_conversion() {
+    assert_eq!(FatxTimestamp::from_unix(946_684_800), FatxTimestamp::default());
+    assert_eq!(FatxTimestamp::default().to_unix(), 946_684_800);
+
+    // Leap days, both ways
+    for (unix, text) in [
+        (951_782_400, "2000-02-29 00:00:00"),
+        (1_709_251_199, "2024-02-29 23:59:58"),
+        (1_700_000_000, "2023-11-14 22:13:20"),
+    ] {
+        let time = FatxTimestamp::from_unix(unix);
+        assert_eq!(time.to_string(), text);
+        assert_eq!(time.to_unix(), unix & !1);
+    }
+
+    // Before the FATX epoch clamps to it
+    assert_eq!(FatxTimestamp::from_unix(0), FatxTimestamp::default());
+}
@@ -0,0 +1,91 @@
+//! XBE boot logo decoding, and BMP export of decoded images.
+
+use nxdk_formats::image::RgbaImage;
+use nxdk_formats::xbe::logo::{decode_logo, LOGO_HEIGHT, LOGO_WIDTH};
+use nxdk_formats::xbe::XbeError;
+
+fn gray(intensity: u8) -> [u8; 4] {
+    [intensity, intensity, intensity, 255]
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[test]
+fn decodes_known_runs() {
+    let rle = [
+        // Byte run: length 2, intensity 0xF
+        0xF5,
+        // Word run 0x8192: length 100, intensity 0x8, wrapping onto the second row
+        0x92, 0x81,
+        // Byte run: length 1, intensity 0x3
+        0x33,
+    ];
+
+    let logo = decode_logo(&rle).unwrap();
+    assert_eq!((logo.width, logo.height), (LOGO_WIDTH, LOGO_HEIGHT));
+
+    assert_eq!(logo.pixel(0, 0), gray(0xFF));
+    assert_eq!(logo.pixel(1, 0), gray(0xFF));
+    assert_eq!(logo.pixel(2, 0), gray(0x88));
+    assert_eq!(logo.pixel(99, 0), gray(0x88));
+    assert_eq!(logo.pixel(1, 1), gray(0x88));
+    assert_eq!(logo.pixel(2, 1), gray(0x33));
+
+    // Anything the runs don't reach is opaque black
+    assert_eq!(logo.pixel(3, 1), gray(0));
+    assert_eq!(logo.pixel(LOGO_WIDTH - 1, LOGO_HEIGHT - 1), gray(0));
+}
+
+#[test]
+fn runs_past_the_end_are_dropped() {
+    // Two word runs of 1023 white pixels, more than the 1700 in the logo
+    let logo = decode_logo(&[0xFE, 0xFF, 0xFE, 0xFF]).unwrap();
+
+    assert!(logo.pixels.chunks_exact(4).all(|pixel| pixel == gray(0xFF)));
+    assert_eq!(decode_logo(&[]).unwrap().pixel(0, 0), gray(0));
+}
+
+#[test]
+fn rejects_invalid_runs() {
+    assert_eq!(decode_logo(&[0x00]), Err(XbeError::Corrupt("invalid logo run")));
+    assert_eq!(decode_logo(&[0xF5, 0x04]), Err(XbeError::Corrupt("invalid logo run")));
+
+    // A word run cut short
+    assert_eq!(decode_logo(&[0x92]), Err(XbeError::UnexpectedEof));
+}
+
+#[test]
+fn bmp_header_and_rows() {
+    let mut image = RgbaImage::new(3, 2);
+    image.set_pixel(0, 0, [0x11, 0x22, 0x33, 0x44]);
+    image.set_pixel(2, 1, [0xAA, 0xBB, 0xCC, 0xDD]);
+
+    let bmp = image.to_bmp();
+    assert_eq!(bmp.len(), 54 + 3 * 2 * 4);
+
+    // BITMAPFILEHEADER
+    assert_eq!(&bmp[..2], b"BM");
+    assert_eq!(u32_at(&bmp, 2), bmp.len() as u32);
+    assert_eq!(u32_at(&bmp, 10), 54);
+
+    // BITMAPINFOHEADER: 32 bit, uncompressed, bottom-up
+    assert_eq!(u32_at(&bmp, 14), 40);
+    assert_eq!(u32_at(&bmp, 18), 3);
+    assert_eq!(u32_at(&bmp, 22), 2);
+    assert_eq!(u16_at(&bmp, 26), 1);
+    assert_eq!(u16_at(&bmp, 28), 32);
+    assert_eq!(u32_at(&bmp, 30), 0);
+    assert_eq!(u32_at(&bmp, 34), 3 * 2 * 4);
+
+    // The bottom row comes first, in B, G, R, A order
+    assert_eq!(&bmp[54 + 8..54 + 12], [0xCC, 0xBB, 0xAA, 0xDD]);
+    assert_eq!(&bmp[54 + 12..54 + 16], [0x33, 0x22, 0x11, 0x44]);
+
+    assert_eq!(RgbaImage::new(0, 0).to_bmp().len(), 54);
+}
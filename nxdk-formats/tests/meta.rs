@@ -0,0 +1,77 @@
+//! Parses and writes `SaveMeta.xbx` and `TitleMeta.xbx` files.
+
+use nxdk_formats::meta::{MetaError, XbxMeta, NO_COPY_KEY, SAVE_NAME_KEY};
+
+fn utf16le(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+#[test]
+fn byte_order_mark_is_optional() {
+    let with_bom = XbxMeta::parse(&utf16le("\u{feff}TitleName=Halo\r\n")).unwrap();
+    let without_bom = XbxMeta::parse(&utf16le("TitleName=Halo\r\n")).unwrap();
+
+    assert_eq!(with_bom, without_bom);
+    assert_eq!(with_bom.title_name(), Some("Halo"));
+
+    // Written files always start with one
+    assert_eq!(&XbxMeta::for_title("Halo").to_bytes()[..2], &[0xFF, 0xFE]);
+    assert_eq!(XbxMeta::new().to_bytes(), [0xFF, 0xFE]);
+}
+
+#[test]
+fn sections_and_localized_values() {
+    let text = "Name=Level 3\r\nNoCopy = 1\r\n[FR]\r\nName=Niveau 3\r\n[de]\r\nOther=x\r\nnot a pair\r\n";
+    let meta = XbxMeta::parse(&utf16le(text)).unwrap();
+
+    assert_eq!(meta.save_name(), Some("Level 3"));
+    assert!(meta.no_copy());
+
+    let names: Vec<_> = meta.sections().iter().map(|section| section.name.as_str()).collect();
+    assert_eq!(names, ["FR", "de"]);
+    assert_eq!(meta.section("fr").unwrap().entries, [("Name".into(), "Niveau 3".into())]);
+    assert_eq!(meta.section("DE").unwrap().entries.len(), 1);
+
+    // Keys are matched without case, falling back to the default section
+    assert_eq!(meta.get_localized("fr", "NAME"), Some("Niveau 3"));
+    assert_eq!(meta.get_localized("de", "name"), Some("Level 3"));
+    assert_eq!(meta.get_localized("es", "name"), Some("Level 3"));
+    assert_eq!(meta.get_localized("de", "missing"), None);
+}
+
+#[test]
+fn crlf_round_trip() {
+    let mut meta = XbxMeta::for_save("Level 3 \u{2013} \u{fc}");
+    meta.set(NO_COPY_KEY, "1");
+    meta.section_mut("FR").set(SAVE_NAME_KEY, "Niveau 3");
+    meta.section_mut("fr").set("Extra", "a=b");
+
+    let bytes = meta.to_bytes();
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]])).collect();
+    let text = String::from_utf16(&units).unwrap();
+
+    assert_eq!(text, "\u{feff}Name=Level 3 \u{2013} \u{fc}\r\nNoCopy=1\r\n[FR]\r\nName=Niveau 3\r\nExtra=a=b\r\n");
+    assert_eq!(XbxMeta::parse(&bytes).unwrap(), meta);
+
+    // Setting an existing key replaces it in place
+    meta.set("name", "Level 4");
+    let reparsed = XbxMeta::parse(&meta.to_bytes()).unwrap();
+    assert_eq!(reparsed.default_section().entries[0], ("Name".into(), "Level 4".into()));
+    assert_eq!(reparsed, meta);
+
+    // Bare LF files parse the same
+    let lf = XbxMeta::parse(&utf16le(&text.replace("\r\n", "\n"))).unwrap();
+    assert_eq!(XbxMeta::parse(&bytes).unwrap().section("FR"), lf.section("FR"));
+}
+
+#[test]
+fn invalid_text() {
+    assert_eq!(XbxMeta::parse(&[0xFF, 0xFE, b'a']), Err(MetaError::OddLength));
+
+    // A lone high surrogate
+    assert_eq!(XbxMeta::parse(&[0x00, 0xD8, b'a', 0]), Err(MetaError::InvalidUtf16));
+
+    // Trailing NULs from fixed size buffers are ignored
+    let meta = XbxMeta::parse(&utf16le("Name=Slot 1\0\0\0")).unwrap();
+    assert_eq!(meta.save_name(), Some("Slot 1"));
+}
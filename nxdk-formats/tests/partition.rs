@@ -0,0 +1,131 @@
+//! Detects retail and PARTINFO hard drive layouts from their first sector.
+
+mod common;
+
+use common::Image;
+use nxdk_formats::partition::{
+    device_path, drive_letter, PartitionLayout, PartitionTable, PARTINFO_MAGIC, PARTINFO_SIZE, RETAIL_END_LBA,
+    SECTOR_SIZE,
+};
+
+/// Adds an entry to a PARTINFO sector, at the slot of partition `number`.
+fn put_entry(sector: &mut [u8], number: usize, name: &[u8], flags: u32, lba_start: u32, lba_size: u32) {
+    let entry = &mut sector[0x30 + (number - 1) * 0x20..][..0x20];
+
+    entry[..0x10].fill(b' ');
+    entry[..name.len()].copy_from_slice(name);
+    entry[0x10..0x14].copy_from_slice(&flags.to_le_bytes());
+    entry[0x14..0x18].copy_from_slice(&lba_start.to_le_bytes());
+    entry[0x18..0x1C].copy_from_slice(&lba_size.to_le_bytes());
+}
+
+/// A PARTINFO sector with the retail partitions, F: after E: and G: after F:.
+fn partinfo_sector() -> [u8; PARTINFO_SIZE] {
+    let mut sector = [0u8; PARTINFO_SIZE];
+    sector[..16].copy_from_slice(PARTINFO_MAGIC);
+
+    for partition in PartitionTable::retail().partitions {
+        put_entry(
+            &mut sector,
+            partition.number as usize,
+            partition.name.as_bytes(),
+            0x8000_0000,
+            partition.lba_start as u32,
+            partition.lba_size as u32,
+        );
+    }
+
+    put_entry(&mut sector, 6, b"XBOX F", 0x8000_0000, RETAIL_END_LBA as u32, 0x1000_0000);
+    put_entry(&mut sector, 7, b"XBOX G", 0x8000_0000, RETAIL_END_LBA as u32 + 0x1000_0000, 0x800_0000);
+
+    // Not in use, so skipped
+    put_entry(&mut sector, 8, b"UNUSED", 0, 0x4000_0000, 0x1000);
+
+    sector
+}
+
+#[test]
+fn retail_sector() {
+    // Retail drives have a zeroed or arbitrary first sector
+    for sector in [[0u8; PARTINFO_SIZE], [0xFF; PARTINFO_SIZE]] {
+        assert_eq!(PartitionTable::parse(&sector), PartitionTable::retail());
+    }
+
+    // Too short to hold a table, even with the magic
+    assert_eq!(PartitionTable::parse(PARTINFO_MAGIC).layout, PartitionLayout::Retail);
+
+    let table = PartitionTable::retail();
+    assert_eq!(table.layout, PartitionLayout::Retail);
+    assert_eq!(table.partitions.len(), 5);
+    assert_eq!(table.extended_partitions().count(), 0);
+
+    let e = table.by_drive_letter('e').unwrap();
+    assert_eq!(e.number, 1);
+    assert_eq!(e.name, "XBOX DATA");
+    assert_eq!(e.offset(), 0xABE8_0000);
+    assert_eq!(e.lba_start + e.lba_size, RETAIL_END_LBA);
+    assert_eq!(e.device_path(), "\\Device\\Harddisk0\\Partition1");
+
+    assert_eq!(table.by_number(2).unwrap().drive_letter, Some('C'));
+    assert_eq!(table.by_number(5).unwrap().drive_letter, Some('Z'));
+    assert!(table.by_drive_letter('F').is_none());
+}
+
+#[test]
+fn partinfo_sector_with_f_and_g() {
+    let table = PartitionTable::parse(&partinfo_sector());
+
+    assert_eq!(table.layout, PartitionLayout::Extended);
+    assert_eq!(table.partitions.len(), 7);
+    assert_eq!(table.partitions[..5], PartitionTable::retail().partitions[..]);
+
+    let extended: Vec<_> = table.extended_partitions().collect();
+    assert_eq!(extended.len(), 2);
+
+    let f = extended[0];
+    assert_eq!((f.number, f.drive_letter, f.name.as_str()), (6, Some('F'), "XBOX F"));
+    assert_eq!(f.lba_start, RETAIL_END_LBA);
+    assert_eq!(f.size(), 0x1000_0000 * SECTOR_SIZE);
+    assert_eq!(f.device_path(), "\\Device\\Harddisk0\\Partition6");
+
+    let g = extended[1];
+    assert_eq!((g.number, g.drive_letter, g.name.as_str()), (7, Some('G'), "XBOX G"));
+    assert_eq!(g.offset(), (RETAIL_END_LBA + 0x1000_0000) * SECTOR_SIZE);
+
+    assert_eq!(table.by_drive_letter('g'), Some(g));
+    assert!(table.by_number(8).is_none());
+}
+
+#[test]
+fn partinfo_entries_past_g_have_no_drive_letter() {
+    let mut sector = partinfo_sector();
+    put_entry(&mut sector, 8, b"XBOX H", 0x8000_0000, 0x4000_0000, 0x1000);
+
+    let table = PartitionTable::parse(&sector);
+    let h = table.by_number(8).unwrap();
+
+    assert_eq!(h.drive_letter, None);
+    assert_eq!(h.name, "XBOX H");
+    assert_eq!(table.extended_partitions().count(), 3);
+}
+
+#[test]
+fn read_rewinds_the_drive() {
+    // The first sector of a drive, followed by nothing
+    let mut drive = Image::new(partinfo_sector().to_vec());
+    drive.position = 100;
+
+    let table = PartitionTable::read(&mut drive).unwrap();
+    assert_eq!(table, PartitionTable::parse(&partinfo_sector()));
+}
+
+#[test]
+fn partition_numbers() {
+    assert_eq!(drive_letter(1), Some('E'));
+    assert_eq!(drive_letter(5), Some('Z'));
+    assert_eq!(drive_letter(7), Some('G'));
+    assert_eq!(drive_letter(0), None);
+    assert_eq!(drive_letter(8), None);
+
+    assert_eq!(device_path(6), "\\Device\\Harddisk0\\Partition6");
+}
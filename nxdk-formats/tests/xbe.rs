@@ -0,0 +1,148 @@
+//! Builds XBEs from a synthetic PE and checks them with the parser.
+
+use nxdk_formats::sha1::Sha1;
+use nxdk_formats::xbe::{
+    AllowedMedia, BuildError, GameRegion, LibraryVersion, SectionFlags, Xbe, XbeBuilder, XbeKind, XBE_BASE_ADDRESS,
+};
+
+const TEXT: &[u8] = &[0x55, 0x89, 0xE5, 0x31, 0xC0, 0x5D, 0xC3];
+
+const DATA: &[u8] = b"thunks and data";
+
+/// A minimal PE32 with `.text`, `.data` and a discardable `.reloc`.
+fn pe(image_base: u32) -> Vec<u8> {
+    let mut pe = vec![0u8; 0x400];
+    let put = |pe: &mut Vec<u8>, offset: usize, value: &[u8]| pe[offset..offset + value.len()].copy_from_slice(value);
+
+    put(&mut pe, 0, b"MZ");
+    put(&mut pe, 0x3C, &0x80u32.to_le_bytes());
+    put(&mut pe, 0x80, b"PE\0\0");
+    put(&mut pe, 0x84, &0x014Cu16.to_le_bytes());
+    put(&mut pe, 0x86, &3u16.to_le_bytes());
+    put(&mut pe, 0x88, &0x6000_0000u32.to_le_bytes());
+    put(&mut pe, 0x94, &0xE0u16.to_le_bytes());
+
+    let optional = 0x98;
+    put(&mut pe, optional, &0x010Bu16.to_le_bytes());
+    put(&mut pe, optional + 16, &0x1000u32.to_le_bytes());
+    put(&mut pe, optional + 28, &image_base.to_le_bytes());
+    put(&mut pe, optional + 56, &0x4000u32.to_le_bytes());
+    put(&mut pe, optional + 60, &0x400u32.to_le_bytes());
+    put(&mut pe, optional + 76, &0x1000u32.to_le_bytes());
+    put(&mut pe, optional + 80, &0x10_0000u32.to_le_bytes());
+    put(&mut pe, optional + 84, &0x1000u32.to_le_bytes());
+    put(&mut pe, optional + 92, &16u32.to_le_bytes());
+    // TLS and IAT directories, both in .data
+    put(&mut pe, optional + 96 + 9 * 8, &0x2100u32.to_le_bytes());
+    put(&mut pe, optional + 96 + 12 * 8, &0x2000u32.to_le_bytes());
+
+    let sections = [
+        (b".text\0\0\0", 0x100u32, 0x1000u32, 0x200u32, 0x400u32, 0x6000_0020u32),
+        (b".data\0\0\0", 0x200, 0x2000, 0x200, 0x600, 0xC000_0040),
+        (b".reloc\0\0", 0x10, 0x3000, 0x200, 0x800, 0x4200_0040),
+    ];
+
+    for (index, (name, virtual_size, virtual_address, raw_size, raw_offset, characteristics)) in
+        sections.into_iter().enumerate()
+    {
+        let header = optional + 0xE0 + index * 0x28;
+        put(&mut pe, header, name);
+        put(&mut pe, header + 8, &virtual_size.to_le_bytes());
+        put(&mut pe, header + 12, &virtual_address.to_le_bytes());
+        put(&mut pe, header + 16, &raw_size.to_le_bytes());
+        put(&mut pe, header + 20, &raw_offset.to_le_bytes());
+        put(&mut pe, header + 36, &characteristics.to_le_bytes());
+    }
+
+    pe.resize(0xA00, 0);
+    put(&mut pe, 0x400, TEXT);
+    put(&mut pe, 0x600, DATA);
+
+    pe
+}
+
+#[test]
+fn builds_xbe_readable_by_parser() {
+    let pe = pe(XBE_BASE_ADDRESS);
+    let title_image = vec![0xAB; 0x1234];
+
+    let mut builder = XbeBuilder::from_pe(&pe).unwrap();
+    builder
+        .title_id(0x4E58_0001)
+        .title_name("Hello nxdk-rs")
+        .version(3)
+        .allowed_media(AllowedMedia::HardDisk | AllowedMedia::DvdX2)
+        .game_region(GameRegion::NorthAmerica)
+        .title_image(title_image.clone())
+        .library_version(LibraryVersion {
+            name: "XAPILIB".into(),
+            major: 1,
+            minor: 0,
+            build: 5849,
+            flags: 0x4001,
+        })
+        .debug_path("C:\\build\\hello.exe");
+
+    let image = builder.build().unwrap();
+    let xbe = Xbe::parse(&image).unwrap();
+
+    assert_eq!(xbe.kind, XbeKind::Retail);
+    assert_eq!(xbe.entry_point, XBE_BASE_ADDRESS + 0x1000);
+    assert_eq!(xbe.kernel_thunk_address, XBE_BASE_ADDRESS + 0x2000);
+    assert_eq!(xbe.header.tls_address, XBE_BASE_ADDRESS + 0x2100);
+    assert_eq!(xbe.header.time_date, 0x6000_0000);
+    assert_eq!(xbe.header.pe_heap_reserve, 0x10_0000);
+
+    assert_eq!(xbe.title_id(), 0x4E58_0001);
+    assert_eq!(xbe.title_name(), "Hello nxdk-rs");
+    assert_eq!(xbe.certificate.version, 3);
+    assert_eq!(xbe.certificate.allowed_media, AllowedMedia::HardDisk | AllowedMedia::DvdX2);
+    assert_eq!(xbe.certificate.game_region, GameRegion::NorthAmerica);
+
+    assert_eq!(xbe.xapi_library_version.as_ref().unwrap().build, 5849);
+    assert_eq!(xbe.debug_pathname, "C:\\build\\hello.exe");
+    assert_eq!(xbe.debug_filename, "hello.exe");
+    assert_eq!(xbe.debug_unicode_filename, "hello.exe");
+
+    let names: Vec<_> = xbe.sections.iter().map(|section| section.name.as_str()).collect();
+    assert_eq!(names, [".text", ".data", "$$XTIMAGE"]);
+
+    let text = xbe.section(".text").unwrap();
+    assert!(text.flags.contains(SectionFlags::Executable | SectionFlags::Preload));
+    assert!(!text.flags.contains(SectionFlags::Writable));
+    assert_eq!(&xbe.section_data(&image, text).unwrap()[..TEXT.len()], TEXT);
+
+    let data = xbe.section(".data").unwrap();
+    assert!(data.flags.contains(SectionFlags::Writable));
+    assert_eq!(data.tail_shared_page_reference_count_address, xbe.sections[2].head_shared_page_reference_count_address);
+
+    let raw = xbe.section_data(&image, data).unwrap();
+    let mut digest = Sha1::new();
+    digest.update(&(raw.len() as u32).to_le_bytes());
+    digest.update(raw);
+    assert_eq!(data.digest, digest.finish());
+
+    let inserted = xbe.section("$$XTIMAGE").unwrap();
+    assert!(inserted.flags.contains(SectionFlags::InsertedFile));
+    assert!(!inserted.flags.contains(SectionFlags::Preload));
+    assert_eq!(xbe.section_data(&image, inserted).unwrap(), title_image.as_slice());
+    assert!(inserted.virtual_address >= data.virtual_address + data.virtual_size);
+}
+
+#[test]
+fn debug_kind_round_trips() {
+    let pe = pe(XBE_BASE_ADDRESS);
+    let image = XbeBuilder::from_pe(&pe).unwrap().kind(XbeKind::Debug).build().unwrap();
+    let xbe = Xbe::parse(&image).unwrap();
+
+    assert_eq!(xbe.kind, XbeKind::Debug);
+    assert_eq!(xbe.entry_point, XBE_BASE_ADDRESS + 0x1000);
+}
+
+#[test]
+fn rejects_relocated_pe() {
+    let pe = pe(0x40_0000);
+
+    assert_eq!(XbeBuilder::from_pe(&pe).unwrap_err(), BuildError::UnsupportedBaseAddress(0x40_0000));
+    assert!(matches!(XbeBuilder::from_pe(b"not a PE"), Err(BuildError::InvalidPe(_))));
+}
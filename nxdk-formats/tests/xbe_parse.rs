@@ -0,0 +1,272 @@
+//! Parses hand built XBE headers, and rejects corrupt ones.
+
+mod common;
+
+use common::Image;
+use embedded_io::ErrorKind;
+use nxdk_formats::xbe::certificate::CERTIFICATE_SIZE;
+use nxdk_formats::xbe::header::IMAGE_HEADER_SIZE;
+use nxdk_formats::xbe::section::SECTION_HEADER_SIZE;
+use nxdk_formats::xbe::{
+    AllowedMedia, Certificate, ImageHeader, LibraryVersion, SectionHeader, TlsDirectory, Xbe, XbeError, XbeKind,
+    XBE_BASE_ADDRESS, XBE_MAGIC,
+};
+
+const HEADERS_SIZE: usize = 0x1000;
+
+const TEXT_ADDRESS: u32 = XBE_BASE_ADDRESS + 0x1000;
+
+const CERTIFICATE_OFFSET: usize = 0x180;
+const SECTION_HEADERS_OFFSET: usize = 0x400;
+const SECTION_NAME_OFFSET: usize = 0x480;
+const DEBUG_PATH_OFFSET: usize = 0x490;
+const DEBUG_UNICODE_FILENAME_OFFSET: usize = 0x4C0;
+const LIBRARY_VERSIONS_OFFSET: usize = 0x500;
+
+/// Offset of the TLS directory in `.text`.
+const TLS_OFFSET: u32 = 0x10;
+
+const TLS: TlsDirectory = TlsDirectory {
+    raw_data_start_address: TEXT_ADDRESS + 0x100,
+    raw_data_end_address: TEXT_ADDRESS + 0x180,
+    tls_index_address: TEXT_ADDRESS + 0x200,
+    tls_callback_address: 0,
+    size_of_zero_fill: 0x20,
+    characteristics: 0,
+};
+
+fn address(offset: usize) -> u32 {
+    XBE_BASE_ADDRESS + offset as u32
+}
+
+fn put(image: &mut [u8], offset: usize, value: &[u8]) {
+    image[offset..offset + value.len()].copy_from_slice(value);
+}
+
+fn library(name: &str, build: u16) -> LibraryVersion {
+    LibraryVersion {
+        name: name.into(),
+        major: 1,
+        minor: 0,
+        build,
+        flags: 0x4001,
+    }
+}
+
+/// A zeroed image header, with the magic and nothing else.
+fn empty_header() -> ImageHeader {
+    let mut bytes = [0u8; IMAGE_HEADER_SIZE];
+    bytes[..XBE_MAGIC.len()].copy_from_slice(XBE_MAGIC);
+
+    ImageHeader::parse::<ErrorKind>(&bytes).unwrap()
+}
+
+/// The headers of a retail XBE with a single `.text` section.
+fn retail_header() -> ImageHeader {
+    let mut header = empty_header();
+
+    header.base_address = XBE_BASE_ADDRESS;
+    header.size_of_headers = HEADERS_SIZE as u32;
+    header.size_of_image = 0x2000;
+    header.size_of_image_header = IMAGE_HEADER_SIZE as u32;
+    header.certificate_address = address(CERTIFICATE_OFFSET);
+    header.number_of_sections = 1;
+    header.section_headers_address = address(SECTION_HEADERS_OFFSET);
+    header.encoded_entry_point = TEXT_ADDRESS ^ XbeKind::Retail.entry_point_key();
+    header.encoded_kernel_thunk_address = (TEXT_ADDRESS + 0x800) ^ XbeKind::Retail.kernel_thunk_key();
+    header.tls_address = TEXT_ADDRESS + TLS_OFFSET;
+    header.debug_pathname_address = address(DEBUG_PATH_OFFSET);
+    header.debug_filename_address = address(DEBUG_PATH_OFFSET + 9);
+    header.debug_unicode_filename_address = address(DEBUG_UNICODE_FILENAME_OFFSET);
+    header.number_of_library_versions = 2;
+    header.library_versions_address = address(LIBRARY_VERSIONS_OFFSET);
+    header.kernel_library_version_address = address(LIBRARY_VERSIONS_OFFSET + 0x10);
+    header.xapi_library_version_address = address(LIBRARY_VERSIONS_OFFSET);
+
+    header
+}
+
+/// A whole XBE around `header`: the headers, then one sector of `.text`.
+fn image(header: &ImageHeader) -> Vec<u8> {
+    let mut image = vec![0u8; HEADERS_SIZE + 0x1000];
+    put(&mut image, 0, &header.to_bytes());
+
+    let mut certificate = Certificate::parse::<ErrorKind>(&[0u8; CERTIFICATE_SIZE]).unwrap();
+    certificate.size = CERTIFICATE_SIZE as u32;
+    certificate.title_id = 0x4E58_0002;
+    certificate.set_title_name("Parser test");
+    certificate.allowed_media = AllowedMedia::HardDisk;
+    put(&mut image, CERTIFICATE_OFFSET, &certificate.to_bytes());
+
+    let mut text = SectionHeader::parse(&[0u8; SECTION_HEADER_SIZE]).unwrap();
+    text.virtual_address = TEXT_ADDRESS;
+    text.virtual_size = 0x1000;
+    text.raw_address = HEADERS_SIZE as u32;
+    text.raw_size = 0x1000;
+    text.name_address = address(SECTION_NAME_OFFSET);
+    put(&mut image, SECTION_HEADERS_OFFSET, &text.to_bytes());
+    put(&mut image, SECTION_NAME_OFFSET, b".text\0");
+
+    put(&mut image, DEBUG_PATH_OFFSET, b"D:\\build\\hello.exe\0");
+    let unicode: Vec<u8> = "hello.exe\0".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    put(&mut image, DEBUG_UNICODE_FILENAME_OFFSET, &unicode);
+
+    put(&mut image, LIBRARY_VERSIONS_OFFSET, &library("XAPILIB", 5849).to_bytes());
+    put(&mut image, LIBRARY_VERSIONS_OFFSET + 0x10, &library("XBOXKRNL", 5849).to_bytes());
+
+    put(&mut image, HEADERS_SIZE + TLS_OFFSET as usize, &TLS.to_bytes());
+
+    image
+}
+
+/// Replaces the image header of an XBE.
+fn with_header(mut image: Vec<u8>, header: &ImageHeader) -> Vec<u8> {
+    put(&mut image, 0, &header.to_bytes());
+    image
+}
+
+#[test]
+fn parses_hand_built_xbe() {
+    let image = image(&retail_header());
+    let xbe = Xbe::parse(&image).unwrap();
+
+    assert_eq!(xbe.kind, XbeKind::Retail);
+    assert_eq!(xbe.entry_point, TEXT_ADDRESS);
+    assert_eq!(xbe.kernel_thunk_address, TEXT_ADDRESS + 0x800);
+
+    assert_eq!(xbe.title_id(), 0x4E58_0002);
+    assert_eq!(xbe.title_name(), "Parser test");
+    assert_eq!(xbe.certificate.allowed_media, AllowedMedia::HardDisk);
+
+    assert_eq!(xbe.sections.len(), 1);
+    assert_eq!(xbe.sections[0].name, ".text");
+    assert_eq!(xbe.section_at(TEXT_ADDRESS + 0x10).unwrap().name, ".text");
+    assert_eq!(xbe.file_offset(TEXT_ADDRESS + 0x10), Some(HEADERS_SIZE as u32 + 0x10));
+    assert_eq!(xbe.file_offset(address(0x20)), Some(0x20));
+
+    assert_eq!(xbe.debug_pathname, "D:\\build\\hello.exe");
+    assert_eq!(xbe.debug_filename, "hello.exe");
+    assert_eq!(xbe.debug_unicode_filename, "hello.exe");
+
+    assert_eq!(xbe.library_versions, [library("XAPILIB", 5849), library("XBOXKRNL", 5849)]);
+    assert_eq!(xbe.xapi_library_version, Some(library("XAPILIB", 5849)));
+    assert_eq!(xbe.kernel_library_version, Some(library("XBOXKRNL", 5849)));
+
+    // The TLS directory lives in .text, so it's found in the whole image
+    assert_eq!(xbe.tls, Some(TLS));
+
+    let mut device = Image::new(image.clone());
+    assert_eq!(Xbe::read(&mut device).unwrap(), xbe);
+}
+
+#[test]
+fn parse_headers_only_sees_the_headers() {
+    let image = image(&retail_header());
+    let xbe = Xbe::parse_headers::<ErrorKind>(&image[..HEADERS_SIZE]).unwrap();
+
+    assert_eq!(xbe.title_name(), "Parser test");
+    assert_eq!(xbe.tls, None);
+
+    // Unless the TLS directory is within them
+    let mut header = retail_header();
+    header.tls_address = address(0x600);
+
+    let mut image = with_header(image, &header);
+    put(&mut image, 0x600, &TLS.to_bytes());
+
+    assert_eq!(Xbe::parse_headers::<ErrorKind>(&image[..HEADERS_SIZE]).unwrap().tls, Some(TLS));
+}
+
+#[test]
+fn debug_kind_is_detected() {
+    let mut header = retail_header();
+    header.encoded_entry_point = TEXT_ADDRESS ^ XbeKind::Debug.entry_point_key();
+    header.encoded_kernel_thunk_address = (TEXT_ADDRESS + 0x800) ^ XbeKind::Debug.kernel_thunk_key();
+
+    let xbe = Xbe::parse(&image(&header)).unwrap();
+
+    assert_eq!(xbe.kind, XbeKind::Debug);
+    assert_eq!(xbe.entry_point, TEXT_ADDRESS);
+}
+
+#[test]
+fn rejects_truncated_and_foreign_images() {
+    let image = image(&retail_header());
+
+    assert_eq!(Xbe::parse(&image[..0x100]), Err(XbeError::UnexpectedEof));
+    assert_eq!(Xbe::parse(&image[..HEADERS_SIZE - 1]), Err(XbeError::UnexpectedEof));
+    assert_eq!(Xbe::parse(b"MZ\x90\x00"), Err(XbeError::UnexpectedEof));
+
+    let mut foreign = image.clone();
+    foreign[..4].copy_from_slice(b"MZ\x90\x00");
+    assert_eq!(Xbe::parse(&foreign), Err(XbeError::InvalidMagic));
+
+    // Headers too large to be real aren't read in
+    let mut header = retail_header();
+    header.size_of_headers = u32::MAX;
+
+    let mut device = Image::new(with_header(image, &header));
+    assert_eq!(Xbe::read(&mut device), Err(XbeError::Corrupt("headers are too large")));
+}
+
+#[test]
+fn huge_table_counts_are_corrupt() {
+    let mut header = retail_header();
+    header.number_of_sections = u32::MAX;
+    assert!(matches!(Xbe::parse(&image(&header)), Err(XbeError::Corrupt(_))));
+
+    let mut header = retail_header();
+    header.number_of_library_versions = u32::MAX;
+    assert!(matches!(Xbe::parse(&image(&header)), Err(XbeError::Corrupt(_))));
+
+    // One entry too many runs past the headers
+    let mut header = retail_header();
+    header.section_headers_address = address(HEADERS_SIZE - SECTION_HEADER_SIZE);
+    header.number_of_sections = 2;
+    assert!(matches!(Xbe::parse(&image(&header)), Err(XbeError::Corrupt(_))));
+}
+
+#[test]
+fn addresses_outside_the_headers_are_corrupt() {
+    let mut header = retail_header();
+    header.certificate_address = XBE_BASE_ADDRESS - 0x10;
+    assert!(matches!(Xbe::parse(&image(&header)), Err(XbeError::Corrupt(_))));
+
+    let mut header = retail_header();
+    header.xapi_library_version_address = address(HEADERS_SIZE);
+    assert!(matches!(Xbe::parse(&image(&header)), Err(XbeError::Corrupt(_))));
+
+    let mut header = retail_header();
+    header.encoded_entry_point = 0;
+    header.encoded_kernel_thunk_address = 0;
+    assert_eq!(Xbe::parse(&image(&header)), Err(XbeError::Corrupt("entry point outside of image")));
+
+    // A TLS directory past the end of the file
+    let mut header = retail_header();
+    header.tls_address = TEXT_ADDRESS + 0xFF0;
+    assert_eq!(Xbe::parse(&image(&header)), Err(XbeError::UnexpectedEof));
+}
+
+#[test]
+fn bad_logo_only_fails_the_logo() {
+    // No logo at all isn't an error
+    assert_eq!(Xbe::parse(&image(&retail_header())).unwrap().logo(), Ok(None));
+
+    let mut header = retail_header();
+    header.logo_bitmap_address = address(0x700);
+    header.logo_bitmap_size = 4;
+
+    let mut image = with_header(image(&retail_header()), &header);
+    put(&mut image, 0x700, &[0xF5, 0x92, 0x81, 0x33]);
+
+    let logo = Xbe::parse(&image).unwrap().logo().unwrap().unwrap();
+    assert_eq!(logo.pixel(0, 0), [0xFF, 0xFF, 0xFF, 255]);
+
+    // Past the end of the headers, which used to make the whole XBE unreadable
+    header.logo_bitmap_address = address(HEADERS_SIZE - 2);
+    let xbe = Xbe::parse(&with_header(image, &header)).unwrap();
+
+    assert_eq!(xbe.title_name(), "Parser test");
+    assert!(xbe.logo_bitmap.is_empty());
+    assert_eq!(xbe.logo(), Err(XbeError::Corrupt("logo bitmap outside of headers")));
+}
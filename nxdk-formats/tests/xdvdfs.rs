@@ -0,0 +1,343 @@
+//! Reads hand assembled XDVDFS images, and images from `XdvdfsBuilder`.
+
+mod common;
+
+use common::Image;
+use embedded_io::{ErrorKind, Read, Seek, SeekFrom};
+use nxdk_formats::xdvdfs::{
+    compare_names, BuildError, VolumeDescriptor, XdvdfsBuilder, XdvdfsEntry, XdvdfsError, XdvdfsReader,
+    ATTRIBUTE_DIRECTORY, ATTRIBUTE_NORMAL,
+};
+
+const SECTOR: usize = 2048;
+
+/// An image of `sectors` sectors, with the volume descriptor pointing at a root table.
+fn empty_disc(sectors: usize, root_sector: u32, root_size: u32) -> Image {
+    let mut data = vec![0u8; sectors * SECTOR];
+    let volume = VolumeDescriptor {
+        root_sector,
+        root_size,
+        creation_time: 0x01D0_0000_0000_0000,
+    };
+
+    data[32 * SECTOR..33 * SECTOR].copy_from_slice(&volume.to_bytes());
+
+    Image::new(data)
+}
+
+fn put(disc: &mut Image, sector: u32, bytes: &[u8]) {
+    let offset = sector as usize * SECTOR;
+    disc.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+/// Appends a directory entry to `table`, padded to 4 bytes. Returns its link, in 4 byte units.
+fn entry(table: &mut Vec<u8>, left: u16, right: u16, sector: u32, size: u32, attributes: u8, name: &str) -> u16 {
+    let link = (table.len() / 4) as u16;
+
+    table.extend(left.to_le_bytes());
+    table.extend(right.to_le_bytes());
+    table.extend(sector.to_le_bytes());
+    table.extend(size.to_le_bytes());
+    table.push(attributes);
+    table.push(name.len() as u8);
+    table.extend(name.as_bytes());
+
+    table.resize(table.len().next_multiple_of(4), 0xFF);
+    link
+}
+
+/// Pads a table to a whole sector, the way tables are stored.
+fn sector_of(mut table: Vec<u8>) -> Vec<u8> {
+    table.resize(table.len().next_multiple_of(SECTOR), 0xFF);
+    table
+}
+
+/// Root at sector 33, holding `b.txt` with `A` to its left and `default.xbe` to its right.
+/// `A` is a directory at sector 34 holding `nested.bin`.
+fn sample() -> Image {
+    let mut disc = empty_disc(40, 33, SECTOR as u32);
+
+    let mut root = Vec::new();
+    // b.txt takes 20 bytes and A 16, so they link to 5 and 9
+    entry(&mut root, 5, 9, 35, 6, ATTRIBUTE_NORMAL, "b.txt");
+    assert_eq!(entry(&mut root, 0, 0, 34, SECTOR as u32, ATTRIBUTE_DIRECTORY, "A"), 5);
+    assert_eq!(entry(&mut root, 0, 0, 36, 3000, ATTRIBUTE_NORMAL, "default.xbe"), 9);
+    put(&mut disc, 33, &sector_of(root));
+
+    let mut a = Vec::new();
+    entry(&mut a, 0, 0, 38, 4, ATTRIBUTE_NORMAL, "nested.bin");
+    put(&mut disc, 34, &sector_of(a));
+
+    put(&mut disc, 35, b"hello!");
+    put(&mut disc, 36, &(0..3000).map(|i| i as u8).collect::<Vec<_>>());
+    put(&mut disc, 38, b"deep");
+
+    disc
+}
+
+#[test]
+fn lists_directories_in_order() {
+    let mut reader = XdvdfsReader::open(sample()).unwrap();
+    assert_eq!(reader.base_offset(), 0);
+    assert_eq!(reader.volume().root_sector, 33);
+
+    let names: Vec<_> = reader.read_dir("").unwrap().into_iter().map(|entry| entry.name).collect();
+    assert_eq!(names, ["A", "b.txt", "default.xbe"]);
+
+    let nested = reader.read_dir("a").unwrap();
+    assert_eq!(nested.len(), 1);
+    assert_eq!(nested[0].name, "nested.bin");
+    assert!(nested[0].is_file());
+
+    assert_eq!(reader.read_dir("b.txt"), Err(XdvdfsError::NotADirectory));
+}
+
+#[test]
+fn looks_up_paths_ignoring_case() {
+    let mut reader = XdvdfsReader::open(sample()).unwrap();
+
+    assert_eq!(reader.metadata("DEFAULT.XBE").unwrap().size, 3000);
+    assert_eq!(reader.metadata("\\a\\Nested.BIN").unwrap().sector, 38);
+    assert_eq!(reader.metadata("./A/").unwrap().name, "A");
+    assert!(reader.metadata("").unwrap().is_dir());
+
+    assert_eq!(reader.metadata("nope"), Err(XdvdfsError::NotFound));
+    assert_eq!(reader.metadata("A/nope"), Err(XdvdfsError::NotFound));
+    assert_eq!(reader.metadata("b.txt/x"), Err(XdvdfsError::NotADirectory));
+    assert_eq!(reader.open_file("A").err(), Some(XdvdfsError::IsADirectory));
+}
+
+#[test]
+fn reads_files() {
+    let mut reader = XdvdfsReader::open(sample()).unwrap();
+
+    let mut file = reader.open_file("/default.xbe").unwrap();
+    assert_eq!(file.len(), 3000);
+
+    // Across the sector boundary, and stopping at the end of the file
+    file.seek(SeekFrom::Start(2040)).unwrap();
+    let mut buf = [0u8; 16];
+    file.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, core::array::from_fn(|i| (2040 + i) as u8));
+
+    file.seek(SeekFrom::End(-10)).unwrap();
+    assert_eq!(file.read(&mut [0u8; 20]).unwrap(), 10);
+    assert_eq!(file.read(&mut buf).unwrap(), 0);
+    assert_eq!(file.seek(SeekFrom::Current(-3000)).unwrap(), 0);
+    assert_eq!(file.seek(SeekFrom::Current(-1)), Err(XdvdfsError::InvalidSeek));
+
+    let mut data = Vec::new();
+    let mut file = reader.open_file("A/nested.bin").unwrap();
+    let mut buf = [0u8; 3];
+    loop {
+        match file.read(&mut buf).unwrap() {
+            0 => break,
+            len => data.extend_from_slice(&buf[..len]),
+        }
+    }
+    assert_eq!(data, b"deep");
+}
+
+#[test]
+fn finds_the_game_partition() {
+    let offset = 0x0208_0000;
+    let sample = sample().data;
+
+    let mut data = vec![0u8; offset];
+    data.extend_from_slice(&sample);
+
+    let mut reader = XdvdfsReader::open(Image::new(data)).unwrap();
+    assert_eq!(reader.base_offset(), offset as u64);
+    assert_eq!(reader.metadata("b.txt").unwrap().size, 6);
+
+    let mut data = vec![0u8; 3 * SECTOR];
+    data.extend_from_slice(&sample);
+
+    let mut reader = XdvdfsReader::open_at(Image::new(data.clone()), 3 * SECTOR as u64).unwrap();
+    let mut buf = [0u8; 6];
+    reader.open_file("b.txt").unwrap().read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello!");
+
+    assert_eq!(XdvdfsReader::open(Image::new(data)).err(), Some(XdvdfsError::InvalidVolume));
+    assert_eq!(
+        XdvdfsReader::open(Image::new(vec![0; 40 * SECTOR])).err(),
+        Some(XdvdfsError::InvalidVolume)
+    );
+}
+
+#[test]
+fn empty_directories() {
+    // Some tools write empty tables, others a sector of padding
+    for size in [0, SECTOR as u32] {
+        let mut disc = empty_disc(40, 33, size);
+        put(&mut disc, 33, &[0xFF; SECTOR]);
+
+        let mut reader = XdvdfsReader::open(disc).unwrap();
+        assert!(reader.read_dir("").unwrap().is_empty());
+        assert_eq!(reader.metadata("x"), Err(XdvdfsError::NotFound));
+    }
+}
+
+#[test]
+fn rejects_corrupt_tables() {
+    // A huge table size must be refused before anything is allocated for it
+    let mut reader = XdvdfsReader::open(empty_disc(40, 33, u32::MAX)).unwrap();
+    assert!(matches!(reader.read_dir(""), Err(XdvdfsError::Corrupt(_))));
+    assert!(matches!(reader.metadata("a"), Err(XdvdfsError::Corrupt(_))));
+
+    // A table past the end of the image
+    let mut reader = XdvdfsReader::open(empty_disc(40, 39, 2 * SECTOR as u32)).unwrap();
+    assert_eq!(reader.read_dir(""), Err(XdvdfsError::UnexpectedEof));
+
+    // An entry linking back to itself
+    let mut disc = empty_disc(40, 33, SECTOR as u32);
+    let mut root = Vec::new();
+    entry(&mut root, 0, 4, 35, 1, ATTRIBUTE_NORMAL, "a");
+    assert_eq!(entry(&mut root, 4, 4, 35, 1, ATTRIBUTE_NORMAL, "b"), 4);
+    put(&mut disc, 33, &sector_of(root));
+
+    let mut reader = XdvdfsReader::open(disc).unwrap();
+    assert!(matches!(reader.read_dir(""), Err(XdvdfsError::Corrupt(_))));
+    assert!(matches!(reader.metadata("c"), Err(XdvdfsError::Corrupt(_))));
+
+    // A link past the end of the table
+    let mut disc = empty_disc(40, 33, 16);
+    let mut root = Vec::new();
+    entry(&mut root, 0, 100, 35, 1, ATTRIBUTE_NORMAL, "a");
+    put(&mut disc, 33, &sector_of(root));
+
+    let mut reader = XdvdfsReader::open(disc).unwrap();
+    assert!(matches!(reader.read_dir(""), Err(XdvdfsError::Corrupt(_))));
+}
+
+/// Contents of a built file, distinct for every path.
+fn contents(path: &str, len: usize) -> Vec<u8> {
+    path.bytes().cycle().take(len).collect()
+}
+
+fn build(builder: XdvdfsBuilder<&[u8]>) -> Image {
+    let mut disc = Image::new(Vec::new());
+
+    builder.build(&mut disc).unwrap();
+    assert_eq!(disc.data.len() % SECTOR, 0);
+
+    disc
+}
+
+/// Height of the tree starting at `offset` in a raw directory table.
+fn tree_height(table: &[u8], offset: usize) -> usize {
+    let link = |at: usize| u16::from_le_bytes([table[offset + at], table[offset + at + 1]]) as usize * 4;
+    let height = |child: usize| if child == 0 { 0 } else { tree_height(table, child) };
+
+    1 + height(link(0)).max(height(link(2)))
+}
+
+fn read_entry(reader: &mut XdvdfsReader<Image>, entry: XdvdfsEntry) -> Vec<u8> {
+    let mut data = vec![0u8; entry.size as usize];
+    reader.open_entry(entry).unwrap().read_exact(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn builder_round_trip() {
+    let names: Vec<String> = (0..150).map(|i| format!("File_{:03}_{}.dat", i * 7 % 150, "x".repeat(i % 30))).collect();
+    let files: Vec<(String, Vec<u8>)> = [
+        ("default.xbe", 5000),
+        ("media/a.txt", 3),
+        ("media/sub/deeper/leaf.bin", 2 * SECTOR + 1),
+        ("media/sub/exact.bin", SECTOR),
+        ("media/empty.bin", 0),
+    ]
+    .into_iter()
+    .map(|(path, len)| (path.to_string(), contents(path, len)))
+    .chain(names.iter().map(|name| {
+        let path = format!("many/{}", name);
+        let data = contents(&path, 17);
+        (path, data)
+    }))
+    .collect();
+
+    let mut builder = XdvdfsBuilder::new();
+    builder.set_creation_time(0x01D9_0000_1234_5678);
+    builder.add_dir::<ErrorKind>("empty").unwrap();
+    builder.add_dir::<ErrorKind>("media/empty_nested").unwrap();
+
+    for (path, data) in &files {
+        builder.add_file::<ErrorKind>(path, data.len() as u64, data.as_slice()).unwrap();
+    }
+
+    let mut reader = XdvdfsReader::open(build(builder)).unwrap();
+    assert_eq!(reader.volume().creation_time, 0x01D9_0000_1234_5678);
+
+    let root: Vec<_> = reader.read_dir("").unwrap().into_iter().map(|entry| entry.name).collect();
+    assert_eq!(root, ["default.xbe", "empty", "many", "media"]);
+
+    let media: Vec<_> = reader.read_dir("media").unwrap().into_iter().map(|entry| entry.name).collect();
+    assert_eq!(media, ["a.txt", "empty.bin", "empty_nested", "sub"]);
+
+    assert!(reader.read_dir("empty").unwrap().is_empty());
+    assert!(reader.read_dir("MEDIA/Empty_Nested").unwrap().is_empty());
+    assert!(reader.metadata("empty").unwrap().is_dir());
+
+    // Every file reads back, looked up through the tree
+    for (path, data) in &files {
+        let entry = reader.metadata(&path.to_uppercase()).unwrap();
+        assert!(entry.is_file(), "{}", path);
+        assert_eq!(read_entry(&mut reader, entry), *data, "{}", path);
+    }
+
+    // Listings come out sorted the way the trees are searched
+    let many = reader.read_dir("many").unwrap();
+    let mut sorted = names.clone();
+    sorted.sort_by(|a, b| compare_names(a, b));
+    assert_eq!(many.iter().map(|entry| &entry.name).collect::<Vec<_>>(), sorted.iter().collect::<Vec<_>>());
+
+    // 150 long names need more than one sector of table, and stay balanced
+    let directory = reader.metadata("many").unwrap();
+    assert!(directory.size as usize > SECTOR);
+
+    let image = reader.into_inner();
+    let offset = directory.sector as usize * SECTOR;
+    let height = tree_height(&image.data[offset..offset + directory.size as usize], 0);
+    assert!(height <= 8, "tree of 150 entries is {} levels deep", height);
+}
+
+#[test]
+fn builder_layout() {
+    let files = [("b.bin", 10), ("a/first.xbe", 3000), ("a/c.bin", 20)];
+
+    let builder = || {
+        let mut builder = XdvdfsBuilder::new();
+
+        for (path, len) in files {
+            builder.add_file::<ErrorKind>(path, len, &[0x5Au8; 3000][..len as usize]).unwrap();
+        }
+
+        builder.prioritize("A\\FIRST.XBE");
+        builder.prioritize("missing");
+        builder
+    };
+
+    let disc = build(builder());
+    assert_eq!(disc.data, build(builder()).data, "builds aren't reproducible");
+
+    let mut reader = XdvdfsReader::open(disc).unwrap();
+    let root = reader.root();
+    let a = reader.metadata("a").unwrap();
+    let first = reader.metadata("a/first.xbe").unwrap();
+
+    // Tables come first, breadth first, then the prioritized file
+    assert_eq!(root.sector, 33);
+    assert_eq!(a.sector, 34);
+    assert_eq!(first.sector, 35);
+    assert!(reader.metadata("b.bin").unwrap().sector > first.sector);
+
+    assert_eq!(
+        builder().add_file::<ErrorKind>("B.BIN", 1, &[0u8][..]),
+        Err(BuildError::AlreadyExists)
+    );
+    assert_eq!(
+        builder().add_dir::<ErrorKind>("b.bin/x"),
+        Err(BuildError::NotADirectory)
+    );
+}
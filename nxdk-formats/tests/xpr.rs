@@ -0,0 +1,262 @@
+//! XPR0 bundles, swizzling and texture formats, round tripped through the
+//! encoder and decoder.
+
+use nxdk_formats::image::RgbaImage;
+use nxdk_formats::xpr::dxt::{self, Dxt};
+use nxdk_formats::xpr::swizzle::{swizzle, unswizzle, SwizzleMasks};
+use nxdk_formats::xpr::{TextureFormat, Xpr, XprBuilder, XprError};
+
+/// An image whose channels all depend on the position, with alpha.
+fn gradient(width: u32, height: u32) -> RgbaImage {
+    let mut image = RgbaImage::new(width, height);
+
+    for y in 0..height {
+        for x in 0..width {
+            image.set_pixel(x, y, [(x * 255 / width) as u8, (y * 255 / height) as u8, (x ^ y) as u8, (x + y) as u8]);
+        }
+    }
+
+    image
+}
+
+fn solid(width: u32, height: u32, rgba: [u8; 4]) -> RgbaImage {
+    let mut image = RgbaImage::new(width, height);
+
+    for pixel in image.pixels.chunks_exact_mut(4) {
+        pixel.copy_from_slice(&rgba);
+    }
+
+    image
+}
+
+fn round_trip(image: &RgbaImage, format: TextureFormat, levels: u32) -> (Vec<u8>, Vec<RgbaImage>) {
+    let mut builder = XprBuilder::new();
+    builder.add_texture(image, format, levels).unwrap();
+
+    let bytes = builder.build();
+    let xpr = Xpr::parse(&bytes).unwrap();
+    let texture = xpr.textures()[0];
+
+    assert_eq!(texture.format, format);
+    assert_eq!((texture.width, texture.height), (image.width, image.height));
+
+    let decoded = (0..texture.levels).map(|level| xpr.decode_level(&texture, level).unwrap()).collect();
+
+    (bytes, decoded)
+}
+
+#[test]
+fn swizzle_interleaves_x_first() {
+    let masks = SwizzleMasks::new(4, 4);
+    assert_eq!((masks.x, masks.y), (0b0101, 0b1010));
+
+    // The extra bits of the wider dimension come last
+    let masks = SwizzleMasks::new(8, 2);
+    assert_eq!((masks.x, masks.y), (0b1101, 0b0010));
+    assert_eq!(masks.offset(3, 1), 0b0111);
+    assert_eq!(masks.offset(4, 0), 0b0100 << 1);
+}
+
+#[test]
+fn swizzle_inverts_unswizzle() {
+    for (width, height) in [(1, 1), (4, 2), (2, 8), (16, 16), (64, 4)] {
+        let linear: Vec<u8> = (0..width * height * 2).map(|value| value as u8).collect();
+        let swizzled = swizzle(&linear, width, height, 2);
+
+        assert_ne!(swizzled.len(), 0);
+        assert_eq!(unswizzle(&swizzled, width, height, 2).unwrap(), linear);
+    }
+}
+
+#[test]
+fn a8r8g8b8_is_lossless_with_mips() {
+    let image = gradient(16, 8);
+    let (bytes, levels) = round_trip(&image, TextureFormat::A8R8G8B8, 0);
+
+    assert_eq!(&bytes[..4], b"XPR0");
+    assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize, bytes.len());
+    assert_eq!(u32::from_le_bytes(bytes[8..12].try_into().unwrap()), 2048);
+
+    let dimensions: Vec<_> = levels.iter().map(|level| (level.width, level.height)).collect();
+    assert_eq!(dimensions, [(16, 8), (8, 4), (4, 2), (2, 1), (1, 1)]);
+
+    assert_eq!(levels[0], image);
+    assert_eq!(levels[1], image.downsample());
+}
+
+#[test]
+fn mip_levels_average_pixels() {
+    let mut image = solid(2, 2, [0, 0, 0, 255]);
+    image.set_pixel(0, 0, [255, 100, 0, 255]);
+    image.set_pixel(1, 1, [255, 100, 0, 255]);
+
+    let chain = image.mip_chain(8);
+
+    assert_eq!(chain.len(), 2);
+    assert_eq!(chain[1].pixel(0, 0), [128, 50, 0, 255]);
+}
+
+#[test]
+fn level_count_is_capped() {
+    let (_, levels) = round_trip(&gradient(8, 8), TextureFormat::R5G6B5, 2);
+    assert_eq!(levels.len(), 2);
+
+    let (_, levels) = round_trip(&gradient(8, 8), TextureFormat::R5G6B5, 99);
+    assert_eq!(levels.len(), 4);
+}
+
+#[test]
+fn r5g6b5_keeps_representable_colors() {
+    // 31, 32 and 16 in five, six and five bits
+    let color = [255, 130, 132, 255];
+    let (_, levels) = round_trip(&solid(4, 4, color), TextureFormat::R5G6B5, 1);
+
+    assert_eq!(levels[0].pixel(3, 3), color);
+}
+
+#[test]
+fn linear_formats_pad_rows_to_pitch() {
+    let image = gradient(5, 3);
+    let (bytes, levels) = round_trip(&image, TextureFormat::LinA8R8G8B8, 4);
+
+    let xpr = Xpr::parse(&bytes).unwrap();
+    let texture = xpr.textures()[0];
+
+    assert_eq!(texture.pitch, 64);
+    assert_eq!(texture.levels, 1);
+    assert_eq!(levels, [image]);
+}
+
+#[test]
+fn a4r4g4b4_and_a1r5g5b5_keep_alpha() {
+    let image = solid(2, 2, [0xFF, 0x00, 0xFF, 0x00]);
+
+    let (_, levels) = round_trip(&image, TextureFormat::A4R4G4B4, 1);
+    assert_eq!(levels[0].pixel(1, 1), [0xFF, 0x00, 0xFF, 0x00]);
+
+    let (_, levels) = round_trip(&image, TextureFormat::A1R5G5B5, 1);
+    assert_eq!(levels[0].pixel(1, 1), [0xFF, 0x00, 0xFF, 0x00]);
+
+    let (_, levels) = round_trip(&image, TextureFormat::X1R5G5B5, 1);
+    assert_eq!(levels[0].pixel(1, 1), [0xFF, 0x00, 0xFF, 0xFF]);
+}
+
+#[test]
+fn dxt1_decodes_known_block() {
+    let mut block = Vec::new();
+    block.extend_from_slice(&0xF800u16.to_le_bytes());
+    block.extend_from_slice(&0x001Fu16.to_le_bytes());
+    block.extend_from_slice(&0b11_10_01_00u32.to_le_bytes());
+
+    let image = dxt::decode(Dxt::Dxt1, &block, 4, 4).unwrap();
+
+    assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(1, 0), [0, 0, 255, 255]);
+    assert_eq!(image.pixel(2, 0), [170, 0, 85, 255]);
+    assert_eq!(image.pixel(3, 0), [85, 0, 170, 255]);
+}
+
+#[test]
+fn dxt_keeps_solid_colors() {
+    for format in [TextureFormat::Dxt1, TextureFormat::Dxt3, TextureFormat::Dxt5] {
+        for color in [[0, 0, 0, 255], [255, 255, 255, 255], [0x84, 0x82, 0x10, 255]] {
+            let (_, levels) = round_trip(&solid(8, 8, color), format, 1);
+            assert_eq!(levels[0].pixel(5, 6), color, "{:?}", format);
+        }
+    }
+}
+
+#[test]
+fn dxt_encodes_two_color_blocks_exactly() {
+    let mut image = solid(4, 4, [255, 0, 0, 255]);
+
+    for x in 0..4 {
+        image.set_pixel(x, 2, [0, 0, 255, 255]);
+    }
+
+    for format in [TextureFormat::Dxt1, TextureFormat::Dxt3, TextureFormat::Dxt5] {
+        let (_, levels) = round_trip(&image, format, 1);
+        assert_eq!(levels[0], image, "{:?}", format);
+    }
+}
+
+#[test]
+fn dxt_alpha() {
+    let mut image = solid(4, 4, [40, 80, 120, 255]);
+    image.set_pixel(0, 0, [0, 0, 0, 0]);
+
+    let (_, levels) = round_trip(&image, TextureFormat::Dxt1, 1);
+    assert_eq!(levels[0].pixel(0, 0), [0, 0, 0, 0]);
+    assert_eq!(levels[0].pixel(1, 0)[3], 255);
+
+    image.set_pixel(1, 0, [40, 80, 120, 0x77]);
+
+    let (_, levels) = round_trip(&image, TextureFormat::Dxt3, 1);
+    assert_eq!(levels[0].pixel(1, 0)[3], 0x77);
+
+    let (_, levels) = round_trip(&image, TextureFormat::Dxt5, 1);
+    assert_eq!(levels[0].pixel(0, 0)[3], 0);
+    assert_eq!(levels[0].pixel(2, 0)[3], 255);
+    assert!(levels[0].pixel(1, 0)[3].abs_diff(0x77) <= 255 / 14);
+}
+
+#[test]
+fn dxt_mips_go_down_to_single_blocks() {
+    let (bytes, levels) = round_trip(&gradient(16, 16), TextureFormat::Dxt1, 0);
+    let xpr = Xpr::parse(&bytes).unwrap();
+    let texture = xpr.textures()[0];
+
+    assert_eq!(levels.len(), 5);
+    assert_eq!(texture.level_size(3), 8);
+    assert_eq!(texture.level_size(4), 8);
+    assert_eq!(texture.data_size(), 128 + 32 + 8 + 8 + 8);
+}
+
+#[test]
+fn several_textures_are_aligned() {
+    let mut builder = XprBuilder::new();
+    builder.add_texture(&gradient(2, 2), TextureFormat::R5G6B5, 1).unwrap();
+    builder.add_texture(&gradient(4, 4), TextureFormat::A8R8G8B8, 1).unwrap();
+
+    let bytes = builder.build();
+    let xpr = Xpr::parse(&bytes).unwrap();
+
+    assert_eq!(xpr.textures().len(), 2);
+    assert_eq!(xpr.textures()[1].data_offset, 2048 + 128);
+    assert_eq!(xpr.decode_texture(&xpr.textures()[1]).unwrap(), gradient(4, 4));
+}
+
+#[test]
+fn rejects_bad_input() {
+    let mut builder = XprBuilder::new();
+
+    assert_eq!(
+        builder.add_texture(&gradient(6, 4), TextureFormat::A8R8G8B8, 1),
+        Err(XprError::UnsupportedDimensions)
+    );
+    assert!(builder.add_texture(&gradient(6, 4), TextureFormat::LinA8R8G8B8, 1).is_ok());
+
+    assert_eq!(Xpr::parse(b"XPR1\0\0\0\0\0\0\0\0").unwrap_err(), XprError::InvalidMagic);
+    assert_eq!(Xpr::parse(b"XPR0").unwrap_err(), XprError::UnexpectedEof);
+
+    let bytes = builder.build();
+    let xpr = Xpr::parse(&bytes).unwrap();
+    assert_eq!(xpr.decode_level(&xpr.textures()[0], 1), Err(XprError::InvalidLevel));
+}
+
+#[test]
+fn rejects_non_power_of_two_swizzled_textures() {
+    let mut builder = XprBuilder::new();
+    builder.add_texture(&gradient(3, 3), TextureFormat::LinA8R8G8B8, 1).unwrap();
+    let mut bytes = builder.build();
+
+    // Relabel the linear texture as swizzled, keeping its 3x3 size register
+    let format_code = 12 + 0x0C + 1;
+    assert_eq!(bytes[format_code], TextureFormat::LinA8R8G8B8.code());
+    bytes[format_code] = TextureFormat::A8R8G8B8.code();
+
+    assert_eq!(Xpr::parse(&bytes).unwrap_err(), XprError::UnsupportedDimensions);
+
+    assert_eq!(unswizzle(&[0; 36], 3, 3, 4), Err(XprError::UnsupportedDimensions));
+    assert_eq!(unswizzle(&[0; 36], 4, 4, 4), Err(XprError::UnexpectedEof));
+}
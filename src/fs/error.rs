@@ -0,0 +1,124 @@
+use crate::utils::error::PlatformError;
+use crate::winapi::error::{NtStatusError, WinError, WinMixedError};
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+use nxdk_formats::meta::MetaError;
+use nxdk_sys::winapi::*;
+
+/// Error type for the `fs` facade. Wraps every error the underlying
+/// path, winapi and kernel calls can produce.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FsError {
+    Path(PlatformError),
+    Win(WinError),
+    NtStatus(NtStatusError),
+    /// The operation was cancelled through its progress callback.
+    Cancelled,
+}
+
+impl FsError {
+    /// Returns the underlying Win error code, if this error came from winapi.
+    pub fn win_error(&self) -> Option<WinError> {
+        match self {
+            FsError::Win(win_error) => Some(*win_error),
+            _ => None,
+        }
+    }
+}
+
+impl Display for FsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FsError::Path(platform_error) => write!(f, "Invalid path: {}", platform_error),
+            FsError::Win(win_error) => write!(f, "{}", win_error),
+            FsError::NtStatus(nt_status_error) => write!(f, "{}", nt_status_error),
+            FsError::Cancelled => write!(f, "Operation cancelled"),
+        }
+    }
+}
+
+impl Error for FsError {}
+
+impl embedded_io::Error for FsError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            FsError::Path(_) => embedded_io::ErrorKind::InvalidInput,
+            FsError::Win(win_error) => match win_error.into_inner() {
+                ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND => embedded_io::ErrorKind::NotFound,
+                ERROR_ACCESS_DENIED => embedded_io::ErrorKind::PermissionDenied,
+                ERROR_FILE_EXISTS | ERROR_ALREADY_EXISTS => embedded_io::ErrorKind::AlreadyExists,
+                ERROR_INVALID_PARAMETER | ERROR_INVALID_NAME => embedded_io::ErrorKind::InvalidInput,
+                ERROR_NOT_ENOUGH_MEMORY => embedded_io::ErrorKind::OutOfMemory,
+                _ => embedded_io::ErrorKind::Other,
+            },
+            FsError::NtStatus(_) => embedded_io::ErrorKind::Other,
+            FsError::Cancelled => embedded_io::ErrorKind::Interrupted,
+        }
+    }
+}
+
+impl From<PlatformError> for FsError {
+    fn from(value: PlatformError) -> Self {
+        FsError::Path(value)
+    }
+}
+
+impl From<WinError> for FsError {
+    fn from(value: WinError) -> Self {
+        FsError::Win(value)
+    }
+}
+
+impl From<NtStatusError> for FsError {
+    fn from(value: NtStatusError) -> Self {
+        FsError::NtStatus(value)
+    }
+}
+
+impl From<WinMixedError> for FsError {
+    fn from(value: WinMixedError) -> Self {
+        match value {
+            WinMixedError::NtStatus(nt_status_error) => FsError::NtStatus(nt_status_error),
+            WinMixedError::WinError(win_error) => FsError::Win(win_error),
+        }
+    }
+}
+
+/// Error type for `fs::save`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SaveError {
+    Fs(FsError),
+    /// A metadata file couldn't be parsed.
+    Meta(MetaError),
+    /// The name is empty, too long, or has line breaks.
+    InvalidName,
+    /// A save with the same name already exists.
+    AlreadyExists,
+    NotFound,
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SaveError::Fs(fs_error) => write!(f, "{}", fs_error),
+            SaveError::Meta(meta_error) => write!(f, "{}", meta_error),
+            SaveError::InvalidName => write!(f, "Invalid save name"),
+            SaveError::AlreadyExists => write!(f, "Save already exists"),
+            SaveError::NotFound => write!(f, "Save not found"),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<FsError> for SaveError {
+    fn from(value: FsError) -> Self {
+        SaveError::Fs(value)
+    }
+}
+
+impl From<MetaError> for SaveError {
+    fn from(value: MetaError) -> Self {
+        SaveError::Meta(value)
+    }
+}
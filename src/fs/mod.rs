@@ -0,0 +1,495 @@
+use crate::fs::error::FsError;
+use crate::path::XboxPath;
+use crate::winapi::file::{AccessRights, CreationDisposition, DirEntry, FileFlagsAndAttributes, ReadDir, ShareMode, WinFileHandle};
+use crate::winapi::error::WinError;
+use crate::winapi::WindowsPath;
+use alloc::vec;
+use alloc::vec::Vec;
+use embedded_io::{Read, ReadExactError, Seek, SeekFrom, Write};
+use nxdk_sys::winapi::*;
+
+pub mod error;
+pub mod save;
+pub mod tree;
+
+/// Buffer size used by `copy`.
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Options used to configure how a `File` is opened. Mirrors `std::fs::OpenOptions`,
+/// mapped onto `AccessRights`, `ShareMode` and `CreationDisposition`.
+#[derive(Debug, Clone)]
+pub struct OpenOptions {
+    read: bool,
+    write: bool,
+    append: bool,
+    truncate: bool,
+    create: bool,
+    create_new: bool,
+    share: ShareMode,
+    flags_attributes: FileFlagsAndAttributes,
+}
+
+impl OpenOptions {
+    /// Creates a new set of options with everything disabled.
+    pub fn new() -> Self {
+        Self {
+            read: false,
+            write: false,
+            append: false,
+            truncate: false,
+            create: false,
+            create_new: false,
+            share: ShareMode::default(),
+            flags_attributes: FileFlagsAndAttributes::default(),
+        }
+    }
+
+    /// Sets the option for read access.
+    pub fn read(mut self, read: bool) -> Self {
+        self.read = read;
+        self
+    }
+
+    /// Sets the option for write access.
+    pub fn write(mut self, write: bool) -> Self {
+        self.write = write;
+        self
+    }
+
+    /// Sets the option for append mode. The file cursor is moved to the end
+    /// of the file after opening. Implies write access.
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Sets the option for truncating an existing file to 0 bytes on open.
+    pub fn truncate(mut self, truncate: bool) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Sets the option to create the file if it doesn't exist.
+    pub fn create(mut self, create: bool) -> Self {
+        self.create = create;
+        self
+    }
+
+    /// Sets the option to always create a new file, failing if it already exists.
+    pub fn create_new(mut self, create_new: bool) -> Self {
+        self.create_new = create_new;
+        self
+    }
+
+    /// Sets the share mode. Defaults to `ShareMode::Read | ShareMode::Write`.
+    pub fn share(mut self, share: ShareMode) -> Self {
+        self.share = share;
+        self
+    }
+
+    /// Sets the option for write-through mode (`FILE_FLAG_WRITE_THROUGH`). Writes
+    /// skip the cache and go straight to disk, for durable saves.
+    pub fn write_through(mut self, write_through: bool) -> Self {
+        self.flags_attributes.set(FileFlagsAndAttributes::FlagWriteThrough, write_through);
+        self
+    }
+
+    /// Sets the option for overlapped mode (`FILE_FLAG_OVERLAPPED`), needed by
+    /// the async and positional operations of `WinFileHandle`.
+    pub fn overlapped(mut self, overlapped: bool) -> Self {
+        self.flags_attributes.set(FileFlagsAndAttributes::FlagOverlapped, overlapped);
+        self
+    }
+
+    /// Sets the raw flags and attributes given to `CreateFileA`.
+    pub fn flags_attributes(mut self, flags_attributes: FileFlagsAndAttributes) -> Self {
+        self.flags_attributes = flags_attributes;
+        self
+    }
+
+    fn access_rights(&self) -> AccessRights {
+        let mut access = AccessRights::None;
+
+        if self.read {
+            access |= AccessRights::Read;
+        }
+
+        if self.write || self.append {
+            access |= AccessRights::Write;
+        }
+
+        access
+    }
+
+    fn creation_disposition(&self) -> CreationDisposition {
+        match (self.create_new, self.create, self.truncate) {
+            (true, _, _) => CreationDisposition::CreateNew,
+            (false, true, true) => CreationDisposition::CreateAlways,
+            (false, true, false) => CreationDisposition::OpenAlways,
+            (false, false, true) => CreationDisposition::TruncateExisting,
+            (false, false, false) => CreationDisposition::OpenExisting,
+        }
+    }
+
+    /// Opens a file at `path` with the options specified by `self`.
+    ///
+    /// When the file may be created, `path` is checked against FATX naming rules first.
+    pub fn open<P: AsRef<XboxPath>>(&self, path: P) -> Result<File, FsError> {
+        let path = path.as_ref();
+
+        if self.create || self.create_new {
+            path.validate()?;
+        } else {
+            c_path(path)?;
+        }
+
+        let mut handle = WinFileHandle::open_path(
+            path,
+            self.access_rights(),
+            self.share,
+            self.creation_disposition(),
+            self.flags_attributes,
+        )?;
+
+        if self.append {
+            handle.seek(SeekFrom::End(0))?;
+        }
+
+        Ok(File { handle })
+    }
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An open file on the filesystem. A thin wrapper over `WinFileHandle` that
+/// reports errors as `FsError`.
+#[derive(Debug)]
+pub struct File {
+    handle: WinFileHandle,
+}
+
+impl File {
+    /// Opens a file in read-only mode.
+    pub fn open<P: AsRef<XboxPath>>(path: P) -> Result<Self, FsError> {
+        OpenOptions::new().read(true).open(path)
+    }
+
+    /// Opens a file in write-only mode, creating it if it doesn't exist and
+    /// truncating it if it does.
+    pub fn create<P: AsRef<XboxPath>>(path: P) -> Result<Self, FsError> {
+        OpenOptions::new().write(true).create(true).truncate(true).open(path)
+    }
+
+    /// Creates a blank new set of options.
+    pub fn options() -> OpenOptions {
+        OpenOptions::new()
+    }
+
+    /// Flushes all buffered data of this file to disk.
+    pub fn sync_all(&mut self) -> Result<(), FsError> {
+        Ok(self.handle.flush()?)
+    }
+
+    /// Queries metadata about this file. Requires read access.
+    pub fn metadata(&self) -> Result<Metadata, FsError> {
+        let basic = self.handle.query_basic_information()?;
+        let standard = self.handle.query_standard_information()?;
+
+        Ok(Metadata {
+            len: standard.end_of_file,
+            attributes: basic.attributes,
+            creation_time: basic.creation_time,
+            last_access_time: basic.last_access_time,
+            last_write_time: basic.last_write_time,
+        })
+    }
+
+    /// Truncates or extends this file to the given size. Requires write access.
+    pub fn set_len(&self, len: u64) -> Result<(), FsError> {
+        Ok(self.handle.set_len(len)?)
+    }
+
+    /// Sets the last write time of this file, in Windows time. Requires write access.
+    pub fn set_modified(&self, last_write_time: u64) -> Result<(), FsError> {
+        Ok(self.handle.set_times(None, None, Some(last_write_time))?)
+    }
+
+    /// Size of this file, in bytes.
+    pub fn len(&self) -> Result<u64, FsError> {
+        Ok(self.handle.query_standard_information()?.end_of_file)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, FsError> {
+        Ok(self.len()? == 0)
+    }
+
+    pub fn as_handle(&self) -> &WinFileHandle {
+        &self.handle
+    }
+
+    pub fn as_handle_mut(&mut self) -> &mut WinFileHandle {
+        &mut self.handle
+    }
+
+    pub fn into_handle(self) -> WinFileHandle {
+        self.handle
+    }
+}
+
+impl From<WinFileHandle> for File {
+    fn from(handle: WinFileHandle) -> Self {
+        Self { handle }
+    }
+}
+
+impl embedded_io::ErrorType for File {
+    type Error = FsError;
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(self.handle.read(buf)?)
+    }
+}
+
+impl Write for File {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(self.handle.write(buf)?)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(self.handle.flush()?)
+    }
+}
+
+impl Seek for File {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Self::Error> {
+        Ok(self.handle.seek(pos)?)
+    }
+}
+
+/// Metadata information about a file or directory.
+///
+/// Timestamps are given in Windows time; 100ns intervals since January 1, 1601 (UTC).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    pub len: u64,
+    pub attributes: FileFlagsAndAttributes,
+    pub creation_time: u64,
+    pub last_access_time: u64,
+    pub last_write_time: u64,
+}
+
+impl Metadata {
+    pub fn is_dir(&self) -> bool {
+        self.attributes.contains(FileFlagsAndAttributes::AttributeDirectory)
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.is_dir()
+    }
+
+    pub fn is_readonly(&self) -> bool {
+        self.attributes.contains(FileFlagsAndAttributes::AttributeReadOnly)
+    }
+}
+
+impl From<DirEntry> for Metadata {
+    fn from(entry: DirEntry) -> Self {
+        Self {
+            len: entry.size,
+            attributes: entry.attributes,
+            creation_time: entry.creation_time,
+            last_access_time: entry.last_access_time,
+            last_write_time: entry.last_write_time,
+        }
+    }
+}
+
+/// Checks that `path` fits in a `WindowsPath`, and converts it.
+fn c_path(path: &XboxPath) -> Result<WindowsPath, FsError> {
+    Ok(path.to_c_path()?)
+}
+
+/// Queries metadata about the file or directory at the given path.
+///
+/// Drive roots (such as `E:\`) have no entry of their own, and will fail
+/// with `ERROR_FILE_NOT_FOUND`.
+pub fn metadata<P: AsRef<XboxPath>>(path: P) -> Result<Metadata, FsError> {
+    let path = XboxPath::new(path.as_ref().as_str().trim_end_matches(['\\', '/']));
+    c_path(path)?;
+
+    match ReadDir::open_path(path)?.next() {
+        Some(entry) => Ok(Metadata::from(entry?)),
+        None => Err(FsError::Win(WinError::from(ERROR_FILE_NOT_FOUND))),
+    }
+}
+
+/// Returns an iterator over the entries within a directory.
+pub fn read_dir<P: AsRef<XboxPath>>(path: P) -> Result<ReadDir, FsError> {
+    let pattern = path.as_ref().join("*");
+    c_path(&pattern)?;
+
+    Ok(ReadDir::open_path(&pattern)?)
+}
+
+/// Removes a file from the filesystem.
+pub fn remove_file<P: AsRef<XboxPath>>(path: P) -> Result<(), FsError> {
+    let c_path = c_path(path.as_ref())?;
+
+    let success = unsafe {
+        DeleteFileA(c_path.as_ptr() as *const i8)
+    };
+
+    if success == 0 {
+        return Err(FsError::Win(WinError::from_last_error()))
+    }
+
+    Ok(())
+}
+
+/// Renames a file or directory. Both paths must be on the same volume.
+///
+/// `to` is checked against FATX naming rules first.
+pub fn rename<P: AsRef<XboxPath>, Q: AsRef<XboxPath>>(from: P, to: Q) -> Result<(), FsError> {
+    to.as_ref().validate()?;
+
+    let c_from = c_path(from.as_ref())?;
+    let c_to = c_path(to.as_ref())?;
+
+    let success = unsafe {
+        MoveFileA(c_from.as_ptr() as *const i8, c_to.as_ptr() as *const i8)
+    };
+
+    if success == 0 {
+        return Err(FsError::Win(WinError::from_last_error()))
+    }
+
+    Ok(())
+}
+
+/// Creates a new, empty directory at the given path.
+///
+/// The path is checked against FATX naming rules first.
+pub fn create_dir<P: AsRef<XboxPath>>(path: P) -> Result<(), FsError> {
+    let path = path.as_ref();
+    path.validate()?;
+
+    let c_path = c_path(path)?;
+
+    let success = unsafe {
+        CreateDirectoryA(c_path.as_ptr() as *const i8, core::ptr::null_mut())
+    };
+
+    if success == 0 {
+        return Err(FsError::Win(WinError::from_last_error()))
+    }
+
+    Ok(())
+}
+
+/// Recursively creates a directory and all of its missing parents.
+pub fn create_dir_all<P: AsRef<XboxPath>>(path: P) -> Result<(), FsError> {
+    let mut ancestors = Vec::new();
+    let mut current = Some(path.as_ref());
+
+    // Drive roots have no file name, and always exist
+    while let Some(ancestor) = current {
+        if ancestor.file_name().is_some() {
+            ancestors.push(ancestor);
+        }
+
+        current = ancestor.parent();
+    }
+
+    for ancestor in ancestors.iter().rev() {
+        create_dir_if_missing(ancestor)?;
+    }
+
+    Ok(())
+}
+
+/// Creates a directory unless one already exists. A file in its place is
+/// still reported as `ERROR_ALREADY_EXISTS`.
+fn create_dir_if_missing(path: &XboxPath) -> Result<(), FsError> {
+    match create_dir(path) {
+        Err(FsError::Win(win_error)) if win_error.into_inner() == ERROR_ALREADY_EXISTS => {
+            if metadata(path)?.is_dir() {
+                return Ok(())
+            }
+
+            Err(FsError::Win(win_error))
+        }
+        result => result,
+    }
+}
+
+/// Removes an empty directory.
+pub fn remove_dir<P: AsRef<XboxPath>>(path: P) -> Result<(), FsError> {
+    let c_path = c_path(path.as_ref())?;
+
+    let success = unsafe {
+        RemoveDirectoryA(c_path.as_ptr() as *const i8)
+    };
+
+    if success == 0 {
+        return Err(FsError::Win(WinError::from_last_error()))
+    }
+
+    Ok(())
+}
+
+/// Reads the whole contents of a file, without blocking the executor.
+pub async fn read_async<P: AsRef<XboxPath>>(path: P) -> Result<Vec<u8>, FsError> {
+    let file = OpenOptions::new().read(true).overlapped(true).open(path)?;
+    let out_of_memory = FsError::Win(WinError::from(ERROR_NOT_ENOUGH_MEMORY));
+
+    // Files can be larger than the address space, so check before allocating
+    let len = usize::try_from(file.len()?).map_err(|_| out_of_memory.clone())?;
+    let mut contents = Vec::new();
+    contents.try_reserve_exact(len).map_err(|_| out_of_memory)?;
+    contents.resize(len, 0);
+
+    file.as_handle().read_exact_at(0, &mut contents).await.map_err(|error| match error {
+        ReadExactError::UnexpectedEof => FsError::Win(WinError::from(ERROR_HANDLE_EOF)),
+        ReadExactError::Other(error) => FsError::Win(error),
+    })?;
+
+    Ok(contents)
+}
+
+/// Writes `contents` to a file, creating it if it doesn't exist and replacing
+/// it if it does, without blocking the executor.
+pub async fn write_async<P: AsRef<XboxPath>>(path: P, contents: &[u8]) -> Result<(), FsError> {
+    let mut file = OpenOptions::new().write(true).create(true).truncate(true).overlapped(true).open(path)?;
+
+    file.as_handle().write_all_at(0, contents).await?;
+    Ok(embedded_io_async::Write::flush(file.as_handle_mut()).await?)
+}
+
+/// Copies the contents of one file to another, overwriting the destination.
+///
+/// Returns the total number of bytes copied.
+pub fn copy<P: AsRef<XboxPath>, Q: AsRef<XboxPath>>(from: P, to: Q) -> Result<u64, FsError> {
+    let mut source = File::open(from)?;
+    let mut destination = File::create(to)?;
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    let mut total_copied: u64 = 0;
+
+    loop {
+        let read = source.read(&mut buffer)?;
+
+        if read == 0 {
+            break;
+        }
+
+        destination.write_all(&buffer[..read])?;
+        total_copied += read as u64;
+    }
+
+    Ok(total_copied)
+}
@@ -0,0 +1,334 @@
+//! Saved games, laid out the way the dashboard's memory manager expects them.
+//!
+//! Saves live in `E:\UDATA\<TitleID>\<SaveID>\`, each with a `SaveMeta.xbx`
+//! holding its name and an optional `SaveImage.xbx`. The title folder holds a
+//! `TitleMeta.xbx` with the title name, and optionally `TitleImage.xbx`, and a
+//! `SaveImage.xbx` used for saves without their own. Data that isn't a save,
+//! such as caches, goes in `E:\TDATA\<TitleID>\`.
+//!
+//! ```ignore
+//! let saves = SaveManager::new(0x4E580001);
+//! saves.set_title_name("My Homebrew")?;
+//!
+//! let save = saves.create_save("Level 3")?;
+//! let mut file = File::create(save.path.join("progress.bin"))?;
+//! ```
+
+use crate::fs::error::{FsError, SaveError};
+use crate::fs::tree::remove_dir_all;
+use crate::fs::{create_dir, create_dir_all, metadata, read_dir, File};
+use crate::nxdk::xbe::current_title_id;
+use crate::path::{XboxPath, XboxPathBuf};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+use embedded_io::{Read, Write};
+use nxdk_formats::meta::{
+    XbxMeta, MAX_SAVE_NAME_LENGTH, SAVE_IMAGE_FILE_NAME, SAVE_META_FILE_NAME, SAVE_NAME_KEY,
+    TITLE_IMAGE_FILE_NAME, TITLE_META_FILE_NAME, TITLE_NAME_KEY,
+};
+use nxdk_formats::xbe::XbeError;
+use nxdk_sys::winapi::{ERROR_FILE_NOT_FOUND, ERROR_PATH_NOT_FOUND};
+
+/// Length of a save folder name, in hex digits.
+const SAVE_ID_LENGTH: usize = 12;
+
+/// A save found in a title's UDATA folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveGame {
+    /// Name of the save folder, 12 uppercase hex digits.
+    pub id: String,
+    /// Name shown in the dashboard, from `SaveMeta.xbx`.
+    pub name: String,
+    /// Full path of the save folder.
+    pub path: XboxPathBuf,
+    /// Last write time of the save folder, in Windows time.
+    pub last_write_time: u64,
+}
+
+/// Manages the saves and title data of a single title.
+#[derive(Debug, Clone)]
+pub struct SaveManager {
+    root: XboxPathBuf,
+    title_id: u32,
+}
+
+impl SaveManager {
+    /// Saves of `title_id`, on the E: drive.
+    pub fn new(title_id: u32) -> Self {
+        Self::with_root("E:\\", title_id)
+    }
+
+    /// Saves of `title_id`, under another root holding UDATA and TDATA, such
+    /// as a memory unit.
+    pub fn with_root<P: AsRef<XboxPath>>(root: P, title_id: u32) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            title_id,
+        }
+    }
+
+    /// Saves of the running title, keyed by the title ID in its certificate.
+    pub fn for_current_title() -> Result<Self, XbeError> {
+        Ok(Self::new(current_title_id()?))
+    }
+
+    pub fn title_id(&self) -> u32 {
+        self.title_id
+    }
+
+    /// The title folder holding saves, such as `E:\UDATA\4e580001`.
+    pub fn user_data_dir(&self) -> XboxPathBuf {
+        self.root.join("UDATA").join(format!("{:08x}", self.title_id))
+    }
+
+    /// The title folder for data that isn't a save, such as `E:\TDATA\4e580001`.
+    pub fn title_data_dir(&self) -> XboxPathBuf {
+        self.root.join("TDATA").join(format!("{:08x}", self.title_id))
+    }
+
+    /// Creates the TDATA folder if missing, and returns its path.
+    pub fn create_title_data_dir(&self) -> Result<XboxPathBuf, SaveError> {
+        let path = self.title_data_dir();
+        create_dir_all(&path)?;
+
+        Ok(path)
+    }
+
+    /// Reads `TitleMeta.xbx`, or None if there isn't one.
+    pub fn title_meta(&self) -> Result<Option<XbxMeta>, SaveError> {
+        match read_optional(&self.user_data_dir().join(TITLE_META_FILE_NAME))? {
+            Some(bytes) => Ok(Some(XbxMeta::parse(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes `TitleMeta.xbx`, creating the title folder if needed.
+    pub fn set_title_meta(&self, meta: &XbxMeta) -> Result<(), SaveError> {
+        let directory = self.user_data_dir();
+        create_dir_all(&directory)?;
+
+        write_file(&directory.join(TITLE_META_FILE_NAME), &meta.to_bytes())
+    }
+
+    /// Sets the title name shown by the dashboard, keeping anything else in `TitleMeta.xbx`.
+    pub fn set_title_name(&self, name: &str) -> Result<(), SaveError> {
+        check_name(name)?;
+
+        let mut meta = self.title_meta()?.unwrap_or_default();
+        meta.set(TITLE_NAME_KEY, name);
+
+        self.set_title_meta(&meta)
+    }
+
+    /// Writes `TitleImage.xbx`, an XPR image shown next to the title name.
+    pub fn set_title_image(&self, image: &[u8]) -> Result<(), SaveError> {
+        let directory = self.user_data_dir();
+        create_dir_all(&directory)?;
+
+        write_file(&directory.join(TITLE_IMAGE_FILE_NAME), image)
+    }
+
+    /// Writes the title's `SaveImage.xbx`, shown for saves without their own image.
+    pub fn set_default_save_image(&self, image: &[u8]) -> Result<(), SaveError> {
+        let directory = self.user_data_dir();
+        create_dir_all(&directory)?;
+
+        write_file(&directory.join(SAVE_IMAGE_FILE_NAME), image)
+    }
+
+    /// Lists the saves of this title, sorted by name. Folders without a valid
+    /// `SaveMeta.xbx` aren't saves, and are skipped.
+    pub fn saves(&self) -> Result<Vec<SaveGame>, SaveError> {
+        let directory = self.user_data_dir();
+
+        let entries = match read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(error) if is_not_found(&error) => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut saves = Vec::new();
+
+        for entry in entries {
+            let entry = entry.map_err(FsError::from)?;
+
+            if !entry.is_dir() || !is_save_id(&entry.name) {
+                continue;
+            }
+
+            let path = directory.join(&entry.name);
+
+            let Some(bytes) = read_optional(&path.join(SAVE_META_FILE_NAME))? else {
+                continue;
+            };
+
+            let Some(name) = XbxMeta::parse(&bytes).ok().and_then(|meta| meta.save_name().map(String::from)) else {
+                continue;
+            };
+
+            saves.push(SaveGame {
+                id: entry.name,
+                name,
+                path,
+                last_write_time: entry.last_write_time,
+            });
+        }
+
+        saves.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(saves)
+    }
+
+    /// Finds a save by its name, as shown in the dashboard.
+    pub fn find_save(&self, name: &str) -> Result<Option<SaveGame>, SaveError> {
+        Ok(self.saves()?.into_iter().find(|save| save.name == name))
+    }
+
+    /// Creates a new, empty save called `name`. Fails with `SaveError::AlreadyExists`
+    /// if this title already has a save with that name.
+    pub fn create_save(&self, name: &str) -> Result<SaveGame, SaveError> {
+        check_name(name)?;
+
+        let saves = self.saves()?;
+
+        if saves.iter().any(|save| save.name == name) {
+            return Err(SaveError::AlreadyExists);
+        }
+
+        // Folder names are a hash of the save name, skipping over any that
+        // are taken. XAPI derives them differently, so they won't match the
+        // folders it would have picked
+        let mut id = save_id_hash(name);
+        let directory = self.user_data_dir();
+        create_dir_all(&directory)?;
+
+        let path = loop {
+            let path = directory.join(format!("{:012X}", id));
+
+            match create_dir(&path) {
+                Ok(()) => break path,
+                Err(FsError::Win(_)) if metadata(&path).is_ok() => id = (id + 1) & 0xFFFF_FFFF_FFFF,
+                Err(error) => return Err(error.into()),
+            }
+        };
+
+        write_file(&path.join(SAVE_META_FILE_NAME), &XbxMeta::for_save(name).to_bytes())?;
+
+        let last_write_time = metadata(&path)?.last_write_time;
+
+        Ok(SaveGame {
+            id: format!("{:012X}", id),
+            name: name.into(),
+            path,
+            last_write_time,
+        })
+    }
+
+    /// Renames a save, keeping its folder and contents.
+    pub fn rename_save(&self, save: &mut SaveGame, name: &str) -> Result<(), SaveError> {
+        check_name(name)?;
+
+        if self.saves()?.iter().any(|other| other.name == name && other.id != save.id) {
+            return Err(SaveError::AlreadyExists);
+        }
+
+        let meta_path = save.path.join(SAVE_META_FILE_NAME);
+        let bytes = read_optional(&meta_path)?.ok_or(SaveError::NotFound)?;
+
+        let mut meta = XbxMeta::parse(&bytes)?;
+        meta.set(SAVE_NAME_KEY, name);
+
+        write_file(&meta_path, &meta.to_bytes())?;
+        save.name = name.into();
+
+        Ok(())
+    }
+
+    /// Writes the `SaveImage.xbx` of a save, an XPR image shown next to its name.
+    pub fn set_save_image(&self, save: &SaveGame, image: &[u8]) -> Result<(), SaveError> {
+        write_file(&save.path.join(SAVE_IMAGE_FILE_NAME), image)
+    }
+
+    /// Deletes a save along with everything in it.
+    pub fn delete_save(&self, save: SaveGame) -> Result<(), SaveError> {
+        match remove_dir_all(&save.path, |_| ControlFlow::Continue(())) {
+            Ok(_) => Ok(()),
+            Err(error) if is_not_found(&error) => Err(SaveError::NotFound),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// Save names are shown on a single line, and are limited in length.
+fn check_name(name: &str) -> Result<(), SaveError> {
+    if name.trim().is_empty()
+        || name.encode_utf16().count() > MAX_SAVE_NAME_LENGTH
+        || name.contains(['\r', '\n', '\0'])
+    {
+        return Err(SaveError::InvalidName);
+    }
+
+    Ok(())
+}
+
+fn is_save_id(name: &str) -> bool {
+    name.len() == SAVE_ID_LENGTH && name.bytes().all(|c| c.is_ascii_hexdigit())
+}
+
+/// 48 bit FNV-1a hash of the upper case name, as UTF-16. This is our own
+/// scheme, not the one XAPI uses for save folder names.
+fn save_id_hash(name: &str) -> u64 {
+    let upper: String = name.chars().flat_map(char::to_uppercase).collect();
+
+    let hash = upper
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .fold(0xCBF2_9CE4_8422_2325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100_0000_01B3));
+
+    (hash ^ (hash >> 48)) & 0xFFFF_FFFF_FFFF
+}
+
+fn is_not_found(error: &FsError) -> bool {
+    error
+        .win_error()
+        .is_some_and(|error| matches!(error.into_inner(), ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND))
+}
+
+/// Reads a whole file, or None if it doesn't exist.
+fn read_optional(path: &XboxPath) -> Result<Option<Vec<u8>>, SaveError> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if is_not_found(&error) => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+
+    let mut bytes = Vec::new();
+    let mut buffer = [0u8; 512];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+
+        if read == 0 {
+            return Ok(Some(bytes));
+        }
+
+        bytes.extend_from_slice(&buffer[..read]);
+    }
+}
+
+/// Replaces a file, writing through the cache so it survives a power cut.
+fn write_file(path: &XboxPath, bytes: &[u8]) -> Result<(), SaveError> {
+    let mut file = File::options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .write_through(true)
+        .open(path)?;
+
+    file.write_all(bytes)?;
+    file.sync_all()?;
+
+    Ok(())
+}
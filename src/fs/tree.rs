@@ -0,0 +1,239 @@
+use crate::fs::error::FsError;
+use crate::fs::{create_dir_all, read_dir, remove_dir, remove_file, File};
+use crate::path::{XboxPath, XboxPathBuf};
+use crate::winapi::error::WinError;
+use crate::winapi::file::DirEntry;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+use embedded_io::{Read, Write};
+use nxdk_sys::winapi::ERROR_INVALID_PARAMETER;
+
+/// Buffer size used when copying files. DVD drives read in 32KiB ECC blocks and
+/// favor long sequential transfers, so this is a large multiple of that.
+pub const TREE_COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Progress of a tree operation, reported after every file and every copied chunk.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub bytes_done: u64,
+    pub files_done: u64,
+}
+
+/// An entry found while walking a directory tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalkEntry {
+    /// Full path of this entry, starting with the root given to `walk_dir`.
+    pub path: XboxPathBuf,
+    /// Path of this entry relative to the root, without a leading separator.
+    pub relative_path: XboxPathBuf,
+    /// How deep into the tree this entry is. Direct children of the root are at depth 0.
+    pub depth: usize,
+    pub entry: DirEntry,
+}
+
+/// Totals computed by `dir_size`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirSize {
+    pub bytes: u64,
+    pub files: u64,
+    pub directories: u64,
+}
+
+/// Walks a directory tree, depth-first, calling `callback` for every entry.
+/// Directories are visited before their contents.
+///
+/// `Progress` counts the files visited so far, and the sum of their sizes.
+/// Returning `ControlFlow::Break` from the callback stops the walk with
+/// `FsError::Cancelled`.
+pub fn walk_dir<P, F>(root: P, mut callback: F) -> Result<Progress, FsError>
+where
+    P: AsRef<XboxPath>,
+    F: FnMut(&WalkEntry, &Progress) -> ControlFlow<()>,
+{
+    let mut progress = Progress::default();
+
+    // Explicit stack instead of recursion; thread stacks are small on the Xbox
+    let mut pending: Vec<(XboxPathBuf, XboxPathBuf, usize)> = vec![(root.as_ref().to_path_buf(), XboxPathBuf::new(), 0)];
+
+    while let Some((directory, relative_directory, depth)) = pending.pop() {
+        let mut subdirectories = Vec::new();
+
+        for entry in read_dir(&directory)? {
+            let entry = entry?;
+
+            let walk_entry = WalkEntry {
+                path: directory.join(&entry.name),
+                relative_path: relative_directory.join(&entry.name),
+                depth,
+                entry,
+            };
+
+            if walk_entry.entry.is_file() {
+                progress.files_done += 1;
+                progress.bytes_done += walk_entry.entry.size;
+            }
+
+            if callback(&walk_entry, &progress).is_break() {
+                return Err(FsError::Cancelled);
+            }
+
+            if walk_entry.entry.is_dir() {
+                subdirectories.push((walk_entry.path, walk_entry.relative_path, depth + 1));
+            }
+        }
+
+        // Reversed, so they're popped in listing order
+        pending.extend(subdirectories.into_iter().rev());
+    }
+
+    Ok(progress)
+}
+
+/// Computes the total size of a directory tree.
+pub fn dir_size<P, F>(root: P, mut progress: F) -> Result<DirSize, FsError>
+where
+    P: AsRef<XboxPath>,
+    F: FnMut(&Progress) -> ControlFlow<()>,
+{
+    let mut directories = 0;
+
+    let total = walk_dir(root, |entry, current| {
+        if entry.entry.is_dir() {
+            directories += 1;
+        }
+
+        progress(current)
+    })?;
+
+    Ok(DirSize {
+        bytes: total.bytes_done,
+        files: total.files_done,
+        directories,
+    })
+}
+
+fn copy_file_chunked<F>(from: &XboxPath, to: &XboxPath, buffer: &mut [u8], progress: &mut Progress, callback: &mut F) -> Result<(), FsError>
+where
+    F: FnMut(&Progress) -> ControlFlow<()>,
+{
+    let mut source = File::open(from)?;
+    let mut destination = File::create(to)?;
+
+    loop {
+        let read = source.read(buffer)?;
+
+        if read == 0 {
+            break;
+        }
+
+        destination.write_all(&buffer[..read])?;
+        progress.bytes_done += read as u64;
+
+        if callback(progress).is_break() {
+            drop(destination);
+            let _ = remove_file(to);
+
+            return Err(FsError::Cancelled);
+        }
+    }
+
+    // Keep the original modification time around, as installers expect
+    if let Ok(source_info) = source.as_handle().query_basic_information() {
+        let _ = destination.set_modified(source_info.last_write_time);
+    }
+
+    Ok(())
+}
+
+/// Recursively copies the contents of the `from` directory into `to`, creating
+/// it and any missing parents. Existing files are overwritten.
+///
+/// `Progress` is reported after every copied chunk and every finished file.
+/// On cancellation, the partially copied file is removed; files already copied are kept.
+///
+/// Copying a directory into itself or one of its descendants fails with
+/// `ERROR_INVALID_PARAMETER`, before anything is created.
+pub fn copy_dir_all<P, Q, F>(from: P, to: Q, mut progress: F) -> Result<Progress, FsError>
+where
+    P: AsRef<XboxPath>,
+    Q: AsRef<XboxPath>,
+    F: FnMut(&Progress) -> ControlFlow<()>,
+{
+    let to = to.as_ref();
+
+    // The walk would find the copy and recurse into it until the disk fills up
+    if to.normalize().starts_with(from.as_ref().normalize()) {
+        return Err(FsError::Win(WinError::from(ERROR_INVALID_PARAMETER)));
+    }
+
+    create_dir_all(to)?;
+
+    let mut buffer = vec![0u8; TREE_COPY_BUFFER_SIZE];
+    let mut current = Progress::default();
+    let mut result = Ok(());
+
+    let walk_result = walk_dir(from, |entry, _| {
+        let destination = to.join(&entry.relative_path);
+
+        result = if entry.entry.is_dir() {
+            create_dir_all(&destination)
+        } else {
+            copy_file_chunked(&entry.path, &destination, &mut buffer, &mut current, &mut progress)
+                .map(|_| current.files_done += 1)
+        };
+
+        if result.is_err() {
+            return ControlFlow::Break(());
+        }
+
+        progress(&current)
+    });
+
+    // Prefer the error that stopped the walk over the generic cancellation
+    result?;
+    walk_result?;
+
+    Ok(current)
+}
+
+/// Recursively removes a directory and all of its contents.
+///
+/// `Progress` counts removed files and their sizes. On cancellation, anything
+/// already removed stays removed.
+pub fn remove_dir_all<P, F>(path: P, mut progress: F) -> Result<Progress, FsError>
+where
+    P: AsRef<XboxPath>,
+    F: FnMut(&Progress) -> ControlFlow<()>,
+{
+    let path = path.as_ref();
+    let mut directories = Vec::new();
+    let mut result = Ok(());
+
+    let walk_result = walk_dir(path, |entry, current| {
+        if entry.entry.is_dir() {
+            directories.push(entry.path.clone());
+            return ControlFlow::Continue(());
+        }
+
+        result = remove_file(&entry.path);
+
+        if result.is_err() {
+            return ControlFlow::Break(());
+        }
+
+        progress(current)
+    });
+
+    result?;
+    let total = walk_result?;
+
+    // Directories were collected parents first, so remove them deepest first
+    for directory in directories.iter().rev() {
+        remove_dir(directory)?;
+    }
+
+    remove_dir(path)?;
+
+    Ok(total)
+}
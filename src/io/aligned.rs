@@ -0,0 +1,94 @@
+use alloc::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use core::fmt::{Debug, Formatter};
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+/// Sector size of the DVD drive.
+pub const DVD_SECTOR_SIZE: usize = 2048;
+
+/// Sector size of the hard drive.
+pub const HDD_SECTOR_SIZE: usize = 512;
+
+/// A zeroed heap buffer whose start is aligned to `align` bytes.
+///
+/// Files opened with `FileFlagsAndAttributes::FlagNoBuffering` need buffers
+/// aligned to the sector size, with lengths and offsets in whole sectors.
+/// The buffer has a stable address, so it can be handed to an `IoRequest`.
+pub struct AlignedBuffer {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+unsafe impl Send for AlignedBuffer {}
+unsafe impl Sync for AlignedBuffer {}
+
+impl AlignedBuffer {
+    /// Allocates `len` zeroed bytes aligned to `align`, which must be a power of two.
+    pub fn new(len: usize, align: usize) -> Self {
+        let layout = Layout::from_size_align(len, align).expect("invalid buffer alignment");
+
+        // Zero sized allocations aren't allowed, but any aligned dangling pointer will do
+        let ptr = if len == 0 {
+            NonNull::new(align as *mut u8)
+        } else {
+            NonNull::new(unsafe { alloc_zeroed(layout) })
+        };
+
+        Self {
+            ptr: ptr.unwrap_or_else(|| handle_alloc_error(layout)),
+            layout,
+        }
+    }
+
+    /// Allocates `sectors` whole sectors, aligned to the sector size.
+    pub fn sectors(sectors: usize, sector_size: usize) -> Self {
+        Self::new(sectors * sector_size, sector_size)
+    }
+
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        if self.layout.size() != 0 {
+            unsafe { dealloc(self.ptr.as_ptr(), self.layout) }
+        }
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.layout.size()) }
+    }
+}
+
+impl AsRef<[u8]> for AlignedBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsMut<[u8]> for AlignedBuffer {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl Debug for AlignedBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AlignedBuffer")
+            .field("len", &self.layout.size())
+            .field("align", &self.layout.align())
+            .finish()
+    }
+}
@@ -0,0 +1,361 @@
+use crate::io::{trim_newline, ReadLineError, DEFAULT_BUF_SIZE};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Internal buffer shared by the sync and async readers.
+#[derive(Debug)]
+struct Buffer {
+    data: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+}
+
+impl Buffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            data: vec![0u8; capacity.max(1)].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    fn available(&self) -> &[u8] {
+        &self.data[self.pos..self.filled]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.filled
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.filled);
+    }
+
+    fn discard(&mut self) {
+        self.pos = 0;
+        self.filled = 0;
+    }
+
+    /// Copies buffered data into `buf`, returning how many bytes were copied.
+    fn read_into(&mut self, buf: &mut [u8]) -> usize {
+        let available = self.available();
+        let len = available.len().min(buf.len());
+
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+
+        len
+    }
+
+    /// Appends buffered data to `out` up to and including `delimiter`. Returns
+    /// how many bytes were appended, and whether the delimiter was found.
+    fn take_until(&mut self, delimiter: u8, out: &mut Vec<u8>) -> (usize, bool) {
+        let available = self.available();
+
+        let (len, found) = match available.iter().position(|&b| b == delimiter) {
+            Some(index) => (index + 1, true),
+            None => (available.len(), false),
+        };
+
+        out.extend_from_slice(&available[..len]);
+        self.consume(len);
+
+        (len, found)
+    }
+}
+
+/// Appends `bytes` to `line` if they're valid UTF-8.
+fn push_utf8<E>(bytes: Vec<u8>, line: &mut String) -> Result<usize, ReadLineError<E>> {
+    let read = bytes.len();
+    let text = String::from_utf8(bytes).map_err(|_| ReadLineError::InvalidUtf8)?;
+
+    line.push_str(&text);
+
+    Ok(read)
+}
+
+/// Adds buffering to any blocking reader, such as a `WinFileHandle` or a `NetconnTcp`.
+///
+/// Small reads are served from an internal buffer, which is refilled with a
+/// single large read from the inner reader.
+#[derive(Debug)]
+pub struct BufReader<R> {
+    inner: R,
+    buffer: Buffer,
+}
+
+impl<R: embedded_io::Read> BufReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self {
+            inner,
+            buffer: Buffer::new(capacity),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Mutable access to the inner reader. Reading from it directly skips
+    /// anything still buffered.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Data read from the inner reader, but not yet consumed.
+    pub fn buffer(&self) -> &[u8] {
+        self.buffer.available()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.data.len()
+    }
+
+    /// Unwraps this reader. Any buffered data is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads into `buf` until `delimiter` is found, or EOF is reached. The
+    /// delimiter is included. Returns the number of bytes read; 0 means EOF.
+    pub fn read_until(&mut self, delimiter: u8, buf: &mut Vec<u8>) -> Result<usize, R::Error> {
+        let mut read = 0;
+
+        loop {
+            if embedded_io::BufRead::fill_buf(self)?.is_empty() {
+                return Ok(read);
+            }
+
+            let (len, found) = self.buffer.take_until(delimiter, buf);
+            read += len;
+
+            if found {
+                return Ok(read);
+            }
+        }
+    }
+
+    /// Appends a line, including its line ending, to `line`. Returns the number
+    /// of bytes read; 0 means EOF.
+    ///
+    /// If the line isn't valid UTF-8, `line` is left untouched.
+    pub fn read_line(&mut self, line: &mut String) -> Result<usize, ReadLineError<R::Error>> {
+        let mut bytes = Vec::new();
+        self.read_until(b'\n', &mut bytes)?;
+
+        push_utf8(bytes, line)
+    }
+
+    /// Iterator over the lines of this reader, without their `\n` or `\r\n` endings.
+    pub fn lines(self) -> Lines<R> {
+        Lines { reader: self }
+    }
+}
+
+impl<R: embedded_io::ErrorType> embedded_io::ErrorType for BufReader<R> {
+    type Error = R::Error;
+}
+
+impl<R: embedded_io::Read> embedded_io::Read for BufReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        // Skip the buffer entirely for large reads
+        if self.buffer.is_empty() && buf.len() >= self.capacity() {
+            self.buffer.discard();
+            return self.inner.read(buf);
+        }
+
+        embedded_io::BufRead::fill_buf(self)?;
+
+        Ok(self.buffer.read_into(buf))
+    }
+}
+
+impl<R: embedded_io::Read> embedded_io::BufRead for BufReader<R> {
+    fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        if self.buffer.is_empty() {
+            self.buffer.discard();
+            self.buffer.filled = self.inner.read(&mut self.buffer.data)?;
+        }
+
+        Ok(self.buffer.available())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.buffer.consume(amt);
+    }
+}
+
+/// Iterator over the lines of a `BufReader`, see `BufReader::lines`.
+#[derive(Debug)]
+pub struct Lines<R> {
+    reader: BufReader<R>,
+}
+
+impl<R> Lines<R> {
+    pub fn into_inner(self) -> BufReader<R> {
+        self.reader
+    }
+}
+
+impl<R: embedded_io::Read> Iterator for Lines<R> {
+    type Item = Result<String, ReadLineError<R::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+
+        match self.reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                trim_newline(&mut line);
+                Some(Ok(line))
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// Async version of `BufReader`, for readers implementing `embedded_io_async::Read`.
+#[derive(Debug)]
+pub struct AsyncBufReader<R> {
+    inner: R,
+    buffer: Buffer,
+}
+
+impl<R: embedded_io_async::Read> AsyncBufReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self {
+            inner,
+            buffer: Buffer::new(capacity),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Mutable access to the inner reader. Reading from it directly skips
+    /// anything still buffered.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Data read from the inner reader, but not yet consumed.
+    pub fn buffer(&self) -> &[u8] {
+        self.buffer.available()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.data.len()
+    }
+
+    /// Unwraps this reader. Any buffered data is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads into `buf` until `delimiter` is found, or EOF is reached. The
+    /// delimiter is included. Returns the number of bytes read; 0 means EOF.
+    pub async fn read_until(&mut self, delimiter: u8, buf: &mut Vec<u8>) -> Result<usize, R::Error> {
+        let mut read = 0;
+
+        loop {
+            if embedded_io_async::BufRead::fill_buf(self).await?.is_empty() {
+                return Ok(read);
+            }
+
+            let (len, found) = self.buffer.take_until(delimiter, buf);
+            read += len;
+
+            if found {
+                return Ok(read);
+            }
+        }
+    }
+
+    /// Appends a line, including its line ending, to `line`. Returns the number
+    /// of bytes read; 0 means EOF.
+    ///
+    /// If the line isn't valid UTF-8, `line` is left untouched.
+    pub async fn read_line(&mut self, line: &mut String) -> Result<usize, ReadLineError<R::Error>> {
+        let mut bytes = Vec::new();
+        self.read_until(b'\n', &mut bytes).await?;
+
+        push_utf8(bytes, line)
+    }
+
+    /// Stream of the lines of this reader, without their `\n` or `\r\n` endings.
+    pub fn lines(self) -> AsyncLines<R> {
+        AsyncLines { reader: self }
+    }
+}
+
+impl<R: embedded_io_async::ErrorType> embedded_io_async::ErrorType for AsyncBufReader<R> {
+    type Error = R::Error;
+}
+
+impl<R: embedded_io_async::Read> embedded_io_async::Read for AsyncBufReader<R> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        // Skip the buffer entirely for large reads
+        if self.buffer.is_empty() && buf.len() >= self.capacity() {
+            self.buffer.discard();
+            return self.inner.read(buf).await;
+        }
+
+        embedded_io_async::BufRead::fill_buf(self).await?;
+
+        Ok(self.buffer.read_into(buf))
+    }
+}
+
+impl<R: embedded_io_async::Read> embedded_io_async::BufRead for AsyncBufReader<R> {
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        if self.buffer.is_empty() {
+            self.buffer.discard();
+            self.buffer.filled = self.inner.read(&mut self.buffer.data).await?;
+        }
+
+        Ok(self.buffer.available())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.buffer.consume(amt);
+    }
+}
+
+/// Lines of an `AsyncBufReader`, see `AsyncBufReader::lines`.
+///
+/// This works like an iterator, through the async `next` method.
+#[derive(Debug)]
+pub struct AsyncLines<R> {
+    reader: AsyncBufReader<R>,
+}
+
+impl<R: embedded_io_async::Read> AsyncLines<R> {
+    /// Returns the next line, or None at EOF.
+    pub async fn next(&mut self) -> Option<Result<String, ReadLineError<R::Error>>> {
+        let mut line = String::new();
+
+        match self.reader.read_line(&mut line).await {
+            Ok(0) => None,
+            Ok(_) => {
+                trim_newline(&mut line);
+                Some(Ok(line))
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+
+    pub fn into_inner(self) -> AsyncBufReader<R> {
+        self.reader
+    }
+}
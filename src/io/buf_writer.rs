@@ -0,0 +1,252 @@
+use crate::io::{BufWriteError, DEFAULT_BUF_SIZE};
+use alloc::vec::Vec;
+use core::mem::ManuallyDrop;
+
+/// Adds buffering to any blocking writer, such as a `WinFileHandle` or a `NetconnTcp`.
+///
+/// Small writes are collected into an internal buffer, and handed to the inner
+/// writer in a single large write once it fills up, or on `flush`.
+///
+/// The buffer is flushed on drop, ignoring any errors. Call `flush` to handle them.
+#[derive(Debug)]
+pub struct BufWriter<W: embedded_io::Write> {
+    inner: W,
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+impl<W: embedded_io::Write> BufWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        let capacity = capacity.max(1);
+
+        Self {
+            inner,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Mutable access to the inner writer. Writing to it directly skips
+    /// anything still buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Data written to this writer, but not yet handed to the inner writer.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Size the buffer fills up to before being handed to the inner writer.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Hands the buffer to the inner writer, without flushing the inner writer itself.
+    ///
+    /// Fails with `WriteZero` if the inner writer stops accepting data, keeping
+    /// whatever couldn't be written for the next attempt.
+    fn flush_buffer(&mut self) -> Result<(), BufWriteError<W::Error>> {
+        let mut written = 0;
+        let mut result = Ok(());
+
+        while written < self.buffer.len() {
+            match self.inner.write(&self.buffer[written..]) {
+                Ok(0) => {
+                    result = Err(BufWriteError::WriteZero);
+                    break;
+                }
+                Ok(len) => written += len,
+                Err(error) => {
+                    result = Err(BufWriteError::Io(error));
+                    break;
+                }
+            }
+        }
+
+        self.buffer.drain(..written);
+        result
+    }
+
+    /// Flushes the buffer and unwraps this writer.
+    ///
+    /// If flushing fails, the error is returned along with this writer, so no data is lost.
+    pub fn into_inner(mut self) -> Result<W, (BufWriteError<W::Error>, Self)> {
+        if let Err(error) = self.flush_buffer() {
+            return Err((error, self));
+        }
+
+        let mut this = ManuallyDrop::new(self);
+
+        // Safety: `this` is never used or dropped again, so both fields are moved out exactly once
+        unsafe {
+            core::ptr::drop_in_place(&mut this.buffer);
+            Ok(core::ptr::read(&this.inner))
+        }
+    }
+}
+
+impl<W: embedded_io::Write> embedded_io::ErrorType for BufWriter<W> {
+    type Error = BufWriteError<W::Error>;
+}
+
+impl<W: embedded_io::Write> embedded_io::Write for BufWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.buffer.len() + buf.len() > self.capacity() {
+            self.flush_buffer()?;
+        }
+
+        // Skip the buffer entirely for large writes
+        if self.buffer.is_empty() && buf.len() >= self.capacity() {
+            return match self.inner.write(buf)? {
+                0 => Err(BufWriteError::WriteZero),
+                len => Ok(len),
+            };
+        }
+
+        let len = buf.len().min(self.capacity() - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.flush_buffer()?;
+        Ok(self.inner.flush()?)
+    }
+}
+
+impl<W: embedded_io::Write> Drop for BufWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush_buffer();
+    }
+}
+
+/// Async version of `BufWriter`, for writers implementing `embedded_io_async::Write`.
+///
+/// Unlike `BufWriter`, the buffer can't be flushed on drop. Anything not
+/// flushed with `flush` or `into_inner` is lost.
+#[derive(Debug)]
+pub struct AsyncBufWriter<W> {
+    inner: W,
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+impl<W: embedded_io_async::Write> AsyncBufWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        let capacity = capacity.max(1);
+
+        Self {
+            inner,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Mutable access to the inner writer. Writing to it directly skips
+    /// anything still buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Data written to this writer, but not yet handed to the inner writer.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Size the buffer fills up to before being handed to the inner writer.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Hands the buffer to the inner writer, without flushing the inner writer itself.
+    ///
+    /// Fails with `WriteZero` if the inner writer stops accepting data, keeping
+    /// whatever couldn't be written for the next attempt.
+    async fn flush_buffer(&mut self) -> Result<(), BufWriteError<W::Error>> {
+        let mut written = 0;
+        let mut result = Ok(());
+
+        while written < self.buffer.len() {
+            match self.inner.write(&self.buffer[written..]).await {
+                Ok(0) => {
+                    result = Err(BufWriteError::WriteZero);
+                    break;
+                }
+                Ok(len) => written += len,
+                Err(error) => {
+                    result = Err(BufWriteError::Io(error));
+                    break;
+                }
+            }
+        }
+
+        self.buffer.drain(..written);
+        result
+    }
+
+    /// Flushes the buffer and unwraps this writer.
+    ///
+    /// If flushing fails, the error is returned along with this writer, so no data is lost.
+    pub async fn into_inner(mut self) -> Result<W, (BufWriteError<W::Error>, Self)> {
+        match self.flush_buffer().await {
+            Ok(()) => Ok(self.inner),
+            Err(error) => Err((error, self)),
+        }
+    }
+}
+
+impl<W: embedded_io_async::ErrorType> embedded_io_async::ErrorType for AsyncBufWriter<W> {
+    type Error = BufWriteError<W::Error>;
+}
+
+impl<W: embedded_io_async::Write> embedded_io_async::Write for AsyncBufWriter<W> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.buffer.len() + buf.len() > self.capacity() {
+            self.flush_buffer().await?;
+        }
+
+        // Skip the buffer entirely for large writes
+        if self.buffer.is_empty() && buf.len() >= self.capacity() {
+            return match self.inner.write(buf).await? {
+                0 => Err(BufWriteError::WriteZero),
+                len => Ok(len),
+            };
+        }
+
+        let len = buf.len().min(self.capacity() - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+
+        Ok(len)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.flush_buffer().await?;
+        Ok(self.inner.flush().await?)
+    }
+}
@@ -0,0 +1,141 @@
+pub mod aligned;
+pub mod buf_reader;
+pub mod buf_writer;
+pub mod stream_reader;
+
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Debug, Display, Formatter};
+
+pub use aligned::{AlignedBuffer, DVD_SECTOR_SIZE, HDD_SECTOR_SIZE};
+pub use buf_reader::{AsyncBufReader, AsyncLines, BufReader, Lines};
+pub use buf_writer::{AsyncBufWriter, BufWriter};
+pub use stream_reader::SectorStreamReader;
+
+/// Default capacity of the buffered adapters.
+pub const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+/// Error returned by the line helpers of the buffered readers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadLineError<E> {
+    Io(E),
+    /// The line isn't valid UTF-8. The bytes of that line have been consumed.
+    InvalidUtf8,
+}
+
+impl<E> From<E> for ReadLineError<E> {
+    fn from(value: E) -> Self {
+        ReadLineError::Io(value)
+    }
+}
+
+impl<E: Debug> Display for ReadLineError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ReadLineError::Io(error) => write!(f, "IO error: {:?}", error),
+            ReadLineError::InvalidUtf8 => write!(f, "Line is not valid UTF-8"),
+        }
+    }
+}
+
+impl<E: Debug> Error for ReadLineError<E> {}
+
+impl<E: embedded_io::Error> embedded_io::Error for ReadLineError<E> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            ReadLineError::Io(error) => error.kind(),
+            ReadLineError::InvalidUtf8 => embedded_io::ErrorKind::InvalidData,
+        }
+    }
+}
+
+/// Error returned by the buffered writers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufWriteError<E> {
+    Io(E),
+    /// The inner writer accepted no data. Whatever it didn't take is still buffered.
+    WriteZero,
+}
+
+impl<E> From<E> for BufWriteError<E> {
+    fn from(value: E) -> Self {
+        BufWriteError::Io(value)
+    }
+}
+
+impl<E: Debug> Display for BufWriteError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BufWriteError::Io(error) => write!(f, "IO error: {:?}", error),
+            BufWriteError::WriteZero => write!(f, "Inner writer accepted no data"),
+        }
+    }
+}
+
+impl<E: Debug> Error for BufWriteError<E> {}
+
+impl<E: embedded_io::Error> embedded_io::Error for BufWriteError<E> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            BufWriteError::Io(error) => error.kind(),
+            BufWriteError::WriteZero => embedded_io::ErrorKind::WriteZero,
+        }
+    }
+}
+
+/// Reads everything until the end of `reader`, appending it to `buf`.
+///
+/// Returns the number of bytes read.
+pub fn read_to_end<R: embedded_io::Read>(reader: &mut R, buf: &mut Vec<u8>) -> Result<usize, R::Error> {
+    let start = buf.len();
+
+    loop {
+        let len = buf.len();
+        buf.resize(len + DEFAULT_BUF_SIZE, 0);
+
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => {
+                buf.truncate(len);
+                return Ok(len - start);
+            }
+            Ok(read) => buf.truncate(len + read),
+            Err(error) => {
+                buf.truncate(len);
+                return Err(error);
+            }
+        }
+    }
+}
+
+/// Async version of `read_to_end`.
+pub async fn read_to_end_async<R: embedded_io_async::Read>(reader: &mut R, buf: &mut Vec<u8>) -> Result<usize, R::Error> {
+    let start = buf.len();
+
+    loop {
+        let len = buf.len();
+        buf.resize(len + DEFAULT_BUF_SIZE, 0);
+
+        match reader.read(&mut buf[len..]).await {
+            Ok(0) => {
+                buf.truncate(len);
+                return Ok(len - start);
+            }
+            Ok(read) => buf.truncate(len + read),
+            Err(error) => {
+                buf.truncate(len);
+                return Err(error);
+            }
+        }
+    }
+}
+
+/// Removes a trailing `\n` or `\r\n` from a line.
+fn trim_newline(line: &mut alloc::string::String) {
+    if line.ends_with('\n') {
+        line.pop();
+
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use crate::fs::error::FsError;
+use crate::fs::{File, OpenOptions};
+use crate::io::aligned::AlignedBuffer;
+use crate::path::XboxPath;
+use crate::winapi::error::WinError;
+use crate::winapi::file::FileFlagsAndAttributes;
+use crate::winapi::io::IoRequest;
+use nxdk_sys::winapi::{ERROR_INVALID_PARAMETER, ERROR_IO_PENDING};
+
+/// Default block size of `SectorStreamReader`; two 32KiB DVD ECC blocks.
+pub const DEFAULT_STREAM_BLOCK_SIZE: usize = 64 * 1024;
+
+/// Streams a file through unbuffered, double-buffered overlapped reads.
+///
+/// Two sector-aligned blocks are kept in flight. While the caller works on
+/// one, the next is already being read, so the drive never sits idle between
+/// calls. This is meant for long sequential reads, such as video and audio
+/// streamed from DVD.
+///
+/// ```ignore
+/// let mut stream = SectorStreamReader::open("D:\\media\\intro.xmv", DVD_SECTOR_SIZE)?;
+///
+/// while let Some(block) = stream.next_block().await? {
+///     decoder.feed(block);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SectorStreamReader {
+    // Declared before the file, so in-flight reads are waited for before it closes
+    requests: [IoRequest<AlignedBuffer>; 2],
+    file: File,
+    len: u64,
+    sector_size: usize,
+    block_size: usize,
+    /// Offset the next block will be requested at.
+    next_offset: u64,
+    /// Request whose block comes next.
+    current: usize,
+    /// Request whose block was last handed out, to be refilled on the next call.
+    holding: Option<usize>,
+    started: bool,
+    /// Bytes to drop from the start of the next block, after an unaligned seek.
+    skip: usize,
+    /// Range of the held block not yet consumed through `read`.
+    block_start: usize,
+    block_end: usize,
+    /// A failed read, returned by every call until the next seek.
+    error: Option<WinError>,
+}
+
+impl SectorStreamReader {
+    /// Opens a file for streaming, with `FlagNoBuffering` and `FlagOverlapped`.
+    ///
+    /// `sector_size` must match the device, such as `DVD_SECTOR_SIZE` or `HDD_SECTOR_SIZE`.
+    pub fn open<P: AsRef<XboxPath>>(path: P, sector_size: usize) -> Result<Self, FsError> {
+        Self::with_block_size(path, sector_size, DEFAULT_STREAM_BLOCK_SIZE)
+    }
+
+    /// Like `open`, with a custom block size. It's rounded up to whole sectors.
+    ///
+    /// Fails with `ERROR_INVALID_PARAMETER` if `sector_size` isn't a power of
+    /// two, or the rounded block size doesn't fit in memory.
+    pub fn with_block_size<P: AsRef<XboxPath>>(path: P, sector_size: usize, block_size: usize) -> Result<Self, FsError> {
+        let block_size = block_size
+            .max(1)
+            .checked_next_multiple_of(sector_size)
+            .filter(|&block_size| sector_size.is_power_of_two() && block_size <= isize::MAX as usize)
+            .ok_or(WinError::from(ERROR_INVALID_PARAMETER))?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .flags_attributes(FileFlagsAndAttributes::FlagNoBuffering | FileFlagsAndAttributes::FlagOverlapped)
+            .open(path)?;
+
+        let len = file.len()?;
+
+        Ok(Self {
+            requests: [
+                IoRequest::new(AlignedBuffer::new(block_size, sector_size))?,
+                IoRequest::new(AlignedBuffer::new(block_size, sector_size))?,
+            ],
+            file,
+            len,
+            sector_size,
+            block_size,
+            next_offset: 0,
+            current: 0,
+            holding: None,
+            started: false,
+            skip: 0,
+            block_start: 0,
+            block_end: 0,
+            error: None,
+        })
+    }
+
+    /// Size of the file, as of opening it.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn issue(&mut self, index: usize) -> Result<(), WinError> {
+        if self.next_offset >= self.len {
+            return Ok(());
+        }
+
+        let handle = self.file.as_handle().as_raw_handle()?;
+        self.requests[index].start_read(handle, self.next_offset, self.block_size)?;
+        self.next_offset += self.block_size as u64;
+
+        Ok(())
+    }
+
+    /// Waits for the next block, and starts reading the one after it. Returns
+    /// None at the end of the file.
+    ///
+    /// The block stays valid until the next call, while the following block
+    /// is already being read. Anything not consumed through `read` is dropped.
+    ///
+    /// Once a read fails, the stream is stuck: every call returns the same
+    /// error, rather than skipping the block, until `seek` restarts it.
+    pub async fn next_block(&mut self) -> Result<Option<&[u8]>, FsError> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+
+        match self.advance().await {
+            Ok(true) => {
+                let buffer = self.requests[self.current].buffer().ok_or(WinError::from(ERROR_IO_PENDING))?;
+                Ok(Some(&buffer[self.block_start..self.block_end]))
+            }
+            Ok(false) => Ok(None),
+            Err(error) => {
+                self.error = Some(error);
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Moves on to the next block, returning whether there is one.
+    async fn advance(&mut self) -> Result<bool, WinError> {
+        if let Some(index) = self.holding.take() {
+            // The caller is done with this buffer, so it can take the block after the other one
+            self.issue(index)?;
+            self.current = 1 - index;
+        } else if !self.started {
+            self.issue(0)?;
+            self.issue(1)?;
+            self.current = 0;
+            self.started = true;
+        }
+
+        let index = self.current;
+        self.block_start = 0;
+        self.block_end = 0;
+
+        if !self.requests[index].is_pending() {
+            return Ok(false);
+        }
+
+        let read = self.requests[index].wait().await?;
+        self.holding = Some(index);
+
+        if read == 0 {
+            return Ok(false);
+        }
+
+        self.block_start = core::mem::take(&mut self.skip).min(read);
+        self.block_end = read;
+
+        Ok(true)
+    }
+
+    /// Moves the stream to `offset`. Reads in flight are waited for and
+    /// dropped, whatever their result, and reading restarts from the sector
+    /// holding `offset`. This also clears a failed read, so it can be retried.
+    pub fn seek(&mut self, offset: u64) -> Result<(), FsError> {
+        for request in &mut self.requests {
+            if request.is_pending() {
+                // The data is being thrown away, so a failed read doesn't matter
+                let _ = request.wait_blocking();
+            }
+        }
+
+        let sector_offset = offset - offset % self.sector_size as u64;
+
+        self.next_offset = sector_offset;
+        self.skip = (offset - sector_offset) as usize;
+        self.holding = None;
+        self.started = false;
+        self.block_start = 0;
+        self.block_end = 0;
+        self.error = None;
+
+        Ok(())
+    }
+
+    pub fn into_file(self) -> File {
+        let Self { requests, file, .. } = self;
+
+        // Waits for anything in flight before handing the file out
+        drop(requests);
+        file
+    }
+}
+
+impl embedded_io_async::ErrorType for SectorStreamReader {
+    type Error = FsError;
+}
+
+impl embedded_io_async::Read for SectorStreamReader {
+    /// Copies out of the current block, moving on to the next one once it's used up.
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.block_start == self.block_end && self.next_block().await?.is_none() {
+            return Ok(0);
+        }
+
+        let index = self.holding.unwrap_or(self.current);
+        let buffer = self.requests[index].buffer().ok_or(WinError::from(ERROR_IO_PENDING))?;
+
+        let len = buf.len().min(self.block_end - self.block_start);
+        buf[..len].copy_from_slice(&buffer[self.block_start..self.block_start + len]);
+        self.block_start += len;
+
+        Ok(len)
+    }
+}
@@ -0,0 +1,24 @@
+use nxdk_formats::signature::{TitleSignatureKey, XboxKey};
+use nxdk_sys::kernel::{XboxHDKey, XboxSignatureKey};
+
+/// The kernel's `XboxSignatureKey`, shared by all retail consoles.
+pub fn xbox_signature_key() -> XboxKey {
+    unsafe { XboxSignatureKey }
+}
+
+/// The kernel's `XboxHDKey`, unique to this console.
+pub fn xbox_hd_key() -> XboxKey {
+    unsafe { XboxHDKey }
+}
+
+/// Derives the key a title signs its saves with, as `XCalculateSignatureBegin` does.
+///
+/// `certificate_key` is the signature key of the title's XBE certificate.
+/// Non roamable signatures only verify on this console.
+pub fn title_signature_key(certificate_key: &XboxKey, roamable: bool) -> TitleSignatureKey {
+    if roamable {
+        TitleSignatureKey::roamable(&xbox_signature_key(), certificate_key)
+    } else {
+        TitleSignatureKey::non_roamable(&xbox_signature_key(), &xbox_hd_key(), certificate_key)
+    }
+}
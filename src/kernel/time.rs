@@ -34,9 +34,16 @@ pub fn query_system_time() -> u64 {
     }
 }
 
-/// Converts from Windows timestamp to Unix seconds timestamp
+/// Converts from Windows timestamp to Unix seconds timestamp.
+///
+/// Timestamps before the Unix epoch are clamped to 0.
 pub fn windows_to_unix_timestamp(sys_time: &u64) -> u64 {
-    (sys_time - WINDOWS_EPOCH) / 10_000_000
+    sys_time.saturating_sub(WINDOWS_EPOCH) / 10_000_000
+}
+
+/// Converts from Unix seconds timestamp to Windows timestamp
+pub fn unix_to_windows_timestamp(unix_time: &u64) -> u64 {
+    unix_time * 10_000_000 + WINDOWS_EPOCH
 }
 
 /// Gets the unix timestamp from the system clock, in seconds.
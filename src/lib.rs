@@ -4,6 +4,7 @@
 extern crate alloc;
 
 pub use nxdk_sys as sys;
+pub use nxdk_formats as formats;
 pub use bitflags;
 pub use embedded_io;
 pub use embedded_io_async;
@@ -14,4 +15,7 @@ pub mod utils;
 pub mod xbox_alloc;
 pub mod lwip;
 pub mod winapi;
-pub mod kernel;
\ No newline at end of file
+pub mod kernel;
+pub mod fs;
+pub mod path;
+pub mod io;
\ No newline at end of file
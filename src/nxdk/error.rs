@@ -1,3 +1,5 @@
+use crate::utils::error::PlatformError;
+use crate::winapi::error::{NtStatusError, WinError};
 use core::error::Error;
 use core::fmt::{Display, Formatter};
 
@@ -37,3 +39,106 @@ impl Display for NxNetError {
 }
 
 impl Error for NxNetError {}
+
+/// Errors from querying or translating drive mounts.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NxMountError {
+    /// Drive letters must be ASCII letters.
+    InvalidDriveLetter(char),
+    /// The drive letter has no mount point.
+    NotMounted(char),
+    /// The path doesn't start with a drive letter.
+    NoDriveLetter,
+    /// No mounted drive points to the given NT path.
+    NoMatchingDrive,
+    /// The drive letter is already mounted.
+    AlreadyMounted(char),
+    /// The kernel refused to create the symbolic link of this drive letter.
+    MountFailed(char, NtStatusError),
+    /// The kernel refused to delete the symbolic link of this drive letter.
+    UnmountFailed(char, NtStatusError),
+    /// The running XBE path has no directory to mount.
+    NoParentDirectory,
+    Path(PlatformError),
+    NtStatus(NtStatusError),
+}
+
+impl From<PlatformError> for NxMountError {
+    fn from(value: PlatformError) -> Self {
+        NxMountError::Path(value)
+    }
+}
+
+impl From<NtStatusError> for NxMountError {
+    fn from(value: NtStatusError) -> Self {
+        NxMountError::NtStatus(value)
+    }
+}
+
+impl Display for NxMountError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NxMountError::InvalidDriveLetter(letter) => write!(f, "Invalid drive letter: {}", letter),
+            NxMountError::NotMounted(letter) => write!(f, "Drive {}: is not mounted", letter),
+            NxMountError::NoDriveLetter => write!(f, "Path has no drive letter"),
+            NxMountError::NoMatchingDrive => write!(f, "No mounted drive matches this path"),
+            NxMountError::AlreadyMounted(letter) => write!(f, "Drive {}: is already mounted", letter),
+            NxMountError::MountFailed(letter, status) => write!(f, "Failed to mount drive {}: ({})", letter, status),
+            NxMountError::UnmountFailed(letter, status) => {
+                write!(f, "Failed to unmount drive {}: ({})", letter, status)
+            }
+            NxMountError::NoParentDirectory => write!(f, "Path has no parent directory"),
+            NxMountError::Path(error) => write!(f, "Path error: {}", error),
+            NxMountError::NtStatus(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for NxMountError {}
+
+/// Errors from formatting a partition.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NxFormatError {
+    /// The confirmation was made for a different partition.
+    ConfirmationMismatch,
+    /// The partition table of the drive has no such partition.
+    NoSuchPartition,
+    /// The partition table couldn't be read.
+    PartitionTable(NxMountError),
+    /// The running XBE lives on this partition.
+    RunningFromPartition,
+    /// The partition is mounted to this drive letter, and must be unmounted first.
+    PartitionMounted(char),
+    /// Mounts couldn't be checked.
+    Mount(NxMountError),
+    /// `nxFormatVolume` failed.
+    WinError(WinError),
+}
+
+impl From<NxMountError> for NxFormatError {
+    fn from(value: NxMountError) -> Self {
+        NxFormatError::Mount(value)
+    }
+}
+
+impl From<WinError> for NxFormatError {
+    fn from(value: WinError) -> Self {
+        NxFormatError::WinError(value)
+    }
+}
+
+impl Display for NxFormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NxFormatError::ConfirmationMismatch => write!(f, "Confirmation was given for another partition"),
+            NxFormatError::NoSuchPartition => write!(f, "The drive has no such partition"),
+            NxFormatError::PartitionTable(error) => write!(f, "Failed to read the partition table: {}", error),
+            NxFormatError::RunningFromPartition => write!(f, "The running XBE is on this partition"),
+            NxFormatError::PartitionMounted(letter) => write!(f, "Partition is mounted as {}:", letter),
+            NxFormatError::Mount(error) => write!(f, "Failed to check mounts: {}", error),
+            NxFormatError::WinError(error) => write!(f, "Format failed: {}", error),
+        }
+    }
+}
+
+impl Error for NxFormatError {}
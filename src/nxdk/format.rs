@@ -1,5 +1,12 @@
+use crate::nxdk::error::NxFormatError;
+use crate::nxdk::mount::{nt_path_starts_with, nx_list_mounts};
+use crate::nxdk::partition::nx_read_partition_table;
+use crate::nxdk::path::nx_get_current_xbe_nt_path_native;
 use crate::utils::error::PlatformError;
 use crate::utils::path_str_to_cstr;
+use crate::winapi::error::WinError;
+use alloc::string::String;
+use nxdk_formats::partition;
 use nxdk_sys::nxdk::format::nxFormatVolume;
 
 /// Formats a volume with FATX.
@@ -8,6 +15,8 @@ use nxdk_sys::nxdk::format::nxFormatVolume;
 /// Returns true on a success. When false, additional error information is available from
 /// GetLastError()
 ///
+/// Prefer `nx_format_partition`, which checks the partition isn't in use first.
+///
 /// # Arguments
 ///  - `volume_path` The NT-style path to the volume that is about to be formatted
 ///  - `bytes_per_cluster` Specifies the number of bytes per cluster, pass 0 for default
@@ -16,3 +25,133 @@ pub fn nx_format_volume(volume_path: &str, bytes_per_cluster: u32) -> Result<boo
 
     Ok(unsafe { nxFormatVolume(c_path.as_ptr() as *const i8, bytes_per_cluster) })
 }
+
+/// Partitions of the internal hard drive, named after their conventional drive letters.
+///
+/// F: and G: only exist on drives with an extended partition table, which
+/// `nx_read_partition_table` tells apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NxPartition {
+    /// Partition1, XBOX DATA.
+    E,
+    /// Partition2, XBOX SHELL. Holds the dashboard, so formatting it leaves the
+    /// console unable to boot without a replacement.
+    C,
+    /// Partition3, XBOX GAME SWAP 1.
+    X,
+    /// Partition4, XBOX GAME SWAP 2.
+    Y,
+    /// Partition5, XBOX GAME SWAP 3.
+    Z,
+    /// Partition6, only on drives with an extended partition table.
+    F,
+    /// Partition7, only on drives with an extended partition table.
+    G,
+}
+
+impl NxPartition {
+    /// Kernel partition number, as in `\Device\Harddisk0\Partition1`.
+    pub fn number(&self) -> u32 {
+        match self {
+            NxPartition::E => 1,
+            NxPartition::C => 2,
+            NxPartition::X => 3,
+            NxPartition::Y => 4,
+            NxPartition::Z => 5,
+            NxPartition::F => 6,
+            NxPartition::G => 7,
+        }
+    }
+
+    pub fn drive_letter(&self) -> char {
+        // Every variant is named after the letter of its partition number
+        partition::drive_letter(self.number()).unwrap()
+    }
+
+    /// NT device path of this partition.
+    pub fn device_path(&self) -> String {
+        partition::device_path(self.number())
+    }
+}
+
+/// Cluster size to format a partition with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NxClusterSize {
+    /// Lets nxdk pick, which is 16KiB on retail sized partitions.
+    #[default]
+    Default,
+    Kib16,
+    Kib32,
+    Kib64,
+}
+
+impl NxClusterSize {
+    /// Bytes per cluster, as `nxFormatVolume` expects them; 0 for the default.
+    pub fn bytes(&self) -> u32 {
+        match self {
+            NxClusterSize::Default => 0,
+            NxClusterSize::Kib16 => 16 * 1024,
+            NxClusterSize::Kib32 => 32 * 1024,
+            NxClusterSize::Kib64 => 64 * 1024,
+        }
+    }
+}
+
+/// Explicit confirmation that a partition may be erased, required by
+/// `nx_format_partition`. It only works for the partition it was made for.
+#[derive(Debug)]
+pub struct NxFormatConfirmation {
+    partition: NxPartition,
+}
+
+impl NxFormatConfirmation {
+    /// Confirms that everything on `partition` may be lost.
+    pub fn erase_all_data_on(partition: NxPartition) -> Self {
+        Self { partition }
+    }
+}
+
+/// Formats a partition of the internal hard drive with FATX.
+/// WARNING: This erases everything on the partition!
+///
+/// Before formatting, this checks that the partition table has the partition,
+/// that the running XBE isn't on it, and that no drive letter is mounted to it.
+/// Unmount any drive letters first, such as with `nx_try_unmount_drive`.
+pub fn nx_format_partition(
+    partition: NxPartition,
+    cluster_size: NxClusterSize,
+    confirmation: NxFormatConfirmation,
+) -> Result<(), NxFormatError> {
+    if confirmation.partition != partition {
+        return Err(NxFormatError::ConfirmationMismatch);
+    }
+
+    let table = nx_read_partition_table().map_err(NxFormatError::PartitionTable)?;
+    let device_path = table
+        .by_number(partition.number())
+        .ok_or(NxFormatError::NoSuchPartition)?
+        .device_path();
+
+    let xbe_path = nx_get_current_xbe_nt_path_native().map(|c| c as u8);
+    let xbe_path_len = xbe_path.iter().position(|&c| c == 0).unwrap_or(xbe_path.len());
+    let xbe_path = String::from_utf8_lossy(&xbe_path[..xbe_path_len]);
+
+    if nt_path_starts_with(&xbe_path, &device_path) {
+        return Err(NxFormatError::RunningFromPartition);
+    }
+
+    for mount in nx_list_mounts()? {
+        if nt_path_starts_with(mount.target.as_str(), &device_path) {
+            return Err(NxFormatError::PartitionMounted(mount.drive_letter));
+        }
+    }
+
+    // The device path is always short enough
+    let c_path = path_str_to_cstr(&device_path).map_err(WinError::from)?;
+
+    if !unsafe { nxFormatVolume(c_path.as_ptr() as *const i8, cluster_size.bytes()) } {
+        return Err(WinError::from_last_error().into());
+    }
+
+    Ok(())
+}
@@ -2,4 +2,6 @@ pub mod error;
 pub mod format;
 pub mod mount;
 pub mod net;
+pub mod partition;
 pub mod path;
+pub mod xbe;
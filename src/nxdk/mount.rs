@@ -1,44 +1,323 @@
+use crate::nxdk::error::NxMountError;
 use crate::nxdk::path::nx_get_current_xbe_nt_path_native;
+use crate::path::{is_separator, prefix_len, XboxPath, XboxPathBuf};
 use crate::utils::error::PlatformError;
 use crate::utils::path_str_to_cstr;
+use crate::winapi::error::NtStatusError;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ptr::null_mut;
+use nxdk_sys::kernel::{
+    IoCreateSymbolicLink, IoDeleteSymbolicLink, NtClose, NtOpenSymbolicLinkObject, NtQuerySymbolicLinkObject,
+    HANDLE, OBJECT_ATTRIBUTES, OBJECT_STRING, OBJ_CASE_INSENSITIVE,
+};
 use nxdk_sys::nxdk::mount::{nxIsDriveMounted, nxMountDrive, nxUnmountDrive};
 
+const STATUS_OBJECT_NAME_NOT_FOUND: i32 = 0xC0000034u32 as i32;
+const STATUS_OBJECT_NAME_COLLISION: i32 = 0xC0000035u32 as i32;
+
+/// A drive letter along with the NT path it is mounted to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NxMount {
+    pub drive_letter: char,
+    pub target: XboxPathBuf,
+}
+
+/// Keeps a drive mounted for as long as it lives, unmounting it on drop.
+///
+/// Errors while unmounting on drop are ignored; use `unmount` to see them.
+#[derive(Debug)]
+#[must_use = "the drive is unmounted as soon as the guard is dropped"]
+pub struct MountGuard {
+    drive_letter: char,
+}
+
+impl MountGuard {
+    /// Mounts `path` to `drive_letter`, as `nx_try_mount_drive` does.
+    pub fn mount(drive_letter: char, path: &str) -> Result<Self, NxMountError> {
+        nx_try_mount_drive(drive_letter, path)?;
+
+        Ok(Self {
+            drive_letter: drive_letter.to_ascii_uppercase(),
+        })
+    }
+
+    /// Mounts the directory of the running XBE to `drive_letter`, as
+    /// `nx_try_mount_execution_to` does.
+    pub fn mount_execution(drive_letter: char) -> Result<Self, NxMountError> {
+        nx_try_mount_execution_to(drive_letter)?;
+
+        Ok(Self {
+            drive_letter: drive_letter.to_ascii_uppercase(),
+        })
+    }
+
+    pub fn drive_letter(&self) -> char {
+        self.drive_letter
+    }
+
+    /// Unmounts the drive now, reporting any error.
+    pub fn unmount(self) -> Result<(), NxMountError> {
+        let drive_letter = self.drive_letter;
+        core::mem::forget(self);
+
+        nx_try_unmount_drive(drive_letter)
+    }
+
+    /// Keeps the drive mounted past the lifetime of the guard.
+    pub fn leak(self) -> char {
+        let drive_letter = self.drive_letter;
+        core::mem::forget(self);
+
+        drive_letter
+    }
+}
+
+impl Drop for MountGuard {
+    fn drop(&mut self) {
+        let _ = nx_try_unmount_drive(self.drive_letter);
+    }
+}
+
+/// Checks a drive letter, returning it in upper case.
+fn check_drive_letter(drive_letter: char) -> Result<char, NxMountError> {
+    if !drive_letter.is_ascii_alphabetic() {
+        return Err(NxMountError::InvalidDriveLetter(drive_letter));
+    }
+
+    Ok(drive_letter.to_ascii_uppercase())
+}
+
+/// The `\??\X:` symbolic link name of a drive letter, NUL terminated.
+fn drive_link_name(drive_letter: char) -> Result<[u8; 7], NxMountError> {
+    let mut link_name = *b"\\??\\X:\0";
+    link_name[4] = check_drive_letter(drive_letter)? as u8;
+
+    Ok(link_name)
+}
+
+/// Points the drive letter symbolic link at `target`, a NT path without its NUL.
+fn create_drive_link(drive_letter: char, target: &mut [u8]) -> Result<(), NxMountError> {
+    let mut link_name = drive_link_name(drive_letter)?;
+    let drive_letter = drive_letter.to_ascii_uppercase();
+
+    let mut link_string = OBJECT_STRING {
+        Length: (link_name.len() - 1) as u16,
+        MaximumLength: link_name.len() as u16,
+        Buffer: link_name.as_mut_ptr() as *mut libc::c_char,
+    };
+
+    let mut target_string = OBJECT_STRING {
+        Length: target.len() as u16,
+        MaximumLength: target.len() as u16,
+        Buffer: target.as_mut_ptr() as *mut libc::c_char,
+    };
+
+    let status = unsafe { IoCreateSymbolicLink(&mut link_string, &mut target_string) };
+
+    match status {
+        0 => Ok(()),
+        STATUS_OBJECT_NAME_COLLISION => Err(NxMountError::AlreadyMounted(drive_letter)),
+        _ => Err(NxMountError::MountFailed(drive_letter, NtStatusError::new(status))),
+    }
+}
+
 /// Mounts the given path to the given drive letter.
 ///
 /// Returns a bool for result status, or PlatformError::PathTooLong if a given
 /// path is over 259 characters.
+#[deprecated(note = "use nx_try_mount_drive, which reports why mounting failed")]
 pub fn nx_mount_drive(drive_letter: char, path: &str) -> Result<bool, PlatformError> {
     let c_path = path_str_to_cstr(path)?;
 
     Ok(unsafe { nxMountDrive(drive_letter as u8 as i8, c_path.as_ptr() as *const i8) })
 }
 
+/// Mounts the given NT path to the given drive letter, such as
+/// `\Device\Harddisk0\Partition1` to `E`.
+///
+/// Fails with `NxMountError::AlreadyMounted` if the drive letter is in use,
+/// `NxMountError::Path` if the path is over 259 characters, or
+/// `NxMountError::MountFailed` with the kernel's status otherwise.
+pub fn nx_try_mount_drive(drive_letter: char, path: &str) -> Result<(), NxMountError> {
+    let mut c_path = path_str_to_cstr(path)?;
+    let len = c_path.iter().position(|&c| c == 0).unwrap_or(c_path.len());
+
+    create_drive_link(drive_letter, &mut c_path[..len])
+}
+
 /// Helper method to mount the current execution path to the given
 /// drive letter.
 ///
 /// This is the equivalent of mounting `nxGetCurrentXbeNtPath` result
 /// to the given drive.
+#[deprecated(note = "use nx_try_mount_execution_to, which reports why mounting failed")]
 pub fn nx_mount_execution_to(drive_letter: char) -> bool {
-    nx_mount_drive_from(drive_letter, &mut nx_get_current_xbe_nt_path_native())
+    nx_try_mount_execution_to(drive_letter).is_ok()
 }
 
-fn nx_mount_drive_from(drive_letter: char, native_path: &mut [libc::c_char; 260]) -> bool {
-    if let Some(last_backslash) = native_path
+/// Mounts the directory of the running XBE to the given drive letter.
+///
+/// This is the equivalent of mounting the directory of the
+/// `nxGetCurrentXbeNtPath` result to the given drive. Fails with
+/// `NxMountError::NoParentDirectory` if that path has no directory.
+pub fn nx_try_mount_execution_to(drive_letter: char) -> Result<(), NxMountError> {
+    let mut native_path = nx_get_current_xbe_nt_path_native().map(|c| c as u8);
+    let len = native_path.iter().position(|&c| c == 0).unwrap_or(native_path.len());
+
+    let directory_len = native_path[..len]
         .iter()
-        .rposition(|&c| c == b'\\' as libc::c_char)
-    {
-        if last_backslash < 260 {
-            native_path[last_backslash] = 0;
-        }
-    }
+        .rposition(|&c| c == b'\\')
+        .ok_or(NxMountError::NoParentDirectory)?;
 
-    unsafe { nxMountDrive(drive_letter as u8 as i8, native_path.as_ptr() as *const i8) }
+    create_drive_link(drive_letter, &mut native_path[..directory_len])
 }
 
+#[deprecated(note = "use nx_try_unmount_drive, which reports why unmounting failed")]
 pub fn nx_unmount_drive(drive_letter: char) -> bool {
     unsafe { nxUnmountDrive(drive_letter as u8 as i8) }
 }
 
+/// Unmounts the given drive letter.
+///
+/// Fails with `NxMountError::NotMounted` if nothing is mounted there, or
+/// `NxMountError::UnmountFailed` with the kernel's status otherwise.
+pub fn nx_try_unmount_drive(drive_letter: char) -> Result<(), NxMountError> {
+    let mut link_name = drive_link_name(drive_letter)?;
+    let drive_letter = drive_letter.to_ascii_uppercase();
+
+    let mut link_string = OBJECT_STRING {
+        Length: (link_name.len() - 1) as u16,
+        MaximumLength: link_name.len() as u16,
+        Buffer: link_name.as_mut_ptr() as *mut libc::c_char,
+    };
+
+    let status = unsafe { IoDeleteSymbolicLink(&mut link_string) };
+
+    match status {
+        0 => Ok(()),
+        STATUS_OBJECT_NAME_NOT_FOUND => Err(NxMountError::NotMounted(drive_letter)),
+        _ => Err(NxMountError::UnmountFailed(drive_letter, NtStatusError::new(status))),
+    }
+}
+
 pub fn nx_is_drive_mounted(drive_letter: char) -> bool {
     unsafe { nxIsDriveMounted(drive_letter as u8 as i8) }
 }
+
+/// Lists every mounted drive letter along with its target, from A to Z.
+pub fn nx_list_mounts() -> Result<Vec<NxMount>, NxMountError> {
+    let mut mounts = Vec::new();
+
+    for drive_letter in 'A'..='Z' {
+        match nx_get_drive_target(drive_letter) {
+            Ok(target) => mounts.push(NxMount { drive_letter, target }),
+            Err(NxMountError::NotMounted(_)) => continue,
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(mounts)
+}
+
+/// Returns the NT path a drive letter is mounted to, such as
+/// `\Device\Harddisk0\Partition1` for `E`.
+///
+/// This reads the `\??\X:` symbolic link `nx_mount_drive` creates.
+pub fn nx_get_drive_target(drive_letter: char) -> Result<XboxPathBuf, NxMountError> {
+    let mut link_name = drive_link_name(drive_letter)?;
+    let mut link_string = OBJECT_STRING {
+        Length: (link_name.len() - 1) as u16,
+        MaximumLength: link_name.len() as u16,
+        Buffer: link_name.as_mut_ptr() as *mut libc::c_char,
+    };
+
+    let mut attributes = OBJECT_ATTRIBUTES {
+        RootDirectory: null_mut(),
+        ObjectName: &mut link_string,
+        Attributes: OBJ_CASE_INSENSITIVE as _,
+    };
+
+    let mut handle: HANDLE = null_mut();
+    let status = unsafe { NtOpenSymbolicLinkObject(&mut handle, &mut attributes) };
+
+    if status == STATUS_OBJECT_NAME_NOT_FOUND {
+        return Err(NxMountError::NotMounted(drive_letter));
+    }
+
+    if status != 0 {
+        return Err(NtStatusError::new(status).into());
+    }
+
+    let mut target_buffer = [0u8; 260];
+    let mut target = OBJECT_STRING {
+        Length: 0,
+        MaximumLength: target_buffer.len() as u16,
+        Buffer: target_buffer.as_mut_ptr() as *mut libc::c_char,
+    };
+
+    let status = unsafe {
+        let status = NtQuerySymbolicLinkObject(handle, &mut target, null_mut());
+        NtClose(handle);
+        status
+    };
+
+    if status != 0 {
+        return Err(NtStatusError::new(status).into());
+    }
+
+    let target = String::from_utf8_lossy(&target_buffer[..target.Length as usize]);
+
+    Ok(XboxPathBuf::from(target.trim_end_matches(is_separator)))
+}
+
+/// Whether `path` is `prefix` or lies within it, ignoring case. Whole
+/// components must match, so Partition1 doesn't match Partition10.
+pub(crate) fn nt_path_starts_with(path: &str, prefix: &str) -> bool {
+    path.len() >= prefix.len()
+        && path.is_char_boundary(prefix.len())
+        && path[..prefix.len()].eq_ignore_ascii_case(prefix)
+        && path[prefix.len()..].chars().next().is_none_or(is_separator)
+}
+
+/// Translates a DOS style path, such as `E:\apps\default.xbe`, to its NT path,
+/// such as `\Device\Harddisk0\Partition1\apps\default.xbe`.
+///
+/// `\??\E:\...` paths are accepted as well.
+pub fn nx_dos_to_nt_path<P: AsRef<XboxPath>>(path: P) -> Result<XboxPathBuf, NxMountError> {
+    let path = path.as_ref();
+    let drive_letter = path.drive_letter().ok_or(NxMountError::NoDriveLetter)?;
+    let rest = &path.as_str()[prefix_len(path.as_str())..];
+
+    let mut nt_path = nx_get_drive_target(drive_letter)?;
+    nt_path.push(XboxPath::new(rest.trim_start_matches(is_separator)));
+
+    Ok(nt_path)
+}
+
+/// Translates a NT path, such as `\Device\CdRom0\default.xbe`, back to a DOS
+/// style path using the currently mounted drives, such as `D:\default.xbe`.
+///
+/// If several drives match, the one mounted deepest into the NT path is used.
+/// Returns `NxMountError::NoMatchingDrive` if no mounted drive covers the path.
+pub fn nx_nt_to_dos_path<P: AsRef<XboxPath>>(path: P) -> Result<XboxPathBuf, NxMountError> {
+    let path = path.as_ref().as_str();
+    let mut best: Option<(char, usize)> = None;
+
+    let mounts = nx_list_mounts()?;
+
+    for NxMount { drive_letter, target } in &mounts {
+        let target = target.as_str();
+
+        if nt_path_starts_with(path, target) && best.is_none_or(|(_, len)| target.len() > len) {
+            best = Some((*drive_letter, target.len()));
+        }
+    }
+
+    let (drive_letter, target_len) = best.ok_or(NxMountError::NoMatchingDrive)?;
+
+    let mut dos_path = XboxPathBuf::from(alloc::format!("{}:\\", drive_letter));
+    dos_path.push(XboxPath::new(path[target_len..].trim_start_matches(is_separator)));
+
+    Ok(dos_path)
+}
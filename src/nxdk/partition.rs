@@ -0,0 +1,111 @@
+use crate::nxdk::error::NxMountError;
+use crate::nxdk::mount::{nx_is_drive_mounted, nx_try_mount_drive};
+use crate::winapi::error::NtStatusError;
+use alloc::vec::Vec;
+use core::ptr::null_mut;
+use nxdk_formats::partition::{PartitionTable, PARTINFO_SIZE};
+use nxdk_sys::kernel::{
+    NtClose, NtOpenFile, NtReadFile, FILE_SHARE_READ, FILE_SHARE_WRITE, FILE_SYNCHRONOUS_IO_ALERT, GENERIC_READ,
+    HANDLE, IO_STATUS_BLOCK, LARGE_INTEGER, OBJECT_ATTRIBUTES, OBJECT_STRING, OBJ_CASE_INSENSITIVE, SYNCHRONIZE,
+};
+
+/// Sector buffer, aligned for the IDE driver.
+#[repr(C, align(16))]
+struct Sector([u8; PARTINFO_SIZE]);
+
+/// Reads the first sector of the internal hard drive.
+fn read_first_sector() -> Result<Sector, NtStatusError> {
+    // Partition0 is the raw device, covering the whole drive
+    let mut device_name = *b"\\Device\\Harddisk0\\Partition0\0";
+
+    let mut device_string = OBJECT_STRING {
+        Length: (device_name.len() - 1) as u16,
+        MaximumLength: device_name.len() as u16,
+        Buffer: device_name.as_mut_ptr() as *mut libc::c_char,
+    };
+
+    let mut attributes = OBJECT_ATTRIBUTES {
+        RootDirectory: null_mut(),
+        ObjectName: &mut device_string,
+        Attributes: OBJ_CASE_INSENSITIVE as _,
+    };
+
+    let mut handle: HANDLE = null_mut();
+    let mut io_status: IO_STATUS_BLOCK = unsafe { core::mem::zeroed() };
+
+    let status = unsafe {
+        NtOpenFile(
+            &mut handle,
+            GENERIC_READ | SYNCHRONIZE,
+            &mut attributes,
+            &mut io_status,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            FILE_SYNCHRONOUS_IO_ALERT,
+        )
+    };
+
+    if status != 0 {
+        return Err(NtStatusError::new(status));
+    }
+
+    let mut sector = Sector([0u8; PARTINFO_SIZE]);
+    let mut offset = LARGE_INTEGER { QuadPart: 0 };
+
+    let status = unsafe {
+        let status = NtReadFile(
+            handle,
+            null_mut(),
+            None,
+            null_mut(),
+            &mut io_status,
+            sector.0.as_mut_ptr() as *mut _,
+            PARTINFO_SIZE as u32,
+            &mut offset,
+        );
+
+        NtClose(handle);
+        status
+    };
+
+    if status != 0 {
+        return Err(NtStatusError::new(status));
+    }
+
+    Ok(sector)
+}
+
+/// Reads the partition table of the internal hard drive, detecting whether it
+/// uses the retail layout or an extended "****PARTINFO****" table.
+pub fn nx_read_partition_table() -> Result<PartitionTable, NxMountError> {
+    let sector = read_first_sector()?;
+
+    Ok(PartitionTable::parse(&sector.0))
+}
+
+/// Mounts the extended partitions of the internal hard drive, such as F: and G:,
+/// to their conventional drive letters. Drive letters already in use are left alone.
+///
+/// Returns the drive letters that were mounted. On retail layouts, nothing is.
+///
+/// Note that the kernel only exposes partitions past Partition5 if it has been
+/// patched to do so, which is usually the case alongside a PARTINFO table.
+pub fn nx_mount_extended_partitions() -> Result<Vec<char>, NxMountError> {
+    let table = nx_read_partition_table()?;
+    let mut mounted = Vec::new();
+
+    for partition in table.extended_partitions() {
+        let Some(drive_letter) = partition.drive_letter else {
+            continue;
+        };
+
+        if nx_is_drive_mounted(drive_letter) {
+            continue;
+        }
+
+        nx_try_mount_drive(drive_letter, &partition.device_path())?;
+
+        mounted.push(drive_letter);
+    }
+
+    Ok(mounted)
+}
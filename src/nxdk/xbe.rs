@@ -0,0 +1,38 @@
+use core::slice;
+use nxdk_formats::xbe::header::IMAGE_HEADER_SIZE;
+use nxdk_formats::xbe::section::TLS_DIRECTORY_SIZE;
+use nxdk_formats::xbe::{Certificate, ImageHeader, TlsDirectory, Xbe, XbeError, XBE_BASE_ADDRESS};
+
+/// Headers of the running XBE, as loaded at `XBE_BASE_ADDRESS`.
+fn loaded_headers() -> Result<&'static [u8], XbeError> {
+    // The kernel keeps the headers mapped for as long as the title runs
+    let image_header = unsafe { slice::from_raw_parts(XBE_BASE_ADDRESS as *const u8, IMAGE_HEADER_SIZE) };
+    let size_of_headers = ImageHeader::parse(image_header)?.size_of_headers;
+
+    Ok(unsafe { slice::from_raw_parts(XBE_BASE_ADDRESS as *const u8, size_of_headers as usize) })
+}
+
+/// Parses the headers of the running XBE, straight from memory.
+///
+/// The result is the same as parsing the file on disk with `Xbe::parse`,
+/// including the TLS directory, which is read from its loaded address.
+pub fn current_xbe() -> Result<Xbe, XbeError> {
+    let mut xbe = Xbe::parse_headers(loaded_headers()?)?;
+
+    if xbe.tls.is_none() && xbe.header.tls_address != 0 {
+        let tls = unsafe { slice::from_raw_parts(xbe.header.tls_address as *const u8, TLS_DIRECTORY_SIZE) };
+        xbe.tls = TlsDirectory::parse(tls);
+    }
+
+    Ok(xbe)
+}
+
+/// Certificate of the running XBE.
+pub fn current_certificate() -> Result<Certificate, XbeError> {
+    Ok(current_xbe()?.certificate)
+}
+
+/// Title ID of the running XBE.
+pub fn current_title_id() -> Result<u32, XbeError> {
+    Ok(current_certificate()?.title_id)
+}
@@ -0,0 +1,607 @@
+use crate::utils::error::PlatformError;
+use crate::winapi::WindowsPath;
+use alloc::borrow::{Cow, ToOwned};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt::{Display, Formatter};
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// Preferred path separator. `/` is accepted as well, and converted on normalization.
+pub const SEPARATOR: char = '\\';
+
+/// Longest path the winapi functions accept, excluding the NUL terminator.
+pub const MAX_PATH_LENGTH: usize = 259;
+
+/// Longest file or directory name FATX can store.
+pub const MAX_NAME_LENGTH: usize = 42;
+
+/// Characters FATX doesn't allow in file or directory names.
+pub const FORBIDDEN_CHARACTERS: &[char] = &['"', '*', '/', ':', '<', '>', '?', '\\', '|'];
+
+pub fn is_separator(c: char) -> bool {
+    c == '\\' || c == '/'
+}
+
+/// Length of the prefix of a path; a drive such as `E:`, or an object manager
+/// drive such as `\??\E:`.
+pub(crate) fn prefix_len(path: &str) -> usize {
+    let bytes = path.as_bytes();
+
+    if bytes.len() >= 6
+        && is_separator(bytes[0] as char)
+        && &bytes[1..3] == b"??"
+        && is_separator(bytes[3] as char)
+        && bytes[4].is_ascii_alphabetic()
+        && bytes[5] == b':'
+    {
+        return 6;
+    }
+
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        return 2;
+    }
+
+    0
+}
+
+/// A single component of a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component<'a> {
+    /// Drive prefix, such as `E:` or `\??\E:`.
+    Prefix(&'a str),
+    /// The separator right after the prefix, or at the start of the path.
+    RootDir,
+    /// `.`
+    CurDir,
+    /// `..`
+    ParentDir,
+    /// A file or directory name.
+    Normal(&'a str),
+}
+
+impl<'a> Component<'a> {
+    pub fn as_str(&self) -> &'a str {
+        match self {
+            Component::Prefix(prefix) => prefix,
+            Component::RootDir => "\\",
+            Component::CurDir => ".",
+            Component::ParentDir => "..",
+            Component::Normal(name) => name,
+        }
+    }
+
+    /// The bytes paths are compared by, before ignoring case. Prefixes only
+    /// keep their drive, so `\??\E:` matches `E:`.
+    fn key(&self) -> &'a str {
+        match self {
+            Component::Prefix(prefix) => &prefix[prefix.len() - 2..],
+            component => component.as_str(),
+        }
+    }
+
+    fn key_bytes(&self) -> impl Iterator<Item = u8> + 'a {
+        self.key().bytes().map(|byte| byte.to_ascii_lowercase())
+    }
+
+    /// Compares components the way FATX compares names, ignoring ASCII case.
+    fn cmp_ignore_case(&self, other: &Component<'_>) -> Ordering {
+        self.key_bytes().cmp(other.key_bytes())
+    }
+}
+
+/// Iterator over the components of an `XboxPath`. Repeated separators are skipped.
+#[derive(Debug, Clone)]
+pub struct Components<'a> {
+    prefix: Option<&'a str>,
+    root: bool,
+    rest: core::str::Split<'a, fn(char) -> bool>,
+}
+
+impl<'a> Iterator for Components<'a> {
+    type Item = Component<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(prefix) = self.prefix.take() {
+            return Some(Component::Prefix(prefix));
+        }
+
+        if self.root {
+            self.root = false;
+            return Some(Component::RootDir);
+        }
+
+        loop {
+            return match self.rest.next()? {
+                "" => continue,
+                "." => Some(Component::CurDir),
+                ".." => Some(Component::ParentDir),
+                name => Some(Component::Normal(name)),
+            };
+        }
+    }
+}
+
+/// A borrowed Xbox path, the `str` to `XboxPathBuf`'s `String`.
+///
+/// Both `\` and `/` are accepted as separators. Paths are only converted to a
+/// NUL terminated `WindowsPath` right before being handed to native functions,
+/// through `to_c_path`.
+///
+/// Equality, ordering and hashing go component by component, ignoring ASCII
+/// case like FATX does. `E:\a`, `e:/A\` and `\??\E:\a` are all equal, but
+/// `.` and `..` are not resolved; `normalize` first to compare those. Use
+/// `as_str` to compare the raw strings instead.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct XboxPath {
+    inner: str,
+}
+
+impl XboxPath {
+    pub fn new<S: AsRef<str> + ?Sized>(path: &S) -> &XboxPath {
+        // Safety: XboxPath is a transparent wrapper over str
+        unsafe { &*(path.as_ref() as *const str as *const XboxPath) }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.inner
+    }
+
+    pub fn to_path_buf(&self) -> XboxPathBuf {
+        XboxPathBuf::from(self.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn components(&self) -> Components<'_> {
+        let prefix = prefix_len(&self.inner);
+        let rest = &self.inner[prefix..];
+
+        Components {
+            prefix: if prefix > 0 { Some(&self.inner[..prefix]) } else { None },
+            root: rest.starts_with(is_separator),
+            rest: rest.split(is_separator as fn(char) -> bool),
+        }
+    }
+
+    /// Drive letter of this path, for both `E:\...` and `\??\E:\...` forms.
+    pub fn drive_letter(&self) -> Option<char> {
+        match prefix_len(&self.inner) {
+            0 => None,
+            len => self.inner[..len].chars().nth(len - 2),
+        }
+    }
+
+    /// True if the path starts at the root of a drive or object namespace,
+    /// such as `E:\foo` or `\Device\CdRom0`.
+    pub fn is_absolute(&self) -> bool {
+        self.inner[prefix_len(&self.inner)..].starts_with(is_separator)
+    }
+
+    /// True if `base` is a leading part of this path, component by component.
+    /// Names are compared ignoring ASCII case, like FATX does. Neither path is
+    /// normalized; `..` components only match each other.
+    pub fn starts_with<P: AsRef<XboxPath>>(&self, base: P) -> bool {
+        let mut components = self.components();
+
+        base.as_ref()
+            .components()
+            .all(|component| components.next().is_some_and(|own| own.cmp_ignore_case(&component).is_eq()))
+    }
+
+    /// The path without its final component, or None if it ends at a root or prefix.
+    pub fn parent(&self) -> Option<&XboxPath> {
+        let prefix = prefix_len(&self.inner);
+        let rest = self.inner[prefix..].trim_end_matches(is_separator);
+
+        if rest.is_empty() {
+            return None;
+        }
+
+        let parent_len = match rest.rfind(is_separator) {
+            Some(index) => {
+                let parent = rest[..index].trim_end_matches(is_separator);
+
+                // Keep the root separator around
+                if parent.is_empty() { 1 } else { parent.len() }
+            }
+            None => 0,
+        };
+
+        Some(XboxPath::new(&self.inner[..prefix + parent_len]))
+    }
+
+    /// Final component of the path, if it's a file or directory name.
+    pub fn file_name(&self) -> Option<&str> {
+        match self.components().last()? {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// File name without its extension.
+    pub fn file_stem(&self) -> Option<&str> {
+        let name = self.file_name()?;
+
+        match name.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => Some(stem),
+            _ => Some(name),
+        }
+    }
+
+    /// Extension of the file name, without the leading dot.
+    pub fn extension(&self) -> Option<&str> {
+        match self.file_name()?.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => Some(extension),
+            _ => None,
+        }
+    }
+
+    /// Creates an owned path with `path` adjoined to `self`. If `path` is
+    /// absolute, it replaces `self`.
+    pub fn join<P: AsRef<XboxPath>>(&self, path: P) -> XboxPathBuf {
+        let mut buf = self.to_path_buf();
+        buf.push(path);
+        buf
+    }
+
+    /// Creates an owned path with the extension replaced.
+    pub fn with_extension(&self, extension: &str) -> XboxPathBuf {
+        let mut buf = self.to_path_buf();
+        buf.set_extension(extension);
+        buf
+    }
+
+    /// Converts every separator to `\`, collapses repeated separators, and
+    /// resolves `.` and `..` components lexically.
+    pub fn normalize(&self) -> XboxPathBuf {
+        let mut prefix = None;
+        let mut root = false;
+        let mut names: Vec<&str> = Vec::new();
+
+        for component in self.components() {
+            match component {
+                Component::Prefix(p) => prefix = Some(p),
+                Component::RootDir => root = true,
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    match names.last() {
+                        Some(&last) if last != ".." => {
+                            names.pop();
+                        }
+                        // Can't go above the root
+                        _ if root => {}
+                        _ => names.push(".."),
+                    }
+                }
+                Component::Normal(name) => names.push(name),
+            }
+        }
+
+        let mut normalized = String::with_capacity(self.inner.len());
+
+        if let Some(prefix) = prefix {
+            normalized.extend(prefix.chars().map(|c| if c == '/' { SEPARATOR } else { c }));
+        }
+
+        if root {
+            normalized.push(SEPARATOR);
+        }
+
+        for (index, name) in names.iter().enumerate() {
+            if index > 0 {
+                normalized.push(SEPARATOR);
+            }
+
+            normalized.push_str(name);
+        }
+
+        XboxPathBuf { inner: normalized }
+    }
+
+    /// Checks the path against FATX rules; names of at most 42 characters,
+    /// printable ASCII only, none of `" * / : < > ? \ |`, and a total length
+    /// that fits in a `WindowsPath`.
+    pub fn validate(&self) -> Result<(), PlatformError> {
+        self.check_length()?;
+
+        for component in self.components() {
+            if let Component::Normal(name) = component {
+                validate_name(name)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_length(&self) -> Result<(), PlatformError> {
+        if self.inner.len() > MAX_PATH_LENGTH {
+            return Err(PlatformError::PathTooLong);
+        }
+
+        if self.inner.contains('\0') {
+            return Err(PlatformError::InvalidCharacter('\0'));
+        }
+
+        Ok(())
+    }
+
+    /// Converts this path into a NUL terminated `WindowsPath`, for native calls.
+    /// Separators are converted to `\`.
+    ///
+    /// Only the length is checked here; use `validate` to check FATX rules.
+    pub fn to_c_path(&self) -> Result<WindowsPath, PlatformError> {
+        self.check_length()?;
+
+        let mut path_buffer: WindowsPath = [0; 260];
+
+        for (target, source) in path_buffer.iter_mut().zip(self.inner.bytes()) {
+            *target = if source == b'/' { b'\\' } else { source };
+        }
+
+        Ok(path_buffer)
+    }
+}
+
+/// Checks a single file or directory name against FATX rules.
+pub fn validate_name(name: &str) -> Result<(), PlatformError> {
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(PlatformError::NameTooLong);
+    }
+
+    if let Some(invalid) = name
+        .chars()
+        .find(|c| !c.is_ascii() || c.is_ascii_control() || FORBIDDEN_CHARACTERS.contains(c))
+    {
+        return Err(PlatformError::InvalidCharacter(invalid));
+    }
+
+    Ok(())
+}
+
+impl AsRef<XboxPath> for XboxPath {
+    fn as_ref(&self) -> &XboxPath {
+        self
+    }
+}
+
+impl AsRef<XboxPath> for str {
+    fn as_ref(&self) -> &XboxPath {
+        XboxPath::new(self)
+    }
+}
+
+impl AsRef<XboxPath> for String {
+    fn as_ref(&self) -> &XboxPath {
+        XboxPath::new(self)
+    }
+}
+
+impl AsRef<str> for XboxPath {
+    fn as_ref(&self) -> &str {
+        &self.inner
+    }
+}
+
+impl PartialEq for XboxPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for XboxPath {}
+
+impl PartialOrd for XboxPath {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for XboxPath {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let mut others = other.components();
+
+        for component in self.components() {
+            let Some(other) = others.next() else {
+                return Ordering::Greater;
+            };
+
+            match component.cmp_ignore_case(&other) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            }
+        }
+
+        match others.next() {
+            Some(_) => Ordering::Less,
+            None => Ordering::Equal,
+        }
+    }
+}
+
+impl Hash for XboxPath {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for component in self.components() {
+            component.key_bytes().for_each(|byte| state.write_u8(byte));
+            // Not valid in a name, so "ab" and "a\b" hash differently
+            state.write_u8(0xFF);
+        }
+    }
+}
+
+impl ToOwned for XboxPath {
+    type Owned = XboxPathBuf;
+
+    fn to_owned(&self) -> Self::Owned {
+        self.to_path_buf()
+    }
+}
+
+impl Display for XboxPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", &self.inner)
+    }
+}
+
+/// An owned, mutable Xbox path. Separators are normalized to `\` on creation.
+///
+/// Compares like `XboxPath`, ignoring ASCII case.
+#[derive(Debug, Clone, Default)]
+pub struct XboxPathBuf {
+    inner: String,
+}
+
+impl XboxPathBuf {
+    pub fn new() -> Self {
+        Self { inner: String::new() }
+    }
+
+    pub fn as_xbox_path(&self) -> &XboxPath {
+        XboxPath::new(&self.inner)
+    }
+
+    pub fn into_string(self) -> String {
+        self.inner
+    }
+
+    /// Extends `self` with `path`. If `path` is absolute, it replaces `self`.
+    pub fn push<P: AsRef<XboxPath>>(&mut self, path: P) {
+        let path = path.as_ref();
+
+        if path.is_absolute() || prefix_len(path.as_str()) > 0 {
+            *self = path.to_path_buf();
+            return;
+        }
+
+        if !self.inner.is_empty() && !self.inner.ends_with(is_separator) {
+            self.inner.push(SEPARATOR);
+        }
+
+        self.inner.push_str(&normalize_separators(path.as_str()));
+    }
+
+    /// Truncates `self` to its parent. Returns false if there's no parent.
+    pub fn pop(&mut self) -> bool {
+        match self.parent().map(|parent| parent.as_str().len()) {
+            Some(len) => {
+                self.inner.truncate(len);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the final component. If there's none, the name is pushed instead.
+    pub fn set_file_name(&mut self, file_name: &str) {
+        if self.file_name().is_some() {
+            self.pop();
+        }
+
+        self.push(file_name);
+    }
+
+    /// Replaces the extension of the final component, or removes it if `extension`
+    /// is empty. Returns false if there's no file name.
+    pub fn set_extension(&mut self, extension: &str) -> bool {
+        let stem = match self.file_stem() {
+            Some(stem) => String::from(stem),
+            None => return false,
+        };
+
+        let name = if extension.is_empty() {
+            stem
+        } else {
+            alloc::format!("{}.{}", stem, extension)
+        };
+
+        self.set_file_name(&name);
+        true
+    }
+}
+
+fn normalize_separators(path: &str) -> Cow<'_, str> {
+    if path.contains('/') {
+        Cow::Owned(path.replace('/', "\\"))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+impl Deref for XboxPathBuf {
+    type Target = XboxPath;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_xbox_path()
+    }
+}
+
+impl PartialEq for XboxPathBuf {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_xbox_path() == other.as_xbox_path()
+    }
+}
+
+impl Eq for XboxPathBuf {}
+
+impl PartialOrd for XboxPathBuf {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for XboxPathBuf {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_xbox_path().cmp(other.as_xbox_path())
+    }
+}
+
+impl Hash for XboxPathBuf {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_xbox_path().hash(state)
+    }
+}
+
+impl Borrow<XboxPath> for XboxPathBuf {
+    fn borrow(&self) -> &XboxPath {
+        self.as_xbox_path()
+    }
+}
+
+impl AsRef<XboxPath> for XboxPathBuf {
+    fn as_ref(&self) -> &XboxPath {
+        self.as_xbox_path()
+    }
+}
+
+impl From<&str> for XboxPathBuf {
+    fn from(path: &str) -> Self {
+        Self { inner: normalize_separators(path).into_owned() }
+    }
+}
+
+impl From<String> for XboxPathBuf {
+    fn from(path: String) -> Self {
+        if path.contains('/') {
+            return Self::from(path.as_str());
+        }
+
+        Self { inner: path }
+    }
+}
+
+impl From<&XboxPath> for XboxPathBuf {
+    fn from(path: &XboxPath) -> Self {
+        path.to_path_buf()
+    }
+}
+
+impl Display for XboxPathBuf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", &self.inner)
+    }
+}
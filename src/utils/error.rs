@@ -4,6 +4,10 @@ use core::fmt::{Display, Formatter};
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum PlatformError {
     PathTooLong,
+    /// A file or directory name is longer than FATX allows (42 characters).
+    NameTooLong,
+    /// A path contains a character FATX doesn't allow.
+    InvalidCharacter(char),
     ReadError(&'static str),
     WriteError(&'static str),
 }
@@ -12,6 +16,8 @@ impl Display for PlatformError {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             PlatformError::PathTooLong => write!(f, "This path is longer than 259 characters."),
+            PlatformError::NameTooLong => write!(f, "This name is longer than 42 characters."),
+            PlatformError::InvalidCharacter(c) => write!(f, "Invalid path character: {:?}", c),
             _ => {
                 write!(f, "{:?}", self)
             }
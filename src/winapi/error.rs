@@ -1,6 +1,7 @@
 use core::error::Error;
 use core::fmt::{Display, Formatter};
-use nxdk_sys::winapi::GetLastError;
+use crate::utils::error::PlatformError;
+use nxdk_sys::winapi::{GetLastError, RtlNtStatusToDosError, ERROR_FILENAME_EXCED_RANGE, ERROR_INVALID_NAME};
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct WinError(u32);
@@ -14,6 +15,12 @@ impl WinError {
         Self::new(unsafe { GetLastError() })
     }
 
+    /// Translates a NTSTATUS code into its Win error equivalent,
+    /// using `RtlNtStatusToDosError`.
+    pub fn from_nt_status(status: NtStatusError) -> Self {
+        Self::new(unsafe { RtlNtStatusToDosError(status.into_inner()) })
+    }
+
     pub const fn into_inner(self) -> u32 {
         self.0
     }
@@ -39,6 +46,17 @@ impl From<u32> for WinError {
     }
 }
 
+/// Maps path errors onto their closest Win error codes, for native calls
+/// that take an `XboxPath`.
+impl From<PlatformError> for WinError {
+    fn from(o: PlatformError) -> Self {
+        match o {
+            PlatformError::PathTooLong | PlatformError::NameTooLong => Self::new(ERROR_FILENAME_EXCED_RANGE),
+            _ => Self::new(ERROR_INVALID_NAME),
+        }
+    }
+}
+
 impl From<WinError> for u32 {
     fn from(o: WinError) -> Self {
         o.into_inner()
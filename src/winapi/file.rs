@@ -1,10 +1,15 @@
+use crate::kernel::time::windows_to_unix_timestamp;
+use crate::path::XboxPath;
 use crate::winapi::error::{NtStatusError, WinError, WinMixedError};
-use crate::winapi::handle::{close_handle_native, GenericWinHandle};
+use crate::winapi::handle::GenericWinHandle;
+use crate::winapi::io::{FlushRequest, IoKind, IoRequest};
 use crate::winapi::WindowsPath;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 use bitflags::bitflags;
-use core::ffi::c_void;
-use embedded_io::SeekFrom;
-use futures_lite::future::yield_now;
+use core::ffi::{c_void, CStr};
+use embedded_io::{ReadExactError, SeekFrom};
 use log::error;
 use nxdk_sys::winapi::*;
 
@@ -58,6 +63,7 @@ bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct FileFlagsAndAttributes: u32 {
         const AttributeArchive = FILE_ATTRIBUTE_ARCHIVE;
+        const AttributeDirectory = FILE_ATTRIBUTE_DIRECTORY;
         const AttributeHidden = FILE_ATTRIBUTE_HIDDEN;
         const AttributeNormal = FILE_ATTRIBUTE_NORMAL;
         const AttributeReadOnly = FILE_ATTRIBUTE_READONLY;
@@ -80,6 +86,53 @@ impl Default for FileFlagsAndAttributes {
     }
 }
 
+/// Timestamps and attributes of a file, as in `FILE_BASIC_INFORMATION`.
+///
+/// Timestamps are given in Windows time; 100ns intervals since January 1, 1601 (UTC).
+/// See `kernel::time` for conversions. When setting, a timestamp of 0 leaves
+/// that timestamp unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileBasicInformation {
+    pub creation_time: u64,
+    pub last_access_time: u64,
+    pub last_write_time: u64,
+    pub change_time: u64,
+    pub attributes: FileFlagsAndAttributes,
+}
+
+impl FileBasicInformation {
+    /// Last write time as a Unix timestamp, in seconds.
+    pub fn modified_unix(&self) -> u64 {
+        windows_to_unix_timestamp(&self.last_write_time)
+    }
+
+    /// Creation time as a Unix timestamp, in seconds.
+    pub fn created_unix(&self) -> u64 {
+        windows_to_unix_timestamp(&self.creation_time)
+    }
+
+    /// Last access time as a Unix timestamp, in seconds.
+    pub fn accessed_unix(&self) -> u64 {
+        windows_to_unix_timestamp(&self.last_access_time)
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.attributes.contains(FileFlagsAndAttributes::AttributeReadOnly)
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.attributes.contains(FileFlagsAndAttributes::AttributeHidden)
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.attributes.set(FileFlagsAndAttributes::AttributeReadOnly, read_only);
+    }
+
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.attributes.set(FileFlagsAndAttributes::AttributeHidden, hidden);
+    }
+}
+
 pub struct FileStandardInformation {
     pub allocation_size: u64,
     /// Also known as filesize
@@ -89,46 +142,231 @@ pub struct FileStandardInformation {
     pub directory: bool
 }
 
+/// Converts a native `FILETIME` into a single 64-bit value, in 100ns
+/// intervals since January 1, 1601 (UTC).
+pub fn filetime_to_u64(file_time: &FILETIME) -> u64 {
+    ((file_time.dwHighDateTime as u64) << 32) | file_time.dwLowDateTime as u64
+}
+
+/// A single entry yielded by `ReadDir`.
+///
+/// Timestamps are given in Windows time; 100ns intervals since January 1, 1601 (UTC).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    pub size: u64,
+    pub attributes: FileFlagsAndAttributes,
+    pub creation_time: u64,
+    pub last_access_time: u64,
+    pub last_write_time: u64,
+}
+
+impl DirEntry {
+    fn from_find_data(find_data: &WIN32_FIND_DATAA) -> Self {
+        let name = unsafe { CStr::from_ptr(find_data.cFileName.as_ptr()) };
+
+        Self {
+            name: String::from_utf8_lossy(name.to_bytes()).into_owned(),
+            size: ((find_data.nFileSizeHigh as u64) << 32) | find_data.nFileSizeLow as u64,
+            attributes: FileFlagsAndAttributes::from_bits_retain(find_data.dwFileAttributes),
+            creation_time: filetime_to_u64(&find_data.ftCreationTime),
+            last_access_time: filetime_to_u64(&find_data.ftLastAccessTime),
+            last_write_time: filetime_to_u64(&find_data.ftLastWriteTime),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.attributes.contains(FileFlagsAndAttributes::AttributeDirectory)
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.is_dir()
+    }
+}
+
+/// Directory enumeration iterator, built on `FindFirstFileA`/`FindNextFileA`.
+///
+/// The pattern is a path that may end in a wildcard, such as `E:\UDATA\*`
+/// or `D:\media\*.xmv`. The `.` and `..` entries are skipped. The search
+/// handle is closed with `FindClose` once dropped.
+#[derive(Debug)]
+pub struct ReadDir {
+    handle: Option<HANDLE>,
+    pending: Option<DirEntry>,
+}
+
+unsafe impl Send for ReadDir {}
+
+impl ReadDir {
+    /// Starts enumerating the entries matching `pattern`, such as `E:\saves\*`.
+    pub fn open_path(pattern: &XboxPath) -> Result<Self, WinError> {
+        Self::find_first(&pattern.to_c_path()?)
+    }
+
+    #[deprecated(note = "use ReadDir::open_path, which takes an XboxPath")]
+    pub fn new(pattern: &WindowsPath) -> Result<Self, WinError> {
+        Self::find_first(pattern)
+    }
+
+    fn find_first(c_pattern: &WindowsPath) -> Result<Self, WinError> {
+        let mut find_data: WIN32_FIND_DATAA = unsafe { core::mem::zeroed() };
+
+        let handle = unsafe {
+            FindFirstFileA(
+                c_pattern.as_ptr() as *const i8,
+                &mut find_data
+            )
+        };
+
+        if handle == INVALID_HANDLE_VALUE {
+            let error = WinError::from_last_error();
+
+            // Nothing matched the pattern; this is an empty directory, not an error
+            if u32::from(error) == ERROR_FILE_NOT_FOUND || u32::from(error) == ERROR_NO_MORE_FILES {
+                return Ok(Self {
+                    handle: None,
+                    pending: None
+                })
+            }
+
+            return Err(error)
+        }
+
+        Ok(Self {
+            handle: Some(handle),
+            pending: Some(DirEntry::from_find_data(&find_data))
+        })
+    }
+
+    fn next_native(&mut self) -> Option<Result<DirEntry, WinError>> {
+        if let Some(entry) = self.pending.take() {
+            return Some(Ok(entry));
+        }
+
+        let handle = self.handle?;
+        let mut find_data: WIN32_FIND_DATAA = unsafe { core::mem::zeroed() };
+
+        let success = unsafe {
+            FindNextFileA(handle, &mut find_data)
+        };
+
+        if success == 0 {
+            let error = WinError::from_last_error();
+            self.close();
+
+            if u32::from(error) == ERROR_NO_MORE_FILES {
+                return None;
+            }
+
+            return Some(Err(error));
+        }
+
+        Some(Ok(DirEntry::from_find_data(&find_data)))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.handle.is_none()
+    }
+
+    pub fn close(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let result = unsafe {
+                FindClose(handle)
+            };
+
+            if result == 0 {
+                error!("Error closing search handle: {}", WinError::from_last_error());
+            }
+        }
+    }
+}
+
+impl Iterator for ReadDir {
+    type Item = Result<DirEntry, WinError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = self.next_native()?;
+
+            if let Ok(entry) = &entry {
+                if entry.name == "." || entry.name == ".." {
+                    continue;
+                }
+            }
+
+            return Some(entry);
+        }
+    }
+}
+
+impl Drop for ReadDir {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Largest transfer a single read or write on a `WinFileHandle` will issue,
+/// bounding the buffer it stages data through. Larger buffers are served
+/// partially, as allowed by the embedded-io traits.
+pub const MAX_OVERLAPPED_TRANSFER: usize = 64 * 1024;
+
+/// Overlapped state of an async `WinFileHandle`; the offset the next operation
+/// starts at, and the `IoRequest` used to issue it.
+///
+/// Data is staged through the request's owned buffer, so dropping a pending
+/// read or write future never leaves the kernel pointing at freed memory. The
+/// buffer grows to the largest transfer seen, up to `MAX_OVERLAPPED_TRANSFER`,
+/// and is reused after that. Each call issues a single transfer of up to that
+/// much, at the cost of one copy. Use an `IoRequest` directly to transfer
+/// without it.
+///
+/// An operation abandoned by a dropped future is waited for before the next
+/// one starts. Abandoned writes still advance the offset; data from abandoned
+/// reads is discarded and the offset is left untouched.
 #[derive(Debug)]
 pub struct Overlapped {
-    overlapped: Option<OVERLAPPED>,
+    request: IoRequest<Vec<u8>>,
+    flush: FlushRequest,
     offset: u64
 }
 
 impl Overlapped {
+    /// Wraps an existing `OVERLAPPED`, taking ownership of its event. `None`
+    /// gives a closed `Overlapped`.
+    #[deprecated(note = "the OVERLAPPED block is owned by an IoRequest now; use Overlapped::new")]
     pub fn wrap(overlapped: Option<OVERLAPPED>) -> Self {
         Self {
-            overlapped,
+            request: IoRequest::from_overlapped(Vec::new(), overlapped),
+            flush: FlushRequest::new(),
             offset: 0
         }
     }
 
     pub fn new() -> Result<Self, WinError> {
-        let overlapped_handle = unsafe {
-            CreateEventA(
-                core::ptr::null_mut(),
-                true as i32,
-                false as i32,
-                core::ptr::null()
-            )
-        };
-
-        if overlapped_handle.is_null() {
-            return Err(WinError::from_last_error())
-        }
-
         Ok(Self {
-            overlapped: Some(OVERLAPPED {
-                Internal: 0,
-                InternalHigh: 0,
-                Offset: 0,
-                OffsetHigh: 0,
-                hEvent: overlapped_handle
-            }),
+            request: IoRequest::new(Vec::new())?,
+            flush: FlushRequest::new(),
             offset: 0
         })
     }
 
+    /// The `OVERLAPPED` block async operations are issued with. Fails with
+    /// `ERROR_BUSY` while an operation left by a dropped future is in flight.
+    #[deprecated(note = "the OVERLAPPED block is owned by an IoRequest now; issue I/O through WinFileHandle or IoRequest")]
+    pub fn get_inner(&mut self) -> Result<&mut OVERLAPPED, WinError> {
+        self.request.overlapped_mut()
+    }
+
+    /// Resets the event and points the `OVERLAPPED` block at the current offset.
+    #[deprecated(note = "async operations on WinFileHandle reset the OVERLAPPED block themselves")]
+    pub fn reset_overlapped(&mut self) -> Result<(), WinError> {
+        self.request.reset(self.offset)
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
     pub fn set_offset(&mut self, offset: u64) {
         self.offset = offset;
     }
@@ -138,42 +376,95 @@ impl Overlapped {
         self.offset = new_offset.max(0) as u64;
     }
 
-    pub fn get_inner(&mut self) -> Result<&mut OVERLAPPED, WinError> {
-        self.overlapped.as_mut().ok_or(WinError::from(ERROR_INVALID_HANDLE))
+    /// True if an operation abandoned by a dropped future is still in flight.
+    pub fn is_pending(&self) -> bool {
+        self.request.is_pending() || self.flush.is_pending()
     }
 
     pub fn is_closed(&self) -> bool {
-        !self.overlapped.is_some()
+        self.request.is_closed()
     }
 
-    pub fn reset_overlapped(&mut self) -> Result<(), WinError> {
-        let offset = self.offset;
-        let overlapped = self.get_inner()?;
+    /// Wait for an operation left in flight by a dropped future.
+    async fn settle(&mut self) -> Result<(), WinError> {
+        if let Some(kind) = self.request.pending() {
+            let transferred = self.request.wait().await?;
 
-        let result = unsafe {
-            ResetEvent(overlapped.hEvent)
-        };
+            if kind == IoKind::Write {
+                self.advance_offset(transferred as i64);
+            }
+        }
 
-        if result == 0 {
-            return Err(WinError::from_last_error())
+        self.flush.wait().await
+    }
+
+    fn settle_blocking(&mut self) -> Result<(), WinError> {
+        if let Some(kind) = self.request.pending() {
+            let transferred = self.request.wait_blocking()?;
+
+            if kind == IoKind::Write {
+                self.advance_offset(transferred as i64);
+            }
         }
 
-        overlapped.Offset = offset as u32;
-        overlapped.OffsetHigh = (offset >> 32) as u32;
-        overlapped.Internal = 0;
-        overlapped.InternalHigh = 0;
+        self.flush.wait_blocking()
+    }
+
+    fn staging_buffer(&mut self, len: usize) -> Result<&mut [u8], WinError> {
+        let buffer = self.request
+            .buffer_mut()
+            .ok_or(WinError::from(ERROR_IO_PENDING))?;
 
-        Ok(())
+        if buffer.len() < len {
+            buffer.resize(len, 0);
+        }
+
+        Ok(&mut buffer[..len])
+    }
+
+    async fn read(&mut self, file: HANDLE, buf: &mut [u8]) -> Result<usize, WinError> {
+        self.settle().await?;
+
+        let len = buf.len().min(MAX_OVERLAPPED_TRANSFER);
+        self.staging_buffer(len)?;
+
+        let read = self.request.read(file, self.offset, len).await?;
+        buf[..read].copy_from_slice(&self.staging_buffer(read)?[..read]);
+        self.advance_offset(read as i64);
+
+        Ok(read)
+    }
+
+    async fn write(&mut self, file: HANDLE, buf: &[u8]) -> Result<usize, WinError> {
+        self.settle().await?;
+
+        let len = buf.len().min(MAX_OVERLAPPED_TRANSFER);
+        self.staging_buffer(len)?.copy_from_slice(&buf[..len]);
+
+        let written = self.request.write(file, self.offset, len).await?;
+        self.advance_offset(written as i64);
+
+        Ok(written)
     }
 
+    async fn flush(&mut self, file: HANDLE) -> Result<(), WinError> {
+        self.settle().await?;
+        self.flush.start(file)?;
+        self.flush.wait().await
+    }
+
+    fn flush_blocking(&mut self, file: HANDLE) -> Result<(), WinError> {
+        self.settle_blocking()?;
+        self.flush.start(file)?;
+        self.flush.wait_blocking()
+    }
+
+    /// Waits for any in-flight operation, then closes the overlapped event.
     pub fn close(&mut self) -> Result<(), WinError> {
-        if let Some(mut overlapped) = self.overlapped.take() {
-            close_handle_native(overlapped.hEvent)?;
-            // Release it just in case
-            overlapped.hEvent = core::ptr::null_mut();
-        }
+        let flush_result = self.flush.wait_blocking();
+        self.request.close();
 
-        Ok(())
+        flush_result
     }
 }
 
@@ -187,9 +478,10 @@ unsafe impl Send for WinFileHandle {}
 
 impl WinFileHandle {
     /// Be careful to never initialize this with a search HANDLE. Doing so
-    /// will result in unexpected panic.
+    /// will result in unexpected panic. Use `ReadDir` for searches instead.
     ///
-    /// Also, if overlapped is given, it must not be null.
+    /// If overlapped is given, the handle must have been opened with
+    /// `FileFlagsAndAttributes::FlagOverlapped`.
     pub fn new(handle: GenericWinHandle, overlapped: Option<Overlapped>) -> Self {
         WinFileHandle {
             handle,
@@ -197,10 +489,19 @@ impl WinFileHandle {
         }
     }
 
+    pub fn open_path(path: &XboxPath, access: AccessRights, share: ShareMode, creation: CreationDisposition, flags_attributes: FileFlagsAndAttributes) -> Result<Self, WinError> {
+        Self::create_file(&path.to_c_path()?, access, share, creation, flags_attributes)
+    }
+
+    #[deprecated(note = "use WinFileHandle::open_path, which takes an XboxPath")]
     pub fn open(path: &WindowsPath, access: AccessRights, share: ShareMode, creation: CreationDisposition, flags_attributes: FileFlagsAndAttributes) -> Result<Self, WinError> {
+        Self::create_file(path, access, share, creation, flags_attributes)
+    }
+
+    fn create_file(c_path: &WindowsPath, access: AccessRights, share: ShareMode, creation: CreationDisposition, flags_attributes: FileFlagsAndAttributes) -> Result<Self, WinError> {
         let handle = unsafe {
             CreateFileA(
-                path.as_ptr() as *const i8,
+                c_path.as_ptr() as *const i8,
                 access.bits(),
                 share.bits(),
                 core::ptr::null_mut(),
@@ -230,6 +531,21 @@ impl WinFileHandle {
         self.handle.get_inner()
     }
 
+    /// The underlying native HANDLE, for inter-op with other winapi or kernel
+    /// functions. It stays owned by this `WinFileHandle`.
+    pub fn as_raw_handle(&self) -> Result<HANDLE, WinError> {
+        self.get_inner()
+    }
+
+    #[deprecated(note = "async operations on WinFileHandle reset the OVERLAPPED block themselves")]
+    pub fn reset_overlapped(&mut self) -> Result<(), WinError> {
+        if let Some(overlapped) = self.overlapped.as_mut() {
+            overlapped.request.reset(overlapped.offset)?;
+        }
+
+        Ok(())
+    }
+
     fn get_overlapped(&mut self) -> Result<&mut Overlapped, WinError> {
         if let Some(overlapped) = self.overlapped.as_mut() {
             Ok(overlapped)
@@ -244,14 +560,96 @@ impl WinFileHandle {
 
     }
 
-    pub fn reset_overlapped(&mut self) -> Result<(), WinError> {
-        if let Some(overlapped) = self.overlapped.as_mut() {
-            overlapped.reset_overlapped()?;
+    /// Handle for positional operations, which need `FileFlagsAndAttributes::FlagOverlapped`.
+    fn get_positional(&self) -> Result<HANDLE, WinError> {
+        if self.overlapped.is_none() {
+            error!("Tried a positional operation on a non-overlapped file handle");
+            return Err(WinError::from(ERROR_INVALID_HANDLE))
+        }
+
+        self.get_inner()
+    }
+
+    /// Reads up to `MAX_OVERLAPPED_TRANSFER` bytes at `offset`, leaving the async
+    /// offset untouched.
+    ///
+    /// Every call issues its own `IoRequest`, so several reads can be in flight
+    /// on the same handle at once, such as with `futures_lite::future::zip`.
+    /// Dropping the future mid-flight blocks until the kernel is done with it.
+    pub async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, WinError> {
+        let handle = self.get_positional()?;
+        let mut request = IoRequest::new(vec![0u8; buf.len().min(MAX_OVERLAPPED_TRANSFER)])?;
+
+        Self::read_with(&mut request, handle, offset, buf).await
+    }
+
+    /// Writes up to `MAX_OVERLAPPED_TRANSFER` bytes at `offset`, leaving the async
+    /// offset untouched. Like `read_at`, each call is independent.
+    pub async fn write_at(&self, offset: u64, buf: &[u8]) -> Result<usize, WinError> {
+        let handle = self.get_positional()?;
+        let mut request = IoRequest::new(vec![0u8; buf.len().min(MAX_OVERLAPPED_TRANSFER)])?;
+
+        Self::write_with(&mut request, handle, offset, buf).await
+    }
+
+    /// Fills `buf` with the bytes at `offset`, issuing as many reads as needed.
+    pub async fn read_exact_at(&self, mut offset: u64, mut buf: &mut [u8]) -> Result<(), ReadExactError<WinError>> {
+        let handle = self.get_positional()?;
+        let mut request = IoRequest::new(vec![0u8; buf.len().min(MAX_OVERLAPPED_TRANSFER)])?;
+
+        while !buf.is_empty() {
+            let read = Self::read_with(&mut request, handle, offset, buf).await?;
+
+            if read == 0 {
+                return Err(ReadExactError::UnexpectedEof)
+            }
+
+            buf = &mut buf[read..];
+            offset += read as u64;
+        }
+
+        Ok(())
+    }
+
+    /// Writes all of `buf` at `offset`, issuing as many writes as needed.
+    pub async fn write_all_at(&self, mut offset: u64, mut buf: &[u8]) -> Result<(), WinError> {
+        let handle = self.get_positional()?;
+        let mut request = IoRequest::new(vec![0u8; buf.len().min(MAX_OVERLAPPED_TRANSFER)])?;
+
+        while !buf.is_empty() {
+            let written = Self::write_with(&mut request, handle, offset, buf).await?;
+
+            if written == 0 {
+                return Err(WinError::from(ERROR_DISK_FULL))
+            }
+
+            buf = &buf[written..];
+            offset += written as u64;
         }
 
         Ok(())
     }
 
+    /// Reads at `offset` through `request`, as much as fits in both its buffer and `buf`.
+    async fn read_with(request: &mut IoRequest<Vec<u8>>, handle: HANDLE, offset: u64, buf: &mut [u8]) -> Result<usize, WinError> {
+        let read = request.read(handle, offset, buf.len()).await?;
+
+        if let Some(buffer) = request.buffer() {
+            buf[..read].copy_from_slice(&buffer[..read]);
+        }
+
+        Ok(read)
+    }
+
+    /// Writes at `offset` through `request`, as much of `buf` as fits in its buffer.
+    async fn write_with(request: &mut IoRequest<Vec<u8>>, handle: HANDLE, offset: u64, buf: &[u8]) -> Result<usize, WinError> {
+        let buffer = request.buffer_mut().ok_or(WinError::from(ERROR_BUSY))?;
+        let len = buf.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&buf[..len]);
+
+        request.write(handle, offset, len).await
+    }
+
     /// Query standard handle information. This can be called from
     /// any open handle, regardless of the `AccessRights` mode.
     pub fn query_standard_information(&self) -> Result<FileStandardInformation, WinMixedError> {
@@ -288,23 +686,132 @@ impl WinFileHandle {
         }
     }
 
+    /// Query timestamps and attributes. Requires an open handle with
+    /// `AccessRights::Read`.
+    pub fn query_basic_information(&self) -> Result<FileBasicInformation, WinMixedError> {
+        let mut file_info: FILE_BASIC_INFORMATION = unsafe { core::mem::zeroed() };
+        let mut io_status: IO_STATUS_BLOCK = unsafe { core::mem::zeroed() };
+
+        let status = unsafe {
+            NtQueryInformationFile(
+                self.get_inner().map_err(|err| WinMixedError::WinError(err))?,
+                &mut io_status,
+                &mut file_info as *mut _ as *mut c_void,
+                size_of::<FILE_BASIC_INFORMATION>() as u32,
+                _FILE_INFORMATION_CLASS_FileBasicInformation,
+            )
+        };
+
+        if status != 0 {
+            return Err(WinMixedError::NtStatus(NtStatusError::new(status)))
+        }
+
+        unsafe {
+            Ok(FileBasicInformation {
+                creation_time: file_info.CreationTime.QuadPart as u64,
+                last_access_time: file_info.LastAccessTime.QuadPart as u64,
+                last_write_time: file_info.LastWriteTime.QuadPart as u64,
+                change_time: file_info.ChangeTime.QuadPart as u64,
+                attributes: FileFlagsAndAttributes::from_bits_retain(file_info.FileAttributes),
+            })
+        }
+    }
+
+    fn set_information<T>(&self, information: &mut T, class: FILE_INFORMATION_CLASS) -> Result<(), WinMixedError> {
+        let mut io_status: IO_STATUS_BLOCK = unsafe { core::mem::zeroed() };
+
+        let status = unsafe {
+            NtSetInformationFile(
+                self.get_inner().map_err(|err| WinMixedError::WinError(err))?,
+                &mut io_status,
+                information as *mut T as *mut c_void,
+                size_of::<T>() as u32,
+                class,
+            )
+        };
+
+        if status != 0 {
+            return Err(WinMixedError::NtStatus(NtStatusError::new(status)))
+        }
+
+        Ok(())
+    }
+
+    /// Set timestamps and attributes. Requires an open handle with
+    /// `AccessRights::Write`.
+    ///
+    /// Timestamps set to 0 are left unchanged. Attributes are always
+    /// replaced; query them first to change a single one.
+    pub fn set_basic_information(&self, information: &FileBasicInformation) -> Result<(), WinMixedError> {
+        let mut attributes = information.attributes.bits();
+
+        // A value of 0 means "don't change"; use Normal to actually clear everything
+        if attributes == 0 {
+            attributes = FILE_ATTRIBUTE_NORMAL;
+        }
+
+        let mut file_info = FILE_BASIC_INFORMATION {
+            CreationTime: LARGE_INTEGER { QuadPart: information.creation_time as i64 },
+            LastAccessTime: LARGE_INTEGER { QuadPart: information.last_access_time as i64 },
+            LastWriteTime: LARGE_INTEGER { QuadPart: information.last_write_time as i64 },
+            ChangeTime: LARGE_INTEGER { QuadPart: information.change_time as i64 },
+            FileAttributes: attributes,
+        };
+
+        self.set_information(&mut file_info, _FILE_INFORMATION_CLASS_FileBasicInformation)
+    }
+
+    /// Set any of the creation, last access and last write timestamps, leaving
+    /// attributes untouched.
+    pub fn set_times(&self, creation_time: Option<u64>, last_access_time: Option<u64>, last_write_time: Option<u64>) -> Result<(), WinMixedError> {
+        let mut file_info: FILE_BASIC_INFORMATION = unsafe { core::mem::zeroed() };
+        file_info.CreationTime.QuadPart = creation_time.unwrap_or(0) as i64;
+        file_info.LastAccessTime.QuadPart = last_access_time.unwrap_or(0) as i64;
+        file_info.LastWriteTime.QuadPart = last_write_time.unwrap_or(0) as i64;
+
+        self.set_information(&mut file_info, _FILE_INFORMATION_CLASS_FileBasicInformation)
+    }
+
+    /// Truncates or extends the file to the given size, through `FileEndOfFileInformation`.
+    /// Extended space reads back as zeros.
+    pub fn set_len(&self, len: u64) -> Result<(), WinMixedError> {
+        let mut file_info = FILE_END_OF_FILE_INFORMATION {
+            EndOfFile: LARGE_INTEGER { QuadPart: len as i64 },
+        };
+
+        self.set_information(&mut file_info, _FILE_INFORMATION_CLASS_FileEndOfFileInformation)
+    }
+
+    /// Preallocates disk space for the file without changing its size, through
+    /// `FileAllocationInformation`. Useful to avoid fragmentation on large writes.
+    pub fn set_allocation_size(&self, size: u64) -> Result<(), WinMixedError> {
+        let mut file_info = FILE_ALLOCATION_INFORMATION {
+            AllocationSize: LARGE_INTEGER { QuadPart: size as i64 },
+        };
+
+        self.set_information(&mut file_info, _FILE_INFORMATION_CLASS_FileAllocationInformation)
+    }
+
     pub fn is_closed(&self) -> bool {
         self.handle.is_closed()
     }
 
     pub fn close(&mut self) -> Result<(), WinError> {
-        self.handle.close()?;
-
+        // Operations left in flight by dropped futures must finish first
         if let Some(mut overlapped) = self.overlapped.take() {
             overlapped.close()?;
         }
 
-        Ok(())
+        self.handle.close()
     }
 }
 
 impl Drop for WinFileHandle {
     fn drop(&mut self) {
+        if let Some(mut overlapped) = self.overlapped.take() {
+            let _ = overlapped.close();
+        }
+
         if let Err(e) = self.handle.close() {
             error!("Error closing dropped file handle: {}", e);
         }
@@ -335,8 +842,19 @@ impl embedded_io::Write for WinFileHandle {
         Ok(bytes_written as usize)
     }
 
+    /// Flushes the file buffers to disk, through `NtFlushBuffersFile`.
+    ///
+    /// On overlapped handles, any operation left in flight is waited for first.
     fn flush(&mut self) -> Result<(), Self::Error> {
-        panic!("Flushing WinNT file handles causes a deadlock")
+        let handle = self.get_inner()?;
+
+        if let Some(overlapped) = self.overlapped.as_mut() {
+            return overlapped.flush_blocking(handle);
+        }
+
+        let mut request = FlushRequest::new();
+        request.start(handle)?;
+        request.wait_blocking()
     }
 }
 
@@ -396,118 +914,37 @@ impl embedded_io::Seek for WinFileHandle {
 }
 
 impl embedded_io_async::Write for WinFileHandle {
+    /// Writes at the current async offset, staging the data through the handle's
+    /// own buffer.
+    ///
+    /// This future is cancellation-safe; see `Overlapped`.
     async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
         let handle = self.get_inner()?;
-        self.reset_overlapped()?;
-        let overlapped = self.get_overlapped()?;
-        let inner_overlapped = overlapped.get_inner()?;
-
-        let mut bytes_written: u32 = 0;
-        let success = unsafe {
-            WriteFile(
-                handle,
-                buf.as_ptr() as *const c_void,
-                buf.len() as u32,
-                &mut bytes_written,
-                inner_overlapped,
-            )
-        };
-
-        if success != 0 {
-            overlapped.advance_offset(bytes_written as i64);
-            return Ok(bytes_written as usize)
-        }
-
-        let error = WinError::from_last_error();
-        if u32::from(error) != ERROR_IO_PENDING {
-            return Err(error);
-        }
-
-        let mut overlapped_bytes_written: u32 = 0;
-        loop {
-            let result = unsafe {
-                GetOverlappedResult(
-                    handle,
-                    inner_overlapped,
-                    &mut overlapped_bytes_written,
-                    0
-                )
-            };
-
-            if result != 0 {
-                overlapped.advance_offset(overlapped_bytes_written as i64);
-                return Ok(overlapped_bytes_written as usize);
-            }
-
-            let last_error = WinError::from_last_error();
-
-            if u32::from(last_error) != ERROR_IO_INCOMPLETE {
-                return Err(last_error);
-            }
-
-            yield_now().await;
-        }
+        self.get_overlapped()?.write(handle, buf).await
     }
 
+    /// Flushes the file buffers to disk, through `NtFlushBuffersFile`,
+    /// yielding to the executor while the flush is in flight.
+    ///
+    /// Non-overlapped handles can't flush asynchronously; they block instead.
     async fn flush(&mut self) -> Result<(), Self::Error> {
-        unimplemented!();
+        if self.overlapped.is_none() {
+            return embedded_io::Write::flush(self);
+        }
+
+        let handle = self.get_inner()?;
+        self.get_overlapped()?.flush(handle).await
     }
 }
 
 impl embedded_io_async::Read for WinFileHandle {
+    /// Reads from the current async offset, staging the data through the handle's
+    /// own buffer.
+    ///
+    /// This future is cancellation-safe; see `Overlapped`.
     async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
         let handle = self.get_inner()?;
-        self.reset_overlapped()?;
-        let overlapped = self.get_overlapped()?;
-        let inner_overlapped = overlapped.get_inner()?;
-
-        let mut bytes_read: u32 = 0;
-
-        let success = unsafe {
-            ReadFile(
-                handle,
-                buf.as_mut_ptr() as *mut c_void,
-                buf.len() as u32,
-                &mut bytes_read,
-                inner_overlapped,
-            )
-        };
-
-        // Done immediately
-        if success == 1 {
-            overlapped.advance_offset(bytes_read as i64);
-            return Ok(bytes_read as usize)
-        }
-
-        let error = WinError::from_last_error();
-        if u32::from(error) != ERROR_IO_PENDING {
-            return Err(error);
-        }
-
-        let mut overlapped_bytes_read: u32 = 0;
-        loop {
-            let result = unsafe {
-                GetOverlappedResult(
-                    handle,
-                    inner_overlapped,
-                    &mut overlapped_bytes_read,
-                    0
-                )
-            };
-
-            if result != 0 {
-                overlapped.advance_offset(overlapped_bytes_read as i64);
-                return Ok(overlapped_bytes_read as usize);
-            }
-
-            let last_error = WinError::from_last_error();
-
-            if u32::from(last_error) != ERROR_IO_INCOMPLETE {
-                return Err(last_error);
-            }
-
-            yield_now().await;
-        }
+        self.get_overlapped()?.read(handle, buf).await
     }
 }
 
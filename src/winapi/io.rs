@@ -0,0 +1,513 @@
+use crate::winapi::error::{NtStatusError, WinError};
+use crate::winapi::handle::GenericWinHandle;
+use alloc::boxed::Box;
+use core::ffi::c_void;
+use futures_lite::future::yield_now;
+use log::error;
+use nxdk_sys::winapi::*;
+
+/// `STATUS_PENDING`; the operation was queued and hasn't completed yet.
+pub const STATUS_PENDING: i32 = 0x103;
+
+/// Kind of operation an `IoRequest` has in flight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoKind {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IoStatus {
+    Idle,
+    Pending(IoKind),
+    /// A read hit the end of the file before being queued.
+    Eof,
+}
+
+fn empty_overlapped(event: HANDLE) -> OVERLAPPED {
+    OVERLAPPED {
+        Internal: 0,
+        InternalHigh: 0,
+        Offset: 0,
+        OffsetHigh: 0,
+        hEvent: event
+    }
+}
+
+/// The memory the kernel writes into while an operation is in flight. Boxed so it
+/// never moves, even if the owning `IoRequest` does.
+#[derive(Debug)]
+struct IoRequestState<B> {
+    overlapped: OVERLAPPED,
+    buffer: B,
+}
+
+/// A single overlapped I/O request that owns both its `OVERLAPPED` block and
+/// the data buffer the kernel reads from or writes into.
+///
+/// The Xbox kernel has no way to cancel in-flight I/O, so instead of lending
+/// a borrowed buffer to `ReadFile`/`WriteFile`, the request keeps ownership of
+/// everything the kernel touches. If a future awaiting the request is dropped
+/// mid-flight, the operation stays pending inside the request. The async
+/// `read` and `write` wait for it before starting the next one, `start_read`
+/// and `start_write` fail with `ERROR_BUSY`, and dropping the request blocks
+/// until the memory is no longer in use.
+///
+/// The buffer can be any owned byte container with a stable address, such as
+/// `Vec<u8>` or `Box<[u8]>`.
+#[derive(Debug)]
+pub struct IoRequest<B: AsRef<[u8]> + AsMut<[u8]>> {
+    state: Box<IoRequestState<B>>,
+    event: GenericWinHandle,
+    file: HANDLE,
+    status: IoStatus,
+}
+
+unsafe impl<B: AsRef<[u8]> + AsMut<[u8]> + Send> Send for IoRequest<B> {}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> IoRequest<B> {
+    pub fn new(buffer: B) -> Result<Self, WinError> {
+        let event = unsafe {
+            CreateEventA(
+                core::ptr::null_mut(),
+                true as i32,
+                false as i32,
+                core::ptr::null()
+            )
+        };
+
+        if event.is_null() {
+            return Err(WinError::from_last_error())
+        }
+
+        Ok(Self::from_overlapped(buffer, Some(empty_overlapped(event))))
+    }
+
+    /// Builds a request around an existing `OVERLAPPED`, taking ownership of its
+    /// event. Without one, the request starts out closed.
+    pub(crate) fn from_overlapped(buffer: B, overlapped: Option<OVERLAPPED>) -> Self {
+        let mut event = GenericWinHandle::new(overlapped.map_or(core::ptr::null_mut(), |overlapped| overlapped.hEvent));
+
+        if overlapped.is_none() {
+            let _ = event.close();
+        }
+
+        Self {
+            state: Box::new(IoRequestState {
+                overlapped: overlapped.unwrap_or(empty_overlapped(core::ptr::null_mut())),
+                buffer,
+            }),
+            event,
+            file: core::ptr::null_mut(),
+            status: IoStatus::Idle,
+        }
+    }
+
+    /// Kind of the operation currently in flight, if any.
+    pub fn pending(&self) -> Option<IoKind> {
+        match self.status {
+            IoStatus::Pending(kind) => Some(kind),
+            _ => None,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending().is_some()
+    }
+
+    /// Access the owned buffer. Returns None while an operation is in flight.
+    pub fn buffer(&self) -> Option<&B> {
+        if self.is_pending() {
+            return None;
+        }
+
+        Some(&self.state.buffer)
+    }
+
+    /// Mutably access the owned buffer. Returns None while an operation is in flight.
+    pub fn buffer_mut(&mut self) -> Option<&mut B> {
+        if self.is_pending() {
+            return None;
+        }
+
+        Some(&mut self.state.buffer)
+    }
+
+    /// The `OVERLAPPED` block handed to the kernel. Fails with `ERROR_BUSY`
+    /// while an operation is in flight.
+    pub(crate) fn overlapped_mut(&mut self) -> Result<&mut OVERLAPPED, WinError> {
+        self.event.get_inner()?;
+
+        if self.is_pending() {
+            return Err(WinError::from(ERROR_BUSY))
+        }
+
+        Ok(&mut self.state.overlapped)
+    }
+
+    /// Resets the event and points the `OVERLAPPED` block at `offset`.
+    ///
+    /// Fails with `ERROR_BUSY` while an operation is in flight, as the kernel
+    /// still owns the memory. An unclaimed end of file result is discarded.
+    pub(crate) fn reset(&mut self, offset: u64) -> Result<(), WinError> {
+        if self.is_pending() {
+            return Err(WinError::from(ERROR_BUSY))
+        }
+
+        self.status = IoStatus::Idle;
+
+        let result = unsafe {
+            ResetEvent(self.event.get_inner()?)
+        };
+
+        if result == 0 {
+            return Err(WinError::from_last_error())
+        }
+
+        let overlapped = &mut self.state.overlapped;
+        overlapped.Offset = offset as u32;
+        overlapped.OffsetHigh = (offset >> 32) as u32;
+        overlapped.Internal = 0;
+        overlapped.InternalHigh = 0;
+
+        Ok(())
+    }
+
+    fn prepare(&mut self, file: HANDLE, offset: u64) -> Result<(), WinError> {
+        self.reset(offset)?;
+        self.file = file;
+
+        Ok(())
+    }
+
+    fn submitted(&mut self, kind: IoKind, success: i32) -> Result<(), WinError> {
+        // Completed immediately; the result is still collected through `wait`
+        if success != 0 {
+            self.status = IoStatus::Pending(kind);
+            return Ok(())
+        }
+
+        let error = WinError::from_last_error();
+        match u32::from(error) {
+            ERROR_IO_PENDING => {
+                self.status = IoStatus::Pending(kind);
+                Ok(())
+            }
+            ERROR_HANDLE_EOF if kind == IoKind::Read => {
+                self.status = IoStatus::Eof;
+                Ok(())
+            }
+            _ => Err(error)
+        }
+    }
+
+    /// Start reading up to `len` bytes from `file` at `offset` into the owned buffer.
+    ///
+    /// `file` must be opened with `FileFlagsAndAttributes::FlagOverlapped`, and
+    /// must stay open until the request completes. Fails with `ERROR_BUSY` if
+    /// another operation is still in flight.
+    pub fn start_read(&mut self, file: HANDLE, offset: u64, len: usize) -> Result<(), WinError> {
+        self.prepare(file, offset)?;
+
+        let state = &mut *self.state;
+        let buffer = state.buffer.as_mut();
+        let len = len.min(buffer.len());
+
+        // Only written on immediate completion, so a local is fine here
+        let mut bytes_read: u32 = 0;
+        let success = unsafe {
+            ReadFile(
+                file,
+                buffer.as_mut_ptr() as *mut c_void,
+                len as u32,
+                &mut bytes_read,
+                &mut state.overlapped,
+            )
+        };
+
+        self.submitted(IoKind::Read, success)
+    }
+
+    /// Start writing the first `len` bytes of the owned buffer to `file` at `offset`.
+    ///
+    /// `file` must be opened with `FileFlagsAndAttributes::FlagOverlapped`, and
+    /// must stay open until the request completes. Fails with `ERROR_BUSY` if
+    /// another operation is still in flight.
+    pub fn start_write(&mut self, file: HANDLE, offset: u64, len: usize) -> Result<(), WinError> {
+        self.prepare(file, offset)?;
+
+        let state = &mut *self.state;
+        let buffer = state.buffer.as_ref();
+        let len = len.min(buffer.len());
+
+        // Only written on immediate completion, so a local is fine here
+        let mut bytes_written: u32 = 0;
+        let success = unsafe {
+            WriteFile(
+                file,
+                buffer.as_ptr() as *const c_void,
+                len as u32,
+                &mut bytes_written,
+                &mut state.overlapped,
+            )
+        };
+
+        self.submitted(IoKind::Write, success)
+    }
+
+    /// Poll the in-flight operation once. Returns None if it's still running.
+    pub fn poll_complete(&mut self) -> Option<Result<usize, WinError>> {
+        self.get_result(false)
+    }
+
+    fn get_result(&mut self, wait: bool) -> Option<Result<usize, WinError>> {
+        match self.status {
+            IoStatus::Idle => return Some(Err(WinError::from(ERROR_INVALID_HANDLE))),
+            IoStatus::Eof => {
+                self.status = IoStatus::Idle;
+                return Some(Ok(0));
+            }
+            IoStatus::Pending(_) => {}
+        }
+
+        let mut transferred: u32 = 0;
+        let result = unsafe {
+            GetOverlappedResult(
+                self.file,
+                &mut self.state.overlapped,
+                &mut transferred,
+                wait as i32
+            )
+        };
+
+        if result != 0 {
+            self.status = IoStatus::Idle;
+            return Some(Ok(transferred as usize));
+        }
+
+        let last_error = WinError::from_last_error();
+
+        match u32::from(last_error) {
+            ERROR_IO_INCOMPLETE => None,
+            ERROR_HANDLE_EOF => {
+                self.status = IoStatus::Idle;
+                Some(Ok(0))
+            }
+            _ => {
+                self.status = IoStatus::Idle;
+                Some(Err(last_error))
+            }
+        }
+    }
+
+    /// Wait for the in-flight operation to complete, yielding to the executor
+    /// in between polls. Returns the number of bytes transferred.
+    ///
+    /// Dropping this future leaves the operation pending inside the request.
+    pub async fn wait(&mut self) -> Result<usize, WinError> {
+        loop {
+            if let Some(result) = self.poll_complete() {
+                return result;
+            }
+
+            yield_now().await;
+        }
+    }
+
+    /// Block the current thread until the in-flight operation completes.
+    pub fn wait_blocking(&mut self) -> Result<usize, WinError> {
+        self.get_result(true).unwrap_or(Ok(0))
+    }
+
+    /// Wait for an operation left in flight by a dropped future, discarding its result.
+    async fn settle(&mut self) {
+        if self.is_pending() {
+            if let Err(e) = self.wait().await {
+                error!("Abandoned overlapped operation failed: {}", e);
+            }
+        }
+    }
+
+    /// Read up to `len` bytes from `file` at `offset` into the owned buffer.
+    ///
+    /// An operation left in flight by a dropped future is waited for first.
+    pub async fn read(&mut self, file: HANDLE, offset: u64, len: usize) -> Result<usize, WinError> {
+        self.settle().await;
+        self.start_read(file, offset, len)?;
+        self.wait().await
+    }
+
+    /// Write the first `len` bytes of the owned buffer to `file` at `offset`.
+    ///
+    /// An operation left in flight by a dropped future is waited for first.
+    pub async fn write(&mut self, file: HANDLE, offset: u64, len: usize) -> Result<usize, WinError> {
+        self.settle().await;
+        self.start_write(file, offset, len)?;
+        self.wait().await
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.event.is_closed()
+    }
+
+    /// Wait for any in-flight operation, then release the event. Waiting happens
+    /// on the event itself, so it's fine if the file handle was closed already.
+    ///
+    /// Once closed, starting new operations fails with `ERROR_INVALID_HANDLE`.
+    pub fn close(&mut self) {
+        if self.is_pending() {
+            if let Ok(event) = self.event.get_inner() {
+                unsafe {
+                    WaitForSingleObject(event, INFINITE);
+                }
+            }
+
+            self.status = IoStatus::Idle;
+        }
+
+        self.state.overlapped.hEvent = core::ptr::null_mut();
+
+        if let Err(e) = self.event.close() {
+            error!("Error closing overlapped event: {}", e);
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> Drop for IoRequest<B> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+
+/// A `NtFlushBuffersFile` call that owns its `IO_STATUS_BLOCK`.
+///
+/// On files opened with `FileFlagsAndAttributes::FlagOverlapped` the flush may
+/// complete asynchronously, with the kernel writing the final status later on.
+/// Like `IoRequest`, a flush left in flight by a dropped future is waited for
+/// before the status block is reused or freed.
+pub struct FlushRequest {
+    status: Box<IO_STATUS_BLOCK>,
+    /// The file being flushed. `NtFlushBuffersFile` takes no event, so the
+    /// kernel signals the file itself when the flush completes.
+    file: HANDLE,
+    pending: bool,
+}
+
+unsafe impl Send for FlushRequest {}
+
+impl core::fmt::Debug for FlushRequest {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FlushRequest")
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
+
+impl FlushRequest {
+    pub fn new() -> Self {
+        Self {
+            status: Box::new(unsafe { core::mem::zeroed() }),
+            file: core::ptr::null_mut(),
+            pending: false,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    fn read_status(&self) -> i32 {
+        unsafe {
+            core::ptr::read_volatile(&self.status.__bindgen_anon_1.Status)
+        }
+    }
+
+    /// Start flushing the buffers of `file`, which must stay open until the
+    /// request completes. Fails with `ERROR_BUSY` if another flush is still
+    /// in flight.
+    pub fn start(&mut self, file: HANDLE) -> Result<(), WinError> {
+        if self.pending {
+            return Err(WinError::from(ERROR_BUSY))
+        }
+
+        self.status.__bindgen_anon_1.Status = STATUS_PENDING;
+        self.status.Information = 0;
+
+        let status = unsafe {
+            NtFlushBuffersFile(file, &mut *self.status)
+        };
+
+        if status == STATUS_PENDING {
+            self.file = file;
+            self.pending = true;
+            return Ok(())
+        }
+
+        if status < 0 {
+            return Err(WinError::from_nt_status(NtStatusError::new(status)))
+        }
+
+        Ok(())
+    }
+
+    /// Poll the in-flight flush once. Returns None if it's still running.
+    pub fn poll_complete(&mut self) -> Option<Result<(), WinError>> {
+        if !self.pending {
+            return Some(Ok(()));
+        }
+
+        let status = self.read_status();
+
+        if status == STATUS_PENDING {
+            return None;
+        }
+
+        self.pending = false;
+
+        if status < 0 {
+            return Some(Err(WinError::from_nt_status(NtStatusError::new(status))));
+        }
+
+        Some(Ok(()))
+    }
+
+    /// Wait for the in-flight flush, yielding to the executor in between polls.
+    pub async fn wait(&mut self) -> Result<(), WinError> {
+        loop {
+            if let Some(result) = self.poll_complete() {
+                return result;
+            }
+
+            yield_now().await;
+        }
+    }
+
+    /// Block the current thread until the in-flight flush completes.
+    pub fn wait_blocking(&mut self) -> Result<(), WinError> {
+        loop {
+            if let Some(result) = self.poll_complete() {
+                return result;
+            }
+
+            // Other I/O on the file signals it too, so check the status again
+            unsafe {
+                WaitForSingleObject(self.file, INFINITE);
+            }
+        }
+    }
+}
+
+impl Default for FlushRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for FlushRequest {
+    fn drop(&mut self) {
+        if let Err(e) = self.wait_blocking() {
+            error!("Error flushing file buffers: {}", e);
+        }
+    }
+}
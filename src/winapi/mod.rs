@@ -1,7 +1,9 @@
 pub mod error;
 pub mod file;
 pub mod handle;
+pub mod io;
 pub mod thread;
+pub mod volume;
 
 pub type WindowsPath = [u8; 260];
 
@@ -0,0 +1,161 @@
+use crate::nxdk::mount::nx_is_drive_mounted;
+use crate::winapi::error::{NtStatusError, WinError, WinMixedError};
+use crate::winapi::file::{AccessRights, CreationDisposition, FileFlagsAndAttributes, ShareMode, WinFileHandle};
+use crate::path::XboxPathBuf;
+use alloc::format;
+use alloc::string::String;
+use core::ffi::c_void;
+use nxdk_sys::winapi::*;
+
+/// Size of the buffer used for variable-length volume queries. Plenty for
+/// the short labels and filesystem names FATX uses.
+const VOLUME_QUERY_BUFFER_SIZE: usize = 256;
+
+/// Space and identity information of a mounted volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeInformation {
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    pub bytes_per_cluster: u32,
+    pub bytes_per_sector: u32,
+    /// Filesystem name, usually `FATX`
+    pub filesystem_name: String,
+    pub serial_number: u32,
+    pub label: String,
+}
+
+/// Free space information, as reported by `GetDiskFreeSpaceExA`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskFreeSpace {
+    pub free_bytes_available: u64,
+    pub total_bytes: u64,
+    pub total_free_bytes: u64,
+}
+
+fn drive_root(drive_letter: char) -> Result<XboxPathBuf, WinError> {
+    if !drive_letter.is_ascii_alphabetic() || !nx_is_drive_mounted(drive_letter) {
+        return Err(WinError::from(ERROR_INVALID_DRIVE));
+    }
+
+    Ok(XboxPathBuf::from(format!("{}:\\", drive_letter)))
+}
+
+/// Query free space for the volume mounted at the given drive letter,
+/// through `GetDiskFreeSpaceExA`.
+pub fn disk_free_space(drive_letter: char) -> Result<DiskFreeSpace, WinError> {
+    let path = drive_root(drive_letter)?.to_c_path()?;
+
+    let mut free_bytes_available = ULARGE_INTEGER { QuadPart: 0 };
+    let mut total_bytes = ULARGE_INTEGER { QuadPart: 0 };
+    let mut total_free_bytes = ULARGE_INTEGER { QuadPart: 0 };
+
+    let success = unsafe {
+        GetDiskFreeSpaceExA(
+            path.as_ptr() as *const i8,
+            &mut free_bytes_available,
+            &mut total_bytes,
+            &mut total_free_bytes
+        )
+    };
+
+    if success == 0 {
+        return Err(WinError::from_last_error())
+    }
+
+    unsafe {
+        Ok(DiskFreeSpace {
+            free_bytes_available: free_bytes_available.QuadPart,
+            total_bytes: total_bytes.QuadPart,
+            total_free_bytes: total_free_bytes.QuadPart,
+        })
+    }
+}
+
+fn query_volume_native(handle: HANDLE, buffer: &mut [u64], class: FS_INFORMATION_CLASS) -> Result<(), WinMixedError> {
+    let mut io_status: IO_STATUS_BLOCK = unsafe { core::mem::zeroed() };
+
+    let status = unsafe {
+        NtQueryVolumeInformationFile(
+            handle,
+            &mut io_status,
+            buffer.as_mut_ptr() as *mut c_void,
+            size_of_val(buffer) as u32,
+            class,
+        )
+    };
+
+    if status != 0 {
+        return Err(WinMixedError::NtStatus(NtStatusError::new(status)))
+    }
+
+    Ok(())
+}
+
+/// Reads a counted, non-terminated string trailing a volume information struct.
+fn read_trailing_string(buffer: &[u64], offset: usize, length: usize) -> String {
+    let bytes = unsafe {
+        core::slice::from_raw_parts(buffer.as_ptr() as *const u8, size_of_val(buffer))
+    };
+
+    let end = (offset + length).min(bytes.len());
+
+    String::from_utf8_lossy(&bytes[offset.min(end)..end]).into_owned()
+}
+
+/// Query size, cluster layout, filesystem name and serial number of the volume
+/// mounted at the given drive letter, through `NtQueryVolumeInformationFile`.
+///
+/// Works on any drive letter mounted with `nxdk::mount`.
+pub fn query_volume_information(drive_letter: char) -> Result<VolumeInformation, WinMixedError> {
+    let path = drive_root(drive_letter)?;
+
+    // Opening the root directory is enough to reach the volume
+    let handle = WinFileHandle::open_path(
+        &path,
+        AccessRights::None,
+        ShareMode::Read | ShareMode::Write,
+        CreationDisposition::OpenExisting,
+        FileFlagsAndAttributes::FlagBackupSemantics
+    )?;
+
+    let native_handle = handle.as_raw_handle()?;
+    let mut buffer = [0u64; VOLUME_QUERY_BUFFER_SIZE / 8];
+
+    query_volume_native(native_handle, &mut buffer, _FS_INFORMATION_CLASS_FileFsSizeInformation)?;
+    let size_info = unsafe { &*(buffer.as_ptr() as *const FILE_FS_SIZE_INFORMATION) };
+
+    let bytes_per_sector = size_info.BytesPerSector;
+    let bytes_per_cluster = size_info.SectorsPerAllocationUnit * bytes_per_sector;
+    let (total_units, available_units) = unsafe {
+        (size_info.TotalAllocationUnits.QuadPart as u64, size_info.AvailableAllocationUnits.QuadPart as u64)
+    };
+
+    buffer.fill(0);
+    query_volume_native(native_handle, &mut buffer, _FS_INFORMATION_CLASS_FileFsVolumeInformation)?;
+    let volume_info = unsafe { &*(buffer.as_ptr() as *const FILE_FS_VOLUME_INFORMATION) };
+    let serial_number = volume_info.VolumeSerialNumber;
+    let label = read_trailing_string(
+        &buffer,
+        core::mem::offset_of!(FILE_FS_VOLUME_INFORMATION, VolumeLabel),
+        volume_info.VolumeLabelLength as usize
+    );
+
+    buffer.fill(0);
+    query_volume_native(native_handle, &mut buffer, _FS_INFORMATION_CLASS_FileFsAttributeInformation)?;
+    let attribute_info = unsafe { &*(buffer.as_ptr() as *const FILE_FS_ATTRIBUTE_INFORMATION) };
+    let filesystem_name = read_trailing_string(
+        &buffer,
+        core::mem::offset_of!(FILE_FS_ATTRIBUTE_INFORMATION, FileSystemName),
+        attribute_info.FileSystemNameLength as usize
+    );
+
+    Ok(VolumeInformation {
+        total_bytes: total_units * bytes_per_cluster as u64,
+        free_bytes: available_units * bytes_per_cluster as u64,
+        bytes_per_cluster,
+        bytes_per_sector,
+        filesystem_name,
+        serial_number,
+        label,
+    })
+}
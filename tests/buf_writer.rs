@@ -0,0 +1,79 @@
+//! `BufWriter` over in-memory writers.
+
+use embedded_io::{Error, ErrorKind, ErrorType, Write};
+use nxdk_rs::io::{BufWriteError, BufWriter};
+
+/// Accepts `limit` bytes, then reports `Ok(0)` for every write.
+#[derive(Debug)]
+struct Limited {
+    data: Vec<u8>,
+    limit: usize,
+}
+
+impl ErrorType for Limited {
+    type Error = ErrorKind;
+}
+
+impl Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let len = buf.len().min(self.limit - self.data.len());
+        self.data.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn buffers_until_capacity() {
+    let mut writer = BufWriter::with_capacity(4, Limited { data: Vec::new(), limit: 64 });
+    assert_eq!(writer.capacity(), 4);
+
+    writer.write_all(b"ab").unwrap();
+    assert!(writer.get_ref().data.is_empty());
+    writer.write_all(b"cdefgh").unwrap();
+    writer.write_all(b"ij").unwrap();
+    writer.flush().unwrap();
+
+    assert_eq!(writer.get_ref().data, b"abcdefghij");
+    assert!(writer.buffer().is_empty());
+}
+
+#[test]
+fn capacity_is_what_was_asked_for() {
+    // Vec::with_capacity may round up; the writer must not
+    let writer = BufWriter::with_capacity(5, Limited { data: Vec::new(), limit: 64 });
+    assert_eq!(writer.capacity(), 5);
+
+    assert_eq!(BufWriter::with_capacity(0, Limited { data: Vec::new(), limit: 64 }).capacity(), 1);
+}
+
+#[test]
+fn stalled_writer_reports_write_zero() {
+    let mut writer = BufWriter::with_capacity(8, Limited { data: Vec::new(), limit: 3 });
+
+    assert_eq!(writer.write(b"abcde"), Ok(5));
+    let error = writer.flush().unwrap_err();
+    assert_eq!(error, BufWriteError::WriteZero);
+    assert_eq!(error.kind(), ErrorKind::WriteZero);
+
+    // What the inner writer didn't take stays buffered
+    assert_eq!(writer.get_ref().data, b"abc");
+    assert_eq!(writer.buffer(), b"de");
+
+    writer.get_mut().limit = 64;
+    writer.flush().unwrap();
+    assert_eq!(writer.get_ref().data, b"abcde");
+
+    // Large writes skip the buffer, but still never report Ok(0)
+    writer.get_mut().limit = 5;
+    assert_eq!(writer.write(b"0123456789"), Err(BufWriteError::WriteZero));
+
+    let mut writer = BufWriter::with_capacity(8, Limited { data: Vec::new(), limit: 0 });
+    writer.write_all(b"x").unwrap();
+    let Err((error, writer)) = writer.into_inner() else { panic!("into_inner succeeded") };
+    assert_eq!(error, BufWriteError::WriteZero);
+    assert_eq!(writer.buffer(), b"x");
+}
@@ -0,0 +1,180 @@
+//! `XboxPath` parsing, normalization, FATX validation and comparison.
+
+use nxdk_rs::path::{Component, XboxPath, XboxPathBuf, MAX_NAME_LENGTH, MAX_PATH_LENGTH};
+use nxdk_rs::utils::error::PlatformError;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+fn hash(path: &XboxPath) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn components_skip_repeated_separators() {
+    let components: Vec<_> = XboxPath::new("E:\\a//.\\..\\b\\").components().collect();
+
+    assert_eq!(
+        components,
+        [
+            Component::Prefix("E:"),
+            Component::RootDir,
+            Component::Normal("a"),
+            Component::CurDir,
+            Component::ParentDir,
+            Component::Normal("b"),
+        ]
+    );
+
+    let components: Vec<_> = XboxPath::new("\\??\\D:\\default.xbe").components().collect();
+    assert_eq!(components, [Component::Prefix("\\??\\D:"), Component::RootDir, Component::Normal("default.xbe")]);
+
+    let components: Vec<_> = XboxPath::new("saves/slot1").components().collect();
+    assert_eq!(components, [Component::Normal("saves"), Component::Normal("slot1")]);
+}
+
+#[test]
+fn prefixes_and_roots() {
+    assert_eq!(XboxPath::new("E:\\UDATA").drive_letter(), Some('E'));
+    assert_eq!(XboxPath::new("\\??\\D:\\x").drive_letter(), Some('D'));
+    assert_eq!(XboxPath::new("\\Device\\CdRom0").drive_letter(), None);
+
+    assert!(XboxPath::new("E:\\a").is_absolute());
+    assert!(XboxPath::new("\\Device\\CdRom0").is_absolute());
+    assert!(!XboxPath::new("E:a").is_absolute());
+    assert!(!XboxPath::new("a\\b").is_absolute());
+}
+
+#[test]
+fn parent_and_file_name() {
+    let path = XboxPath::new("E:\\UDATA/4d530004\\save.xbx");
+
+    assert_eq!(path.file_name(), Some("save.xbx"));
+    assert_eq!(path.file_stem(), Some("save"));
+    assert_eq!(path.extension(), Some("xbx"));
+    assert_eq!(path.parent().unwrap().as_str(), "E:\\UDATA/4d530004");
+
+    assert_eq!(XboxPath::new("E:\\foo").parent().unwrap().as_str(), "E:\\");
+    assert_eq!(XboxPath::new("E:\\foo\\\\").parent().unwrap().as_str(), "E:\\");
+    assert!(XboxPath::new("E:\\").parent().is_none());
+    assert!(XboxPath::new("E:").parent().is_none());
+    assert_eq!(XboxPath::new("foo").parent().unwrap().as_str(), "");
+
+    // Dot files have no extension
+    assert_eq!(XboxPath::new(".hidden").extension(), None);
+    assert_eq!(XboxPath::new(".hidden").file_stem(), Some(".hidden"));
+    assert_eq!(XboxPath::new("E:\\..").file_name(), None);
+}
+
+#[test]
+fn join_and_push() {
+    assert_eq!(XboxPath::new("E:\\").join("foo").join("bar.txt").as_str(), "E:\\foo\\bar.txt");
+    assert_eq!(XboxPath::new("E:\\a").join("b/c").as_str(), "E:\\a\\b\\c");
+
+    // Absolute paths and drives replace what's there
+    assert_eq!(XboxPath::new("E:\\a").join("D:\\b").as_str(), "D:\\b");
+    assert_eq!(XboxPath::new("E:\\a").join("\\Device\\CdRom0").as_str(), "\\Device\\CdRom0");
+
+    let mut path = XboxPathBuf::from("E:/a/b");
+    assert_eq!(path.as_str(), "E:\\a\\b");
+    assert!(path.pop());
+    assert_eq!(path.as_str(), "E:\\a");
+    assert!(path.pop());
+    assert_eq!(path.as_str(), "E:\\");
+    assert!(!path.pop());
+
+    assert_eq!(XboxPath::new("a.b.c").with_extension("z").as_str(), "a.b.z");
+    assert_eq!(XboxPath::new("E:\\save.xbx").with_extension("").as_str(), "E:\\save");
+
+    let mut path = XboxPathBuf::from("E:\\");
+    assert!(!path.set_extension("txt"));
+    path.set_file_name("name");
+    assert_eq!(path.as_str(), "E:\\name");
+}
+
+#[test]
+fn normalize_resolves_dots() {
+    assert_eq!(XboxPath::new("E:\\a\\.\\b\\..\\c//d").normalize().as_str(), "E:\\a\\c\\d");
+    assert_eq!(XboxPath::new("E:/a/b/").normalize().as_str(), "E:\\a\\b");
+
+    // Can't go above the root, but relative paths keep their leading `..`
+    assert_eq!(XboxPath::new("E:\\..\\..\\a").normalize().as_str(), "E:\\a");
+    assert_eq!(XboxPath::new("..\\a\\..\\..\\b").normalize().as_str(), "..\\..\\b");
+    assert_eq!(XboxPath::new("\\??\\E:\\a\\..").normalize().as_str(), "\\??\\E:\\");
+}
+
+#[test]
+fn validate_fatx_rules() {
+    assert!(XboxPath::new("E:\\games\\halo").validate().is_ok());
+    assert!(XboxPath::new("\\??\\E:\\x").validate().is_ok());
+
+    let longest = "a".repeat(MAX_NAME_LENGTH);
+    assert!(XboxPath::new(&format!("E:\\{}", longest)).validate().is_ok());
+    assert_eq!(XboxPath::new(&format!("E:\\{}a", longest)).validate(), Err(PlatformError::NameTooLong));
+
+    assert_eq!(XboxPath::new("E:\\ga?mes").validate(), Err(PlatformError::InvalidCharacter('?')));
+    assert_eq!(XboxPath::new("E:\\a|b").validate(), Err(PlatformError::InvalidCharacter('|')));
+    assert_eq!(XboxPath::new("E:\\caf\u{e9}").validate(), Err(PlatformError::InvalidCharacter('\u{e9}')));
+    assert_eq!(XboxPath::new("E:\\a\tb").validate(), Err(PlatformError::InvalidCharacter('\t')));
+
+    let mut long_path = format!("E:{}\\abcd", "\\abcdefgh".repeat(28));
+    assert_eq!(long_path.len(), MAX_PATH_LENGTH);
+    assert!(XboxPath::new(&long_path).validate().is_ok());
+
+    long_path.push('e');
+    assert_eq!(XboxPath::new(&long_path).validate(), Err(PlatformError::PathTooLong));
+}
+
+#[test]
+fn c_paths_are_nul_terminated() {
+    let c_path = XboxPath::new("E:/a/b").to_c_path().unwrap();
+    assert_eq!(&c_path[..7], b"E:\\a\\b\0");
+
+    assert_eq!(XboxPath::new("").to_c_path().unwrap()[0], 0);
+
+    let longest = "a".repeat(MAX_PATH_LENGTH);
+    assert_eq!(XboxPath::new(&longest).to_c_path().unwrap()[MAX_PATH_LENGTH], 0);
+    assert_eq!(XboxPath::new(&format!("{}a", longest)).to_c_path(), Err(PlatformError::PathTooLong));
+    assert_eq!(XboxPath::new("E:\\a\0b").to_c_path(), Err(PlatformError::InvalidCharacter('\0')));
+}
+
+#[test]
+fn comparison_ignores_case_and_separators() {
+    let equal = ["E:\\UDATA\\a", "E:/udata/A", "e:\\UData\\\\a\\", "\\??\\E:\\UDATA\\a"];
+
+    for a in equal {
+        for b in equal {
+            assert_eq!(XboxPath::new(a), XboxPath::new(b), "{} == {}", a, b);
+            assert_eq!(hash(XboxPath::new(a)), hash(XboxPath::new(b)), "{} == {}", a, b);
+            assert_eq!(XboxPathBuf::from(a), XboxPathBuf::from(b), "{} == {}", a, b);
+        }
+    }
+
+    assert_ne!(XboxPath::new("E:\\a"), XboxPath::new("F:\\a"));
+    assert_ne!(XboxPath::new("E:\\a"), XboxPath::new("E:a"));
+    assert_ne!(XboxPath::new("E:\\ab"), XboxPath::new("E:\\a\\b"));
+    assert_ne!(hash(XboxPath::new("E:\\ab")), hash(XboxPath::new("E:\\a\\b")));
+
+    // Dots aren't resolved
+    assert_ne!(XboxPath::new("E:\\a\\..\\b"), XboxPath::new("E:\\b"));
+    assert_eq!(XboxPath::new("E:\\a\\..\\b").normalize(), XboxPathBuf::from("e:/B"));
+
+    let mut sorted = vec![XboxPathBuf::from("E:\\b"), XboxPathBuf::from("e:\\A\\z"), XboxPathBuf::from("E:\\a")];
+    sorted.sort();
+    assert_eq!(sorted, [XboxPathBuf::from("E:\\a"), XboxPathBuf::from("E:\\a\\z"), XboxPathBuf::from("E:\\b")]);
+}
+
+#[test]
+fn starts_with_compares_components() {
+    let path = XboxPath::new("E:\\Games\\Halo\\default.xbe");
+
+    assert!(path.starts_with("E:\\"));
+    assert!(path.starts_with("e:/games"));
+    assert!(path.starts_with("\\??\\E:\\Games\\Halo\\"));
+    assert!(path.starts_with(path));
+
+    assert!(!path.starts_with("E:\\Game"));
+    assert!(!path.starts_with("D:\\Games"));
+    assert!(!XboxPath::new("E:\\Games").starts_with(path));
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: MIT
+//! Converts a PE32 executable linked by nxdk into an XBE, in place of cxbe.
+//!
+//! Usage: xbe-build <exe> <xbe> [options]
+//!
+//! Options:
+//!   --title <name>          Title name shown by the dashboard
+//!   --title-id <hex>        Title ID, such as 4E580001
+//!   --version <number>      Certificate version
+//!   --media <flags>         Allowed media, as flag names joined with `|` or a hex mask
+//!   --region <flags>        Game region, as flag names joined with `|` or a hex mask
+//!   --title-image <xpr>     Title image, inserted as $$XTIMAGE
+//!   --save-image <xpr>      Default save image, inserted as $$XSIMAGE
+//!   --debug                 Encode addresses with the debug kit keys
+//!
+//! Flag names are those of `AllowedMedia` and `GameRegion`, such as `HardDisk|DvdX2`.
+//! The output is parsed back before it's written, so a broken XBE is never left behind.
+
+use nxdk_formats::xbe::{AllowedMedia, GameRegion, Xbe, XbeBuilder, XbeKind};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: xbe-build <exe> <xbe> [--title <name>] [--title-id <hex>] [--version <number>] \
+                     [--media <flags>] [--region <flags>] [--title-image <xpr>] [--save-image <xpr>] [--debug]";
+
+fn parse_hex(value: &str) -> Result<u32, String> {
+    let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+    u32::from_str_radix(digits, 16).map_err(|_| format!("{}: not a hex number", value))
+}
+
+/// Parses flags given by name, joined with `|`, or as a hex mask.
+fn parse_flags(value: &str, from_name: impl Fn(&str) -> Option<u32>) -> Result<u32, String> {
+    if value.starts_with("0x") || value.starts_with("0X") {
+        return parse_hex(value);
+    }
+
+    value.split('|').map(str::trim).try_fold(0, |bits, name| {
+        from_name(name)
+            .map(|flag| bits | flag)
+            .ok_or_else(|| format!("{}: unknown flag", name))
+    })
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|error| format!("{}: {}", path, error))
+}
+
+fn run() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    let mut kind = XbeKind::Retail;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--debug" => kind = XbeKind::Debug,
+            option if option.starts_with("--") => {
+                let value = args.next().ok_or_else(|| format!("{} needs a value", option))?;
+                options.push((arg, value));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let [exe, xbe] = positional.as_slice() else {
+        return Err(USAGE.into());
+    };
+
+    let pe = read(exe)?;
+    let mut builder = XbeBuilder::from_pe(&pe).map_err(|error| format!("{}: {}", exe, error))?;
+    builder.kind(kind).debug_path(exe);
+
+    for (option, value) in options {
+        match option.as_str() {
+            "--title" => {
+                builder.title_name(&value);
+            }
+            "--title-id" => {
+                builder.title_id(parse_hex(&value)?);
+            }
+            "--version" => {
+                builder.version(value.parse().map_err(|_| format!("{}: not a number", value))?);
+            }
+            "--media" => {
+                let bits = parse_flags(&value, |name| AllowedMedia::from_name(name).map(|flag| flag.bits()))?;
+                builder.allowed_media(AllowedMedia::from_bits_retain(bits));
+            }
+            "--region" => {
+                let bits = parse_flags(&value, |name| GameRegion::from_name(name).map(|flag| flag.bits()))?;
+                builder.game_region(GameRegion::from_bits_retain(bits));
+            }
+            "--title-image" => {
+                builder.title_image(read(&value)?);
+            }
+            "--save-image" => {
+                builder.save_image(read(&value)?);
+            }
+            _ => return Err(format!("unknown option {}\n{}", option, USAGE)),
+        }
+    }
+
+    let image = builder.build().map_err(|error| format!("{}: {}", exe, error))?;
+
+    Xbe::parse(&image).map_err(|error| format!("{}: built an unreadable XBE: {}", xbe, error))?;
+
+    std::fs::write(xbe, image).map_err(|error| format!("{}: {}", xbe, error))
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("xbe-build: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: MIT
+//! Prints the headers of an XBE, and exports its images as BMP, or PNG when
+//! the output path ends in `.png`.
+//!
+//! Usage: xbe-info <xbe> [--title-image <image>] [--save-image <image>] [--logo <image>]
+
+use nxdk_formats::image::RgbaImage;
+use nxdk_formats::xbe::{Xbe, XbeError};
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: xbe-info <xbe> [--title-image <image>] [--save-image <image>] [--logo <image>]";
+
+fn print(xbe: &Xbe) {
+    let certificate = &xbe.certificate;
+
+    println!("Title ID:       {:08X}", certificate.title_id);
+    println!("Title name:     {}", certificate.title_name());
+    println!("Version:        {}", certificate.version);
+    println!("Allowed media:  {:?}", certificate.allowed_media);
+    println!("Region:         {:?}", certificate.game_region);
+    println!("Kind:           {:?}", xbe.kind);
+    println!("Entry point:    {:#010x}", xbe.entry_point);
+    println!("Kernel thunk:   {:#010x}", xbe.kernel_thunk_address);
+    println!("Init flags:     {:?}", xbe.header.init_flags);
+
+    if let Some(tls) = &xbe.tls {
+        println!("TLS index:      {:#010x}", tls.tls_index_address);
+    }
+
+    if !xbe.debug_pathname.is_empty() {
+        println!("Debug path:     {}", xbe.debug_pathname);
+    }
+
+    println!("Sections:");
+
+    for section in &xbe.sections {
+        println!(
+            "  {:<10} {:#010x} {:#010x} raw {:#010x} {:#010x} {:?}",
+            section.name, section.virtual_address, section.virtual_size, section.raw_address, section.raw_size, section.flags
+        );
+    }
+
+    if !xbe.library_versions.is_empty() {
+        println!("Libraries:");
+    }
+
+    for library in &xbe.library_versions {
+        println!("  {:<8} {}.{}.{}.{}", library.name, library.major, library.minor, library.build, library.qfe_version());
+    }
+}
+
+fn export(image: Result<Option<RgbaImage>, XbeError>, what: &str, path: &str) -> Result<(), String> {
+    let image = image
+        .map_err(|error| format!("{}: {}", what, error))?
+        .ok_or_else(|| format!("the XBE has no {}", what))?;
+
+    let is_png = Path::new(path).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+
+    let bytes = match is_png {
+        true => to_png(&image).map_err(|error| format!("{}: {}", path, error))?,
+        false => image.to_bmp(),
+    };
+
+    std::fs::write(path, bytes).map_err(|error| format!("{}: {}", path, error))
+}
+
+/// Encodes an image as an 8 bit per channel RGBA PNG.
+fn to_png(image: &RgbaImage) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, image.width, image.height);
+
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&image.pixels)?;
+
+    Ok(png)
+}
+
+fn run() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    let mut positional = Vec::new();
+    let mut exports = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--title-image" | "--save-image" | "--logo" => {
+                let path = args.next().ok_or_else(|| format!("{} needs a path", arg))?;
+                exports.push((arg, path));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let [path] = positional.as_slice() else {
+        return Err(USAGE.into());
+    };
+
+    let image = std::fs::read(path).map_err(|error| format!("{}: {}", path, error))?;
+    let xbe = Xbe::parse(&image).map_err(|error| format!("{}: {}", path, error))?;
+
+    print(&xbe);
+
+    for (option, out) in exports {
+        match option.as_str() {
+            "--title-image" => export(xbe.title_image(&image), "title image", &out)?,
+            "--save-image" => export(xbe.save_image(&image), "save image", &out)?,
+            _ => export(xbe.logo(), "logo", &out)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("xbe-info: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MIT
+//! Builds an XDVDFS (XISO) image from a directory.
+//!
+//! Usage: xdvdfs-build <directory> <image> [--first <path>]...
+//!
+//! `--first` places a file ahead of the others, such as `default.xbe`, and can be
+//! given several times.
+
+use nxdk_formats::xdvdfs::{BuildError, XdvdfsBuilder};
+use nxdk_tools::io::{LazyFile, StdIo};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::process::ExitCode;
+
+type Builder = XdvdfsBuilder<LazyFile>;
+
+/// Adds the contents of `directory` to the image, under `relative`.
+fn add_tree(builder: &mut Builder, directory: &Path, relative: &str) -> Result<(), String> {
+    let mut entries = std::fs::read_dir(directory)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|error| format!("{}: {}", directory.display(), error))?;
+
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|name| format!("{}: name is not valid UTF-8", name.to_string_lossy()))?;
+
+        let path = match relative.is_empty() {
+            true => name,
+            false => format!("{}\\{}", relative, name),
+        };
+
+        let metadata = entry
+            .metadata()
+            .map_err(|error| format!("{}: {}", entry.path().display(), error))?;
+
+        let result = if metadata.is_dir() {
+            builder.add_dir::<std::io::Error>(&path)
+        } else {
+            builder.add_file::<std::io::Error>(&path, metadata.len(), LazyFile::new(entry.path()))
+        };
+
+        result.map_err(|error| format!("{}: {}", path, error))?;
+
+        if metadata.is_dir() {
+            add_tree(builder, &entry.path(), &path)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    let mut positional = Vec::new();
+    let mut first = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--first" => first.push(args.next().ok_or("--first needs a path")?),
+            _ => positional.push(arg),
+        }
+    }
+
+    let [directory, image] = positional.as_slice() else {
+        return Err("usage: xdvdfs-build <directory> <image> [--first <path>]...".into());
+    };
+
+    let mut builder = Builder::new();
+    add_tree(&mut builder, Path::new(directory), "")?;
+
+    for path in &first {
+        builder.prioritize(path);
+    }
+
+    let out = File::create(image).map_err(|error| format!("{}: {}", image, error))?;
+    let mut out = StdIo(BufWriter::new(out));
+
+    builder.build(&mut out).map_err(|error: BuildError<_>| format!("{}: {}", image, error))
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("xdvdfs-build: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
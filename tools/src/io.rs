@@ -0,0 +1,69 @@
+use embedded_io::{ErrorType, Read, Seek, SeekFrom, Write};
+use std::fs::File;
+use std::path::PathBuf;
+
+/// Wraps a `std::io` type to implement the `embedded_io` traits.
+#[derive(Debug)]
+pub struct StdIo<T>(pub T);
+
+impl<T> ErrorType for StdIo<T> {
+    type Error = std::io::Error;
+}
+
+impl<T: std::io::Read> Read for StdIo<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.0.read(buf)
+    }
+}
+
+impl<T: std::io::Write> Write for StdIo<T> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.0.flush()
+    }
+}
+
+impl<T: std::io::Seek> Seek for StdIo<T> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Self::Error> {
+        self.0.seek(match pos {
+            SeekFrom::Start(offset) => std::io::SeekFrom::Start(offset),
+            SeekFrom::End(offset) => std::io::SeekFrom::End(offset),
+            SeekFrom::Current(offset) => std::io::SeekFrom::Current(offset),
+        })
+    }
+}
+
+/// A file that is only opened once it's first read, so large trees don't
+/// hold every file open at once.
+#[derive(Debug)]
+pub struct LazyFile {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl LazyFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file: None,
+        }
+    }
+}
+
+impl ErrorType for LazyFile {
+    type Error = std::io::Error;
+}
+
+impl Read for LazyFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(File::open(&self.path)?),
+        };
+
+        std::io::Read::read(file, buf)
+    }
+}
@@ -0,0 +1,4 @@
+// SPDX-License-Identifier: MIT
+//! Host tools built on nxdk-formats.
+
+pub mod io;